@@ -1,8 +1,25 @@
-use crate::geo::interpolation;
-use crate::models::{BansheeState, GpsPoint};
-use chrono::{DateTime, Utc};
+use crate::banshee::{
+    build_composite_track, build_race_report, project_finish, target_finish_plan, AiPacer,
+    BansheeEvent, BansheeEventKind, BansheeSession, Course, CourseGeometry, DistanceComparison,
+    EventConfig, FinishProjection, LeaderboardEntry, Opponent, RaceBanshee, RaceEventTracker,
+    RaceSession, RaceStandings, Registry, REPORT_SPLIT_DISTANCE_M,
+};
+use crate::db::RunQuery;
+use crate::frb_generated::StreamSink;
+use crate::geo::elevation::{self, GradeSegment, GRADE_SAMPLE_SPACING_M};
+use crate::geo::{IndexedTrack, RouteMatch, RouteMatcher};
+use crate::import::{parse_track, TrackFormat};
+use crate::models::{
+    Banshee, BansheeState, BansheeType, CompositeSplit, GapSample, GhostSource, GhostTrack,
+    GhostTrackSummary, GpsPoint, LeadChange, PacePlan, PaceSegment, RaceReport, ReplayHandicap,
+    Run, SavedBanshee, SplitComparison,
+};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 
-use super::run_api::get_run;
+use super::route_api::route_course;
+use super::run_api::get_db;
 
 /// DTO for banshee state returned to Flutter
 pub struct BansheeStateDto {
@@ -25,100 +42,1309 @@ impl From<BansheeState> for BansheeStateDto {
     }
 }
 
-/// Get banshee position for a recorded run at a given elapsed time
+/// DTO for a distance-aligned comparison returned to Flutter
+pub struct DistanceComparisonDto {
+    pub distance_m: f64,
+    pub runner_time_ms: i64,
+    pub banshee_time_ms: i64,
+    /// Runner time minus banshee time at this distance (positive = banshee ahead)
+    pub time_delta_ms: i64,
+}
+
+impl From<DistanceComparison> for DistanceComparisonDto {
+    fn from(comparison: DistanceComparison) -> Self {
+        Self {
+            distance_m: comparison.distance_m,
+            runner_time_ms: comparison.runner_time_ms,
+            banshee_time_ms: comparison.banshee_time_ms,
+            time_delta_ms: comparison.time_delta_ms,
+        }
+    }
+}
+
+/// Get banshee position for a recorded run at a given elapsed time.
+/// Loads the run on every call; during a race, open a session with
+/// `open_banshee_session` and poll `get_banshee_session_position` instead.
 pub fn get_recorded_banshee_position(
     run_id: String,
     elapsed_ms: i64,
 ) -> Result<BansheeStateDto, String> {
-    let run = get_run(run_id)?.ok_or_else(|| "Run not found".to_string())?;
+    let session = load_session(run_id)?;
+    Ok(session.state_at(elapsed_ms).into())
+}
 
-    let points: Vec<GpsPoint> = run
-        .points
-        .into_iter()
-        .map(|p| {
-            let timestamp = DateTime::from_timestamp_millis(p.timestamp_ms)
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(Utc::now);
-
-            GpsPoint {
-                lat: p.lat,
-                lon: p.lon,
-                altitude: p.altitude,
-                timestamp,
-                accuracy: p.accuracy,
-                speed: p.speed,
-            }
-        })
-        .collect();
+/// Load a recorded run, or failing that a ghost track, into a banshee session
+fn load_session(run_id: String) -> Result<BansheeSession, String> {
+    let db = get_db()?;
+    let points = match db.get_run(&run_id).map_err(|e| e.to_string())? {
+        Some(run) => run.points,
+        None => {
+            db.get_ghost_track(&run_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "Run not found".to_string())?
+                .points
+        }
+    };
+
+    BansheeSession::new(run_id, points).map_err(|e| e.to_string())
+}
+
+/// DTO for a recorded banshee's replay handicap
+pub struct ReplayHandicapDto {
+    /// How long after the runner the banshee sets off (negative = earlier)
+    pub start_offset_ms: i64,
+    /// Replay speed relative to the recording (1.02 = 2% faster)
+    pub speed_factor: f64,
+}
+
+impl From<ReplayHandicapDto> for ReplayHandicap {
+    fn from(dto: ReplayHandicapDto) -> Self {
+        Self {
+            start_offset_ms: dto.start_offset_ms,
+            speed_factor: dto.speed_factor,
+        }
+    }
+}
 
-    if points.is_empty() {
-        return Err("Run has no GPS points".to_string());
+impl From<ReplayHandicap> for ReplayHandicapDto {
+    fn from(handicap: ReplayHandicap) -> Self {
+        Self {
+            start_offset_ms: handicap.start_offset_ms,
+            speed_factor: handicap.speed_factor,
+        }
     }
+}
 
-    let position = interpolation::interpolate_position(&points, elapsed_ms)
-        .ok_or_else(|| "Could not interpolate position".to_string())?;
+fn sessions() -> &'static Registry<BansheeSession> {
+    static SESSIONS: OnceLock<Registry<BansheeSession>> = OnceLock::new();
+    SESSIONS.get_or_init(Registry::new)
+}
 
-    let distance = interpolation::distance_at_time(&points, elapsed_ms);
+/// Load a recorded run once for a race and return a session ID for
+/// `get_banshee_session_position`. Close it with `close_banshee_session`.
+pub fn open_banshee_session(run_id: String) -> Result<String, String> {
+    let session = load_session(run_id)?;
+    Ok(sessions().insert(session))
+}
 
-    Ok(BansheeStateDto {
-        lat: position.lat,
-        lon: position.lon,
-        distance_meters: distance,
-        time_delta_ms: 0,
-        distance_delta_meters: 0.0,
-    })
+/// Like `open_banshee_session`, but replay the run with a time and speed
+/// handicap (e.g. a speed factor of 1.01 to beat it by 1%). Positions and
+/// comparisons from the session have the handicap applied.
+pub fn open_handicapped_banshee_session(
+    run_id: String,
+    handicap: ReplayHandicapDto,
+) -> Result<String, String> {
+    let session = load_session(run_id)?
+        .with_handicap(handicap.into())
+        .map_err(|e| e.to_string())?;
+    Ok(sessions().insert(session))
+}
+
+/// Get the banshee position for an open session at a given elapsed time
+#[flutter_rust_bridge::frb(sync)]
+pub fn get_banshee_session_position(
+    session_id: String,
+    elapsed_ms: i64,
+) -> Result<BansheeStateDto, String> {
+    let session = get_session(&session_id)?;
+    Ok(session.state_at(elapsed_ms).into())
+}
+
+/// Close a banshee session, returning false if it wasn't open
+#[flutter_rust_bridge::frb(sync)]
+pub fn close_banshee_session(session_id: String) -> bool {
+    sessions().remove(&session_id).is_some()
+}
+
+/// Compare the runner with an open session's banshee at the same distance
+/// along the course (e.g. "12s up on last week at this kilometre").
+/// Returns None once the runner has gone further than the banshee's run.
+#[flutter_rust_bridge::frb(sync)]
+pub fn compare_banshee_at_distance(
+    session_id: String,
+    runner_distance_m: f64,
+    runner_elapsed_ms: i64,
+) -> Result<Option<DistanceComparisonDto>, String> {
+    let session = get_session(&session_id)?;
+    Ok(session
+        .compare_at_distance(runner_distance_m, runner_elapsed_ms)
+        .map(|c| c.into()))
 }
 
-/// Get AI pacer position given start point, target pace, and elapsed time
-/// The pacer follows the provided route if given, otherwise moves in a straight line
+fn get_session(session_id: &str) -> Result<Arc<BansheeSession>, String> {
+    sessions()
+        .get(session_id)
+        .ok_or_else(|| "Banshee session not found".to_string())
+}
+
+/// Where an AI pacer runs
+pub enum PacerCourseDto {
+    /// Along a saved route from its first point, stopping at its end
+    Route { route_id: String },
+    /// Without a planned route, on a course opened with `open_pacer_course`
+    Open { course_id: String },
+}
+
+/// Get AI pacer position given target pace and elapsed time.
+/// With a pace plan (e.g. negative splits) the pacer follows it instead of
+/// the constant target pace.
+/// On a saved route the pacer stops at its end; with `adjust_for_terrain`
+/// its pace is treated as flat effort, so it slows on the route's climbs.
+/// Open courses have no altitude, so the terrain adjustment doesn't apply.
+#[flutter_rust_bridge::frb(sync)]
 pub fn get_ai_pacer_position(
-    start_lat: f64,
-    start_lon: f64,
     target_pace_sec_per_km: f64,
+    pace_plan: Option<PacePlanDto>,
     elapsed_ms: i64,
-    route: Option<Vec<(f64, f64)>>,
+    course: PacerCourseDto,
+    adjust_for_terrain: bool,
 ) -> Result<BansheeStateDto, String> {
-    if target_pace_sec_per_km <= 0.0 {
-        return Err("Invalid pace".to_string());
+    let pacer = ai_pacer(target_pace_sec_per_km, pace_plan)?;
+
+    let route_id = match course {
+        PacerCourseDto::Route { route_id } => route_id,
+        PacerCourseDto::Open { course_id } => {
+            let distance_meters = pacer.distance_at(elapsed_ms);
+            let (lat, lon) = get_pacer_course(&course_id)?
+                .lock()
+                .unwrap()
+                .position_at_distance(distance_meters);
+            return Ok(BansheeState::new(lat, lon, distance_meters).into());
+        }
+    };
+
+    let route = route_course(&route_id)?;
+    let pacer = if adjust_for_terrain {
+        pacer.adjusted_for_terrain(&route.grades)
+    } else {
+        pacer
+    };
+
+    // Calculate distance the pacer should have covered, up to the end of the route
+    let distance_meters = pacer
+        .distance_at(elapsed_ms)
+        .min(route.track.total_distance());
+    let point = route
+        .track
+        .position_at_distance(distance_meters)
+        .ok_or("Route is empty")?;
+
+    Ok(BansheeState::new(point.lat, point.lon, distance_meters).into())
+}
+
+/// A pacer following the plan if there is one, otherwise the target pace
+fn ai_pacer(
+    target_pace_sec_per_km: f64,
+    pace_plan: Option<PacePlanDto>,
+) -> Result<AiPacer, String> {
+    match pace_plan {
+        Some(plan) => AiPacer::with_plan(plan.into()),
+        None => AiPacer::new(target_pace_sec_per_km),
     }
+    .map_err(|e| e.to_string())
+}
 
-    // Calculate distance the pacer should have covered
-    let elapsed_sec = elapsed_ms as f64 / 1000.0;
-    let speed_m_per_sec = 1000.0 / target_pace_sec_per_km;
-    let distance_meters = speed_m_per_sec * elapsed_sec;
+/// How a pacer moves on the map when there's no planned route
+pub enum PacerGeometryDto {
+    /// Stay at the start point
+    Stationary,
+    /// Run in a straight line along the runner's initial bearing
+    InitialBearing,
+    /// Run out along the runner's initial bearing and turn back at `turnaround_m`
+    OutAndBack { turnaround_m: f64 },
+    /// Follow the runner's own path so far
+    MirrorRunner,
+}
 
-    let (lat, lon) = if let Some(route_points) = route {
-        if route_points.is_empty() {
-            (start_lat, start_lon)
-        } else {
-            // Convert route to GpsPoints for interpolation
-            let now = Utc::now();
-            let gps_points: Vec<GpsPoint> = route_points
+impl From<PacerGeometryDto> for CourseGeometry {
+    fn from(dto: PacerGeometryDto) -> Self {
+        match dto {
+            PacerGeometryDto::Stationary => CourseGeometry::Stationary,
+            PacerGeometryDto::InitialBearing => CourseGeometry::InitialBearing,
+            PacerGeometryDto::OutAndBack { turnaround_m } => {
+                CourseGeometry::OutAndBack { turnaround_m }
+            }
+            PacerGeometryDto::MirrorRunner => CourseGeometry::MirrorRunner,
+        }
+    }
+}
+
+fn pacer_courses() -> &'static Registry<Mutex<Course>> {
+    static COURSES: OnceLock<Registry<Mutex<Course>>> = OnceLock::new();
+    COURSES.get_or_init(Registry::new)
+}
+
+/// Open a course for pacers without a planned route, starting at the
+/// runner's start point. Pass each of the runner's GPS fixes to
+/// `update_pacer_course`: bearing-based pacers wait at the start until the
+/// runner has moved far enough to give a bearing. Returns a course ID for
+/// `get_ai_pacer_position` and `start_banshee_race`.
+pub fn open_pacer_course(
+    start_lat: f64,
+    start_lon: f64,
+    geometry: PacerGeometryDto,
+) -> Result<String, String> {
+    let course =
+        Course::from_geometry(start_lat, start_lon, geometry.into()).map_err(|e| e.to_string())?;
+    Ok(pacer_courses().insert(Mutex::new(course)))
+}
+
+/// Feed the runner's latest GPS fix to an open pacer course
+#[flutter_rust_bridge::frb(sync)]
+pub fn update_pacer_course(course_id: String, lat: f64, lon: f64) -> Result<(), String> {
+    get_pacer_course(&course_id)?
+        .lock()
+        .unwrap()
+        .add_runner_position(lat, lon);
+    Ok(())
+}
+
+/// Close a pacer course, returning false if it wasn't open. Races already
+/// started on it keep the course as it was.
+#[flutter_rust_bridge::frb(sync)]
+pub fn close_pacer_course(course_id: String) -> bool {
+    pacer_courses().remove(&course_id).is_some()
+}
+
+fn get_pacer_course(course_id: &str) -> Result<Arc<Mutex<Course>>, String> {
+    pacer_courses()
+        .get(course_id)
+        .ok_or_else(|| "Pacer course not found".to_string())
+}
+
+/// DTO for one leg of a pace plan from Flutter
+pub struct PaceSegmentDto {
+    pub until_distance_m: f64,
+    pub pace_sec_per_km: f64,
+}
+
+/// DTO for an AI pacer's pace plan from Flutter
+pub enum PacePlanDto {
+    /// Constant pace per leg; the last pace continues past the last leg
+    Segments { segments: Vec<PaceSegmentDto> },
+    /// Pace changes linearly from start to finish pace over the distance
+    Progression {
+        start_pace_sec_per_km: f64,
+        finish_pace_sec_per_km: f64,
+        distance_m: f64,
+    },
+}
+
+impl From<PacePlanDto> for PacePlan {
+    fn from(dto: PacePlanDto) -> Self {
+        match dto {
+            PacePlanDto::Segments { segments } => PacePlan::Segments(
+                segments
+                    .into_iter()
+                    .map(|s| PaceSegment {
+                        until_distance_m: s.until_distance_m,
+                        pace_sec_per_km: s.pace_sec_per_km,
+                    })
+                    .collect(),
+            ),
+            PacePlanDto::Progression {
+                start_pace_sec_per_km,
+                finish_pace_sec_per_km,
+                distance_m,
+            } => PacePlan::Progression {
+                start_pace_sec_per_km,
+                finish_pace_sec_per_km,
+                distance_m,
+            },
+        }
+    }
+}
+
+impl From<PacePlan> for PacePlanDto {
+    fn from(plan: PacePlan) -> Self {
+        match plan {
+            PacePlan::Segments(segments) => PacePlanDto::Segments {
+                segments: segments
+                    .into_iter()
+                    .map(|s| PaceSegmentDto {
+                        until_distance_m: s.until_distance_m,
+                        pace_sec_per_km: s.pace_sec_per_km,
+                    })
+                    .collect(),
+            },
+            PacePlan::Progression {
+                start_pace_sec_per_km,
+                finish_pace_sec_per_km,
+                distance_m,
+            } => PacePlanDto::Progression {
+                start_pace_sec_per_km,
+                finish_pace_sec_per_km,
+                distance_m,
+            },
+        }
+    }
+}
+
+/// DTO for a route point with optional altitude from Flutter
+pub struct RoutePointDto {
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude: Option<f64>,
+}
+
+/// Convert a route with altitude sent from Flutter into an indexed track
+fn route_points_to_track(route: Vec<RoutePointDto>) -> IndexedTrack {
+    let now = Utc::now();
+    IndexedTrack::new(
+        route
+            .into_iter()
+            .map(|p| GpsPoint {
+                altitude: p.altitude,
+                ..GpsPoint::new(p.lat, p.lon, now)
+            })
+            .collect(),
+    )
+}
+
+/// DTO for a finish-time projection returned to Flutter
+pub struct FinishProjectionDto {
+    pub projected_finish_ms: i64,
+    /// Projected finish minus target (positive = slower than target)
+    pub finish_delta_ms: i64,
+    /// Average pace needed for the rest of the run to hit the target
+    pub required_pace_sec_per_km: Option<f64>,
+}
+
+impl From<FinishProjection> for FinishProjectionDto {
+    fn from(projection: FinishProjection) -> Self {
+        Self {
+            projected_finish_ms: projection.projected_finish_ms,
+            finish_delta_ms: projection.finish_delta_ms,
+            required_pace_sec_per_km: projection.required_pace_sec_per_km,
+        }
+    }
+}
+
+/// Build a pacer plan for "finish distance D in time T". If a route with
+/// altitude is given, the pace varies with the grade so the effort stays even.
+/// Pass the result to `get_ai_pacer_position` or a race.
+#[flutter_rust_bridge::frb(sync)]
+pub fn create_target_finish_plan(
+    distance_m: f64,
+    target_time_ms: i64,
+    route: Option<Vec<RoutePointDto>>,
+) -> Result<PacePlanDto, String> {
+    let grades = route
+        .map(|r| elevation::grade_profile(&route_points_to_track(r), GRADE_SAMPLE_SPACING_M))
+        .unwrap_or_default();
+
+    target_finish_plan(distance_m, target_time_ms, &grades)
+        .map(|plan| plan.into())
+        .map_err(|e| e.to_string())
+}
+
+/// Project the runner's finish time against a target-finish plan
+#[flutter_rust_bridge::frb(sync)]
+pub fn project_target_finish(
+    pace_plan: PacePlanDto,
+    distance_m: f64,
+    target_time_ms: i64,
+    runner_distance_m: f64,
+    runner_elapsed_ms: i64,
+) -> Option<FinishProjectionDto> {
+    project_finish(
+        &pace_plan.into(),
+        distance_m,
+        target_time_ms,
+        runner_distance_m,
+        runner_elapsed_ms,
+    )
+    .map(|p| p.into())
+}
+
+/// DTO describing one banshee in a multi-banshee race. Set `run_id` for a
+/// recorded run, or `target_pace_sec_per_km` or `pace_plan` for an AI pacer,
+/// or `saved_banshee_id` to race one from the library (its result is then
+/// added to the library record by `finish_banshee_race`).
+/// Set `adjust_for_terrain` to have a pacer slow on the route's climbs (leave
+/// it off for target-finish plans built from the route, which already are).
+/// `handicap` applies to recorded runs, replacing a saved banshee's own.
+pub struct RaceBansheeDto {
+    pub name: String,
+    pub run_id: Option<String>,
+    pub target_pace_sec_per_km: Option<f64>,
+    pub pace_plan: Option<PacePlanDto>,
+    pub saved_banshee_id: Option<String>,
+    pub adjust_for_terrain: bool,
+    pub handicap: Option<ReplayHandicapDto>,
+}
+
+impl TryFrom<RaceBansheeDto> for Banshee {
+    type Error = String;
+
+    fn try_from(dto: RaceBansheeDto) -> Result<Self, Self::Error> {
+        let banshee = banshee_from_parts(
+            dto.name,
+            dto.run_id,
+            dto.target_pace_sec_per_km,
+            dto.pace_plan,
+        )?;
+        match dto.handicap {
+            Some(handicap) => with_handicap(banshee, handicap.into()),
+            None => Ok(banshee),
+        }
+    }
+}
+
+/// Build a banshee from exactly one of a run, a target pace or a pace plan
+fn banshee_from_parts(
+    name: String,
+    run_id: Option<String>,
+    target_pace_sec_per_km: Option<f64>,
+    pace_plan: Option<PacePlanDto>,
+) -> Result<Banshee, String> {
+    let banshee = match (run_id, target_pace_sec_per_km, pace_plan) {
+        (Some(run_id), None, None) => Banshee::from_run(run_id, name),
+        (None, Some(pace), None) => Banshee::ai_pacer(pace, name),
+        (None, None, Some(plan)) => Banshee::ai_pacer_with_plan(plan.into(), name),
+        _ => {
+            return Err(format!(
+                "Banshee '{name}' needs exactly one of a run, a target pace or a pace plan"
+            ))
+        }
+    };
+
+    // Catch invalid paces and plans before they are raced or saved
+    if banshee.is_ai_pacer() {
+        AiPacer::try_from(banshee.banshee_type.clone()).map_err(|e| e.to_string())?;
+    }
+    Ok(banshee)
+}
+
+/// Give a recorded banshee a replay handicap
+fn with_handicap(mut banshee: Banshee, new_handicap: ReplayHandicap) -> Result<Banshee, String> {
+    new_handicap.validate().map_err(|e| e.to_string())?;
+    match &mut banshee.banshee_type {
+        BansheeType::RecordedRun { handicap, .. } => *handicap = new_handicap,
+        BansheeType::AiPacer { .. } => {
+            return Err(format!(
+                "Banshee '{}' is an AI pacer; set its pace instead of a handicap",
+                banshee.name
+            ))
+        }
+    }
+    Ok(banshee)
+}
+
+/// DTO for a leaderboard row returned to Flutter
+pub struct LeaderboardEntryDto {
+    pub position: u32,
+    pub name: String,
+    pub is_runner: bool,
+    /// Index into the race's banshees (None for the runner)
+    pub banshee_index: Option<u32>,
+    pub distance_meters: f64,
+    /// Distance ahead of the runner (positive = ahead of the runner)
+    pub gap_to_runner_meters: f64,
+}
+
+impl From<LeaderboardEntry> for LeaderboardEntryDto {
+    fn from(entry: LeaderboardEntry) -> Self {
+        Self {
+            position: entry.position,
+            name: entry.name,
+            is_runner: entry.is_runner,
+            banshee_index: entry.banshee_index.map(|i| i as u32),
+            distance_meters: entry.distance_meters,
+            gap_to_runner_meters: entry.gap_to_runner_meters,
+        }
+    }
+}
+
+/// DTO for race standings returned to Flutter
+pub struct RaceStandingsDto {
+    /// One state per banshee, in the order they were given to `start_banshee_race`
+    pub banshees: Vec<BansheeStateDto>,
+    /// Runner and banshees, leader first
+    pub leaderboard: Vec<LeaderboardEntryDto>,
+}
+
+impl From<RaceStandings> for RaceStandingsDto {
+    fn from(standings: RaceStandings) -> Self {
+        Self {
+            banshees: standings.banshees.into_iter().map(|b| b.into()).collect(),
+            leaderboard: standings
+                .leaderboard
                 .into_iter()
-                .map(|(lat, lon)| GpsPoint::new(lat, lon, now))
-                .collect();
-
-            match interpolation::interpolate_position_at_distance(&gps_points, distance_meters) {
-                Some(point) => (point.lat, point.lon),
-                None => {
-                    // Beyond route, use last point
-                    let last = gps_points.last().unwrap();
-                    (last.lat, last.lon)
+                .map(|e| e.into())
+                .collect(),
+        }
+    }
+}
+
+fn races() -> &'static Registry<RaceSession> {
+    static RACES: OnceLock<Registry<RaceSession>> = OnceLock::new();
+    RACES.get_or_init(Registry::new)
+}
+
+/// Load the track or pacer behind a banshee, adjusting a pacer for the
+/// course's grades if given
+fn race_banshee(
+    banshee: Banshee,
+    grades: Option<&[GradeSegment]>,
+    saved_id: Option<String>,
+) -> Result<RaceBanshee, String> {
+    let opponent = match banshee.banshee_type {
+        BansheeType::RecordedRun { run_id, handicap } => Opponent::Recorded(
+            load_session(run_id)?
+                .with_handicap(handicap)
+                .map_err(|e| e.to_string())?,
+        ),
+        pacer @ BansheeType::AiPacer { .. } => {
+            let pacer = AiPacer::try_from(pacer).map_err(|e| e.to_string())?;
+            Opponent::Pacer(match grades {
+                Some(grades) => pacer.adjusted_for_terrain(grades),
+                None => pacer,
+            })
+        }
+    };
+
+    Ok(RaceBanshee {
+        name: banshee.name,
+        opponent,
+        saved_id,
+    })
+}
+
+/// Start a race against several banshees at once (recorded runs and AI pacers).
+/// Pacers follow the saved route or move on the open pacer course; keep
+/// feeding an open course with `update_pacer_course` during the race.
+/// Returns a race ID for `update_banshee_race`.
+pub fn start_banshee_race(
+    banshees: Vec<RaceBansheeDto>,
+    course: PacerCourseDto,
+) -> Result<String, String> {
+    match course {
+        PacerCourseDto::Route { route_id } => {
+            let route = route_course(&route_id)?;
+            let start = route.track.points().first().ok_or("Route is empty")?;
+            let course = Course::new(start.lat, start.lon, Some(route.track.clone()));
+            start_race(banshees, Arc::new(Mutex::new(course)), &route.grades)
+        }
+        PacerCourseDto::Open { course_id } => {
+            start_race(banshees, get_pacer_course(&course_id)?, &[])
+        }
+    }
+}
+
+fn start_race(
+    banshees: Vec<RaceBansheeDto>,
+    course: Arc<Mutex<Course>>,
+    grades: &[GradeSegment],
+) -> Result<String, String> {
+    let banshees = banshees
+        .into_iter()
+        .map(|dto| {
+            let terrain = dto.adjust_for_terrain.then_some(grades);
+            match dto.saved_banshee_id.clone() {
+                Some(saved_id) => {
+                    let mut banshee = get_saved(&saved_id)?.banshee;
+                    if let Some(handicap) = dto.handicap {
+                        banshee = with_handicap(banshee, handicap.into())?;
+                    }
+                    race_banshee(banshee, terrain, Some(saved_id))
                 }
+                None => Banshee::try_from(dto).and_then(|b| race_banshee(b, terrain, None)),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let race = RaceSession::on_shared_course(banshees, course).map_err(|e| e.to_string())?;
+
+    let db = get_db()?;
+    for saved_id in race.banshees().iter().filter_map(|b| b.saved_id.as_deref()) {
+        db.mark_banshee_used(saved_id).map_err(|e| e.to_string())?;
+    }
+    Ok(races().insert(race))
+}
+
+/// Get every banshee's state and the leaderboard for the runner's progress
+#[flutter_rust_bridge::frb(sync)]
+pub fn update_banshee_race(
+    race_id: String,
+    runner_distance_m: f64,
+    runner_elapsed_ms: i64,
+) -> Result<RaceStandingsDto, String> {
+    let race = get_race(&race_id)?;
+    let standings = race.standings(runner_distance_m, runner_elapsed_ms);
+
+    let mut watchers = event_watchers().lock().unwrap();
+    if let Some(watcher) = watchers.get_mut(&race_id) {
+        let finish = watcher.tracker.config().finish_distance_m;
+        let samples = race.event_samples(&standings, runner_elapsed_ms, finish);
+        let events = watcher
+            .tracker
+            .update(runner_distance_m, runner_elapsed_ms, &samples);
+        let delivered = events
+            .into_iter()
+            .map(|event| BansheeEventDto::new(event, &race))
+            .all(|event| watcher.sink.add(event).is_ok());
+        // Dart stopped listening
+        if !delivered {
+            watchers.remove(&race_id);
+        }
+    }
+
+    Ok(standings.into())
+}
+
+/// End a race, returning false if it wasn't running. Closes its event stream.
+#[flutter_rust_bridge::frb(sync)]
+pub fn end_banshee_race(race_id: String) -> bool {
+    event_watchers().lock().unwrap().remove(&race_id);
+    races().remove(&race_id).is_some()
+}
+
+/// DTO for one point of a race report's gap series
+pub struct GapSampleDto {
+    pub elapsed_ms: i64,
+    pub runner_distance_meters: f64,
+    /// Distance the banshee is ahead of the runner (positive = banshee ahead)
+    pub gap_meters: f64,
+}
+
+impl From<GapSample> for GapSampleDto {
+    fn from(sample: GapSample) -> Self {
+        Self {
+            elapsed_ms: sample.elapsed_ms,
+            runner_distance_meters: sample.runner_distance_m,
+            gap_meters: sample.gap_m,
+        }
+    }
+}
+
+/// DTO for a lead change in a race report
+pub struct LeadChangeDto {
+    pub elapsed_ms: i64,
+    pub runner_distance_meters: f64,
+    pub runner_took_lead: bool,
+}
+
+impl From<LeadChange> for LeadChangeDto {
+    fn from(change: LeadChange) -> Self {
+        Self {
+            elapsed_ms: change.elapsed_ms,
+            runner_distance_meters: change.runner_distance_m,
+            runner_took_lead: change.runner_took_lead,
+        }
+    }
+}
+
+/// DTO for one split compared between runner and banshee
+pub struct SplitComparisonDto {
+    pub number: i32,
+    pub runner_ms: i64,
+    pub banshee_ms: i64,
+    pub runner_won: bool,
+}
+
+impl From<SplitComparison> for SplitComparisonDto {
+    fn from(split: SplitComparison) -> Self {
+        Self {
+            number: split.number,
+            runner_ms: split.runner_ms,
+            banshee_ms: split.banshee_ms,
+            runner_won: split.runner_won(),
+        }
+    }
+}
+
+/// DTO for a post-race comparison against one banshee
+pub struct RaceReportDto {
+    pub banshee_name: String,
+    pub banshee_run_id: Option<String>,
+    pub gap_series: Vec<GapSampleDto>,
+    pub lead_changes: Vec<LeadChangeDto>,
+    pub largest_lead: Option<GapSampleDto>,
+    pub largest_deficit: Option<GapSampleDto>,
+    pub split_distance_meters: f64,
+    pub splits: Vec<SplitComparisonDto>,
+    /// Runner's time minus the banshee's over the race distance
+    /// (positive = banshee ahead); None if the banshee's run was shorter
+    pub final_time_delta_ms: Option<i64>,
+    /// Distance the banshee was ahead when the runner finished
+    pub final_distance_delta_meters: f64,
+    pub runner_won: bool,
+}
+
+impl From<RaceReport> for RaceReportDto {
+    fn from(report: RaceReport) -> Self {
+        let runner_won = report.runner_won();
+        Self {
+            banshee_name: report.banshee_name,
+            banshee_run_id: report.banshee_run_id,
+            gap_series: report.gap_series.into_iter().map(|s| s.into()).collect(),
+            lead_changes: report.lead_changes.into_iter().map(|c| c.into()).collect(),
+            largest_lead: report.largest_lead.map(|s| s.into()),
+            largest_deficit: report.largest_deficit.map(|s| s.into()),
+            split_distance_meters: report.split_distance_m,
+            splits: report.splits.into_iter().map(|s| s.into()).collect(),
+            final_time_delta_ms: report.final_time_delta_ms,
+            final_distance_delta_meters: report.final_distance_delta_m,
+            runner_won,
+        }
+    }
+}
+
+/// End a race after `finish_run` and compare the finished run against each
+/// banshee. The reports are stored with the run and returned in race order,
+/// and library banshees get the result added to their record.
+pub fn finish_banshee_race(race_id: String, run_id: String) -> Result<Vec<RaceReportDto>, String> {
+    let race = get_race(&race_id)?;
+    let db = get_db()?;
+    let run = db
+        .get_run(&run_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Run not found".to_string())?;
+    let track = IndexedTrack::new(run.points);
+
+    let reports = race
+        .banshees()
+        .iter()
+        .map(|banshee| build_race_report(&track, banshee, REPORT_SPLIT_DISTANCE_M))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    db.save_race_reports(&run_id, &reports)
+        .map_err(|e| e.to_string())?;
+    for (banshee, report) in race.banshees().iter().zip(&reports) {
+        if let Some(saved_id) = &banshee.saved_id {
+            db.record_banshee_result(saved_id, report.runner_won())
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    end_banshee_race(race_id);
+    Ok(reports.into_iter().map(|r| r.into()).collect())
+}
+
+/// Get the race reports stored with a run (empty if it wasn't a race)
+pub fn get_race_reports(run_id: String) -> Result<Vec<RaceReportDto>, String> {
+    let reports = get_db()?
+        .get_race_reports(&run_id)
+        .map_err(|e| e.to_string())?;
+    Ok(reports.into_iter().map(|r| r.into()).collect())
+}
+
+/// DTO for a ghost track (a banshee that isn't one of the runner's runs)
+pub struct GhostTrackDto {
+    /// Usable anywhere a banshee run ID is
+    pub id: String,
+    pub name: String,
+    /// "composite" or "imported"
+    pub kind: String,
+    pub created_at_ms: i64,
+    pub distance_meters: f64,
+    pub duration_ms: i64,
+}
+
+impl From<GhostTrackSummary> for GhostTrackDto {
+    fn from(ghost: GhostTrackSummary) -> Self {
+        let kind = match ghost.source {
+            GhostSource::Composite { .. } => "composite",
+            GhostSource::Imported { .. } => "imported",
+        };
+        Self {
+            id: ghost.id,
+            name: ghost.name,
+            kind: kind.to_string(),
+            created_at_ms: ghost.created_at.timestamp_millis(),
+            distance_meters: ghost.distance_meters,
+            duration_ms: ghost.duration_ms,
+        }
+    }
+}
+
+impl From<GhostTrack> for GhostTrackDto {
+    fn from(ghost: GhostTrack) -> Self {
+        GhostTrackSummary {
+            id: ghost.id,
+            name: ghost.name,
+            source: ghost.source,
+            created_at: ghost.created_at,
+            distance_meters: ghost.distance_meters,
+            duration_ms: ghost.duration_ms,
+        }
+        .into()
+    }
+}
+
+/// Stitch the given runs into a composite ghost and store it
+fn save_composite_ghost(
+    runs: Vec<Run>,
+    name: String,
+    limit_m: Option<f64>,
+) -> Result<GhostTrackDto, String> {
+    let tracks: Vec<IndexedTrack> = runs
+        .iter()
+        .map(|r| IndexedTrack::new(r.points.clone()))
+        .collect();
+    let start_time = runs.first().map_or_else(Utc::now, |r| r.start_time);
+    let composite = build_composite_track(&tracks, REPORT_SPLIT_DISTANCE_M, limit_m, start_time)
+        .map_err(|e| e.to_string())?;
+
+    let splits = composite
+        .splits
+        .iter()
+        .zip(1..)
+        .map(|(split, number)| CompositeSplit {
+            number,
+            run_id: runs[split.track_index].id.clone(),
+            duration_ms: split.duration_ms,
+        })
+        .collect();
+    let source = GhostSource::Composite {
+        split_distance_m: REPORT_SPLIT_DISTANCE_M,
+        splits,
+    };
+
+    let ghost = GhostTrack::new(name, source, composite.points);
+    get_db()?
+        .save_ghost_track(&ghost)
+        .map_err(|e| e.to_string())?;
+    Ok(ghost.into())
+}
+
+/// Build a "best-of" banshee from the fastest kilometer splits across runs of
+/// the same route. The first run's path is followed. The ghost's ID can be
+/// used like a run ID, e.g. with `get_recorded_banshee_position`.
+pub fn create_composite_banshee(
+    run_ids: Vec<String>,
+    name: String,
+) -> Result<GhostTrackDto, String> {
+    let db = get_db()?;
+    let runs = run_ids
+        .iter()
+        .map(|id| {
+            db.get_run(id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Run not found: {id}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    save_composite_ghost(runs, name, None)
+}
+
+/// Build a "best-of" banshee over `distance_m` from the best kilometer
+/// splits of every run at least that long. It follows the path of the run
+/// with the fastest time over the distance.
+pub fn create_best_effort_banshee(distance_m: f64, name: String) -> Result<GhostTrackDto, String> {
+    let db = get_db()?;
+    let query = RunQuery {
+        min_distance_m: Some(distance_m),
+        limit: None,
+        ..RunQuery::default()
+    };
+    let summaries = db.query_runs(&query).map_err(|e| e.to_string())?.runs;
+
+    let mut runs = Vec::new();
+    for summary in summaries {
+        if let Some(run) = db.get_run(&summary.id).map_err(|e| e.to_string())? {
+            let time = IndexedTrack::new(run.points.clone()).time_at_distance(distance_m);
+            if let Some(time) = time {
+                runs.push((time, run));
             }
         }
-    } else {
-        // No route - just stay at start (or could move in a direction)
-        (start_lat, start_lon)
+    }
+    runs.sort_by_key(|(time, _)| *time);
+    if runs.is_empty() {
+        return Err("No runs cover that distance".to_string());
+    }
+
+    save_composite_ghost(
+        runs.into_iter().map(|(_, r)| r).collect(),
+        name,
+        Some(distance_m),
+    )
+}
+
+/// Import a GPX, TCX or FIT track recorded elsewhere as a ghost track. It
+/// can be raced like a run but isn't one: it's kept out of the run history,
+/// personal records and totals. The name defaults to the one in the file,
+/// then the file name.
+pub fn import_ghost_track(
+    bytes: Vec<u8>,
+    file_name: String,
+    name: Option<String>,
+) -> Result<GhostTrackDto, String> {
+    let format = TrackFormat::detect(&file_name, &bytes)
+        .ok_or_else(|| "Unsupported track file".to_string())?;
+    let track = parse_track(&bytes, format).map_err(|e| e.to_string())?;
+
+    let file_name = Some(file_name).filter(|f| !f.is_empty());
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .or(track.name)
+        .or_else(|| {
+            file_name
+                .as_deref()
+                .map(|f| f.rsplit_once('.').map_or(f, |(stem, _)| stem).to_string())
+        })
+        .unwrap_or_else(|| "Imported track".to_string());
+    let source = GhostSource::Imported {
+        format: format.as_str().to_string(),
+        file_name,
     };
 
-    Ok(BansheeStateDto {
-        lat,
-        lon,
-        distance_meters,
-        time_delta_ms: 0,
-        distance_delta_meters: 0.0,
-    })
+    let ghost = GhostTrack::new(name, source, track.points);
+    get_db()?
+        .save_ghost_track(&ghost)
+        .map_err(|e| e.to_string())?;
+    Ok(ghost.into())
+}
+
+/// List stored ghost tracks, newest first
+pub fn list_ghost_tracks() -> Result<Vec<GhostTrackDto>, String> {
+    let ghosts = get_db()?.list_ghost_tracks().map_err(|e| e.to_string())?;
+    Ok(ghosts.into_iter().map(|g| g.into()).collect())
+}
+
+/// Delete a ghost track, returning false if it didn't exist
+pub fn delete_ghost_track(ghost_id: String) -> Result<bool, String> {
+    get_db()?
+        .delete_ghost_track(&ghost_id)
+        .map_err(|e| e.to_string())
+}
+
+/// DTO for a banshee saved in the library
+pub struct SavedBansheeDto {
+    pub id: String,
+    pub name: String,
+    /// Recorded run (or ghost track) for replay banshees
+    pub run_id: Option<String>,
+    /// Target (or average) pace for AI pacers
+    pub target_pace_sec_per_km: Option<f64>,
+    pub pace_plan: Option<PacePlanDto>,
+    /// Replay handicap for recorded runs (None when there isn't one)
+    pub handicap: Option<ReplayHandicapDto>,
+    pub created_at_ms: i64,
+    pub last_used_at_ms: Option<i64>,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+impl From<SavedBanshee> for SavedBansheeDto {
+    fn from(saved: SavedBanshee) -> Self {
+        let (run_id, target_pace_sec_per_km, pace_plan, handicap) = match saved.banshee.banshee_type
+        {
+            BansheeType::RecordedRun { run_id, handicap } => (
+                Some(run_id),
+                None,
+                None,
+                Some(handicap).filter(|h| !h.is_none()).map(|h| h.into()),
+            ),
+            BansheeType::AiPacer {
+                target_pace_sec_per_km,
+                pace_plan,
+            } => (
+                None,
+                Some(target_pace_sec_per_km),
+                pace_plan.map(|p| p.into()),
+                None,
+            ),
+        };
+
+        Self {
+            id: saved.id,
+            name: saved.banshee.name,
+            run_id,
+            target_pace_sec_per_km,
+            pace_plan,
+            handicap,
+            created_at_ms: saved.created_at.timestamp_millis(),
+            last_used_at_ms: saved.last_used_at.map(|t| t.timestamp_millis()),
+            wins: saved.wins,
+            losses: saved.losses,
+        }
+    }
+}
+
+fn get_saved(saved_id: &str) -> Result<SavedBanshee, String> {
+    get_db()?
+        .get_banshee(saved_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Saved banshee not found".to_string())
+}
+
+/// Save a banshee to the library. Give exactly one of a run (or ghost track),
+/// a target pace or a pace plan.
+pub fn create_saved_banshee(
+    name: String,
+    run_id: Option<String>,
+    target_pace_sec_per_km: Option<f64>,
+    pace_plan: Option<PacePlanDto>,
+) -> Result<SavedBansheeDto, String> {
+    let banshee = banshee_from_parts(name, run_id, target_pace_sec_per_km, pace_plan)?;
+    let saved = SavedBanshee::new(banshee);
+    get_db()?.save_banshee(&saved).map_err(|e| e.to_string())?;
+    Ok(saved.into())
+}
+
+/// Change a saved banshee's name and configuration, keeping its record (and
+/// its handicap, if it stays a recorded run)
+pub fn update_saved_banshee(
+    saved_id: String,
+    name: String,
+    run_id: Option<String>,
+    target_pace_sec_per_km: Option<f64>,
+    pace_plan: Option<PacePlanDto>,
+) -> Result<SavedBansheeDto, String> {
+    let mut banshee = banshee_from_parts(name, run_id, target_pace_sec_per_km, pace_plan)?;
+    let mut saved = get_saved(&saved_id)?;
+    // A recorded banshee keeps its handicap
+    if let (
+        BansheeType::RecordedRun { handicap, .. },
+        BansheeType::RecordedRun { handicap: kept, .. },
+    ) = (&mut banshee.banshee_type, &saved.banshee.banshee_type)
+    {
+        *handicap = *kept;
+    }
+    saved.banshee = banshee;
+    get_db()?.save_banshee(&saved).map_err(|e| e.to_string())?;
+    Ok(saved.into())
+}
+
+/// Set (or with None, clear) a saved recorded banshee's replay handicap,
+/// e.g. to move a "beat last week" banshee up to 102%
+pub fn set_saved_banshee_handicap(
+    saved_id: String,
+    handicap: Option<ReplayHandicapDto>,
+) -> Result<SavedBansheeDto, String> {
+    let mut saved = get_saved(&saved_id)?;
+    let handicap = handicap.map_or_else(ReplayHandicap::default, |h| h.into());
+    saved.banshee = with_handicap(saved.banshee, handicap)?;
+    get_db()?.save_banshee(&saved).map_err(|e| e.to_string())?;
+    Ok(saved.into())
+}
+
+/// Get a saved banshee
+pub fn get_saved_banshee(saved_id: String) -> Result<Option<SavedBansheeDto>, String> {
+    let saved = get_db()?
+        .get_banshee(&saved_id)
+        .map_err(|e| e.to_string())?;
+    Ok(saved.map(|s| s.into()))
+}
+
+/// List the banshee library, most recently raced first
+pub fn list_saved_banshees() -> Result<Vec<SavedBansheeDto>, String> {
+    let saved = get_db()?.list_banshees().map_err(|e| e.to_string())?;
+    Ok(saved.into_iter().map(|s| s.into()).collect())
+}
+
+/// Delete a saved banshee, returning false if it didn't exist
+pub fn delete_saved_banshee(saved_id: String) -> Result<bool, String> {
+    get_db()?
+        .delete_banshee(&saved_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Record a result against a saved banshee raced outside `start_banshee_race`
+pub fn record_saved_banshee_result(saved_id: String, runner_won: bool) -> Result<bool, String> {
+    get_db()?
+        .record_banshee_result(&saved_id, runner_won)
+        .map_err(|e| e.to_string())
+}
+
+/// DTO for race event settings
+pub struct BansheeEventConfigDto {
+    /// The lead only changes hands once the new leader is this far ahead
+    pub lead_hysteresis_meters: f64,
+    /// Gaps that raise a `GapGrew` event when first passed
+    pub gap_thresholds_meters: Vec<f64>,
+    /// A grown gap counts as closed again within this distance
+    pub gap_closed_meters: f64,
+    /// Minimum time between two lead-change or two gap events per banshee
+    pub cooldown_ms: i64,
+    /// Race distance, for `RunnerFinished` and pacers without a plan
+    pub finish_distance_meters: Option<f64>,
+}
+
+impl From<BansheeEventConfigDto> for EventConfig {
+    fn from(dto: BansheeEventConfigDto) -> Self {
+        Self {
+            lead_hysteresis_m: dto.lead_hysteresis_meters,
+            gap_thresholds_m: dto.gap_thresholds_meters,
+            gap_closed_m: dto.gap_closed_meters,
+            cooldown_ms: dto.cooldown_ms,
+            finish_distance_m: dto.finish_distance_meters,
+        }
+    }
+}
+
+impl From<EventConfig> for BansheeEventConfigDto {
+    fn from(config: EventConfig) -> Self {
+        Self {
+            lead_hysteresis_meters: config.lead_hysteresis_m,
+            gap_thresholds_meters: config.gap_thresholds_m,
+            gap_closed_meters: config.gap_closed_m,
+            cooldown_ms: config.cooldown_ms,
+            finish_distance_meters: config.finish_distance_m,
+        }
+    }
+}
+
+/// Default race event settings, for Flutter to tweak
+#[flutter_rust_bridge::frb(sync)]
+pub fn get_default_banshee_event_config() -> BansheeEventConfigDto {
+    EventConfig::default().into()
+}
+
+/// Kind of race event
+pub enum BansheeEventKindDto {
+    Overtaken,
+    Overtook,
+    GapGrew,
+    GapClosed,
+    BansheeFinished,
+    RunnerFinished,
+}
+
+/// DTO for a race event streamed to Flutter
+pub struct BansheeEventDto {
+    pub kind: BansheeEventKindDto,
+    /// Index into the race's banshees (None for `RunnerFinished`)
+    pub banshee_index: Option<u32>,
+    pub banshee_name: Option<String>,
+    /// Distance the banshee is ahead of the runner (positive = banshee ahead)
+    pub gap_meters: f64,
+    /// Threshold passed, for `GapGrew`
+    pub threshold_meters: Option<f64>,
+    pub runner_distance_meters: f64,
+    pub runner_elapsed_ms: i64,
+}
+
+impl BansheeEventDto {
+    fn new(event: BansheeEvent, race: &RaceSession) -> Self {
+        let (kind, threshold_meters) = match event.kind {
+            BansheeEventKind::Overtaken => (BansheeEventKindDto::Overtaken, None),
+            BansheeEventKind::Overtook => (BansheeEventKindDto::Overtook, None),
+            BansheeEventKind::GapGrew { threshold_m } => {
+                (BansheeEventKindDto::GapGrew, Some(threshold_m))
+            }
+            BansheeEventKind::GapClosed => (BansheeEventKindDto::GapClosed, None),
+            BansheeEventKind::BansheeFinished => (BansheeEventKindDto::BansheeFinished, None),
+            BansheeEventKind::RunnerFinished => (BansheeEventKindDto::RunnerFinished, None),
+        };
+
+        Self {
+            kind,
+            banshee_index: event.banshee_index.map(|i| i as u32),
+            banshee_name: event
+                .banshee_index
+                .and_then(|i| race.banshees().get(i))
+                .map(|b| b.name.clone()),
+            gap_meters: event.gap_m,
+            threshold_meters,
+            runner_distance_meters: event.runner_distance_m,
+            runner_elapsed_ms: event.runner_elapsed_ms,
+        }
+    }
+}
+
+/// Race events being streamed to Dart for one race
+struct RaceEventWatcher {
+    tracker: RaceEventTracker,
+    sink: StreamSink<BansheeEventDto>,
+}
+
+/// Event watchers keyed by race ID
+fn event_watchers() -> &'static Mutex<HashMap<String, RaceEventWatcher>> {
+    static WATCHERS: OnceLock<Mutex<HashMap<String, RaceEventWatcher>>> = OnceLock::new();
+    WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Stream a race's events (overtakes, gaps, finishes) to Dart. Events are
+/// raised as `update_banshee_race` is called, replacing polling with
+/// `check_position_change`. Watching again replaces the previous stream.
+pub fn watch_banshee_race_events(
+    race_id: String,
+    config: BansheeEventConfigDto,
+    sink: StreamSink<BansheeEventDto>,
+) -> Result<(), String> {
+    let race = get_race(&race_id)?;
+    let tracker =
+        RaceEventTracker::new(race.banshees().len(), config.into()).map_err(|e| e.to_string())?;
+
+    event_watchers()
+        .lock()
+        .unwrap()
+        .insert(race_id, RaceEventWatcher { tracker, sink });
+    Ok(())
+}
+
+fn get_race(race_id: &str) -> Result<Arc<RaceSession>, String> {
+    races()
+        .get(race_id)
+        .ok_or_else(|| "Race not found".to_string())
+}
+
+/// DTO for the runner's position matched onto a route
+pub struct RouteMatchDto {
+    /// Distance along the route; use it for `compare_banshee_at_distance`
+    pub distance_along_meters: f64,
+    /// Distance from the route
+    pub cross_track_meters: f64,
+    pub off_course: bool,
+}
+
+impl From<RouteMatch> for RouteMatchDto {
+    fn from(m: RouteMatch) -> Self {
+        Self {
+            distance_along_meters: m.distance_along_m,
+            cross_track_meters: m.cross_track_m,
+            off_course: m.off_course,
+        }
+    }
+}
+
+fn matchers() -> &'static Registry<Mutex<RouteMatcher>> {
+    static MATCHERS: OnceLock<Registry<Mutex<RouteMatcher>>> = OnceLock::new();
+    MATCHERS.get_or_init(Registry::new)
+}
+
+/// Start matching the runner onto a planned route. Returns a matcher ID for
+/// `match_route_position`.
+pub fn open_route_matcher(route: Vec<RoutePointDto>) -> Result<String, String> {
+    let matcher = RouteMatcher::new(route_points_to_track(route)).map_err(|e| e.to_string())?;
+    Ok(matchers().insert(Mutex::new(matcher)))
+}
+
+/// Start matching the runner onto the route of a recorded run (the banshee's)
+pub fn open_route_matcher_for_run(run_id: String) -> Result<String, String> {
+    let session = load_session(run_id)?;
+    let matcher = RouteMatcher::new(session.track().clone()).map_err(|e| e.to_string())?;
+    Ok(matchers().insert(Mutex::new(matcher)))
+}
+
+/// Start matching the runner onto a saved route
+pub fn open_route_matcher_for_route(route_id: String) -> Result<String, String> {
+    let route = route_course(&route_id)?;
+    let matcher = RouteMatcher::new(route.track.clone()).map_err(|e| e.to_string())?;
+    Ok(matchers().insert(Mutex::new(matcher)))
+}
+
+/// Match the runner's latest GPS fix onto the route
+#[flutter_rust_bridge::frb(sync)]
+pub fn match_route_position(
+    matcher_id: String,
+    lat: f64,
+    lon: f64,
+) -> Result<RouteMatchDto, String> {
+    let matcher = matchers()
+        .get(&matcher_id)
+        .ok_or_else(|| "Route matcher not found".to_string())?;
+    let result = matcher.lock().unwrap().update(lat, lon);
+    Ok(result.into())
+}
+
+/// Stop matching, returning false if the matcher wasn't open
+#[flutter_rust_bridge::frb(sync)]
+pub fn close_route_matcher(matcher_id: String) -> bool {
+    matchers().remove(&matcher_id).is_some()
 }
 
 /// Calculate banshee state relative to runner
@@ -173,8 +1399,25 @@ pub fn format_banshee_delta(distance_delta_meters: f64) -> String {
     }
 }
 
-/// Check if the runner has crossed the ahead/behind threshold
-/// Returns: -1 if now behind (was ahead), 1 if now ahead (was behind), 0 if no change
+/// Format a time delta for display (e.g., "12s behind", "1:05 ahead")
+/// time_delta_ms: runner time minus banshee time (positive = banshee ahead)
+#[flutter_rust_bridge::frb(sync)]
+pub fn format_banshee_time_delta(time_delta_ms: i64) -> String {
+    let abs_secs = time_delta_ms.abs() / 1000;
+    let direction = if time_delta_ms > 0 { "behind" } else { "ahead" };
+
+    if abs_secs < 1 {
+        "Even".to_string()
+    } else if abs_secs < 60 {
+        format!("{}s {}", abs_secs, direction)
+    } else {
+        format!("{}:{:02} {}", abs_secs / 60, abs_secs % 60, direction)
+    }
+}
+
+/// Check if the runner has crossed the ahead/behind threshold.
+/// Prefer `watch_banshee_race_events`, which also catches lead changes
+/// between samples. Returns: -1 if now behind (was ahead), 1 if now ahead (was behind), 0 if no change
 #[flutter_rust_bridge::frb(sync)]
 pub fn check_position_change(previous_delta_m: f64, current_delta_m: f64, threshold_m: f64) -> i32 {
     let was_ahead = previous_delta_m < -threshold_m;
@@ -0,0 +1,154 @@
+use crate::export::{gpx, ShareableRun};
+use crate::models::{GpsPoint, PrivacyZone, Run};
+
+use super::banshee_api::RoutePointDto;
+use super::run_api::get_db;
+
+/// DTO for a privacy zone: points within `radius_meters` of the centre are
+/// left out of exports and shared maps
+pub struct PrivacyZoneDto {
+    pub id: String,
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub radius_meters: f64,
+}
+
+impl From<PrivacyZone> for PrivacyZoneDto {
+    fn from(zone: PrivacyZone) -> Self {
+        Self {
+            id: zone.id,
+            name: zone.name,
+            lat: zone.lat,
+            lon: zone.lon,
+            radius_meters: zone.radius_m,
+        }
+    }
+}
+
+fn privacy_zones() -> Result<Vec<PrivacyZone>, String> {
+    get_db()?.get_privacy_zones().map_err(|e| e.to_string())
+}
+
+fn save_privacy_zones(zones: &[PrivacyZone]) -> Result<(), String> {
+    get_db()?
+        .set_privacy_zones(zones)
+        .map_err(|e| e.to_string())
+}
+
+/// List privacy zones
+pub fn list_privacy_zones() -> Result<Vec<PrivacyZoneDto>, String> {
+    Ok(privacy_zones()?.into_iter().map(|z| z.into()).collect())
+}
+
+/// Add a privacy zone (e.g. around home)
+pub fn add_privacy_zone(
+    name: String,
+    lat: f64,
+    lon: f64,
+    radius_meters: f64,
+) -> Result<PrivacyZoneDto, String> {
+    let zone = PrivacyZone::new(name, lat, lon, radius_meters).map_err(|e| e.to_string())?;
+    let mut zones = privacy_zones()?;
+    zones.push(zone.clone());
+    save_privacy_zones(&zones)?;
+    Ok(zone.into())
+}
+
+/// Move, resize or rename a privacy zone. Returns false if it doesn't exist.
+pub fn update_privacy_zone(
+    zone_id: String,
+    name: String,
+    lat: f64,
+    lon: f64,
+    radius_meters: f64,
+) -> Result<bool, String> {
+    let mut zones = privacy_zones()?;
+    let Some(zone) = zones.iter_mut().find(|z| z.id == zone_id) else {
+        return Ok(false);
+    };
+    let updated = PrivacyZone {
+        id: zone_id,
+        name,
+        lat,
+        lon,
+        radius_m: radius_meters,
+    };
+    updated.validate().map_err(|e| e.to_string())?;
+    *zone = updated;
+    save_privacy_zones(&zones)?;
+    Ok(true)
+}
+
+/// Remove a privacy zone. Returns false if it didn't exist.
+pub fn remove_privacy_zone(zone_id: String) -> Result<bool, String> {
+    let mut zones = privacy_zones()?;
+    let count = zones.len();
+    zones.retain(|z| z.id != zone_id);
+    if zones.len() == count {
+        return Ok(false);
+    }
+    save_privacy_zones(&zones)?;
+    Ok(true)
+}
+
+/// A run with the privacy zones applied
+fn shareable_run(run_id: &str) -> Result<ShareableRun, String> {
+    let run: Run = get_db()?
+        .get_run(run_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Run not found".to_string())?;
+    Ok(ShareableRun::new(&run, &privacy_zones()?))
+}
+
+/// Export a run as GPX, leaving out points inside privacy zones. The
+/// stored run keeps every point.
+pub fn export_run_gpx(run_id: String) -> Result<String, String> {
+    Ok(gpx::write(&shareable_run(&run_id)?))
+}
+
+/// DTO for one visible stretch of a shared run's path
+pub struct TrackSectionDto {
+    pub points: Vec<RoutePointDto>,
+}
+
+/// DTO for drawing a run to share (e.g. a map card): the path outside
+/// privacy zones in sections, with stats from the whole run
+pub struct ShareableRunDto {
+    pub name: Option<String>,
+    pub start_time_ms: i64,
+    /// Includes the hidden parts of the path
+    pub distance_meters: f64,
+    pub duration_ms: i64,
+    pub avg_pace_sec_per_km: Option<f64>,
+    pub sections: Vec<TrackSectionDto>,
+}
+
+impl From<ShareableRun> for ShareableRunDto {
+    fn from(run: ShareableRun) -> Self {
+        let point = |p: GpsPoint| RoutePointDto {
+            lat: p.lat,
+            lon: p.lon,
+            altitude: p.altitude,
+        };
+        Self {
+            name: run.name,
+            start_time_ms: run.start_time.timestamp_millis(),
+            distance_meters: run.distance_meters,
+            duration_ms: run.duration_ms,
+            avg_pace_sec_per_km: run.avg_pace_sec_per_km,
+            sections: run
+                .sections
+                .into_iter()
+                .map(|s| TrackSectionDto {
+                    points: s.into_iter().map(point).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Get a run ready for a share renderer, with privacy zones applied
+pub fn get_shareable_run(run_id: String) -> Result<ShareableRunDto, String> {
+    Ok(shareable_run(&run_id)?.into())
+}
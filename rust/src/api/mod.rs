@@ -1,9 +1,19 @@
 pub mod banshee_api;
+pub mod export_api;
+pub mod plan_api;
+pub mod route_api;
 pub mod run_api;
+pub mod segment_api;
 pub mod simple;
 pub mod stats_api;
+pub mod workout_api;
 
 // Re-export for convenience
 pub use banshee_api::*;
+pub use export_api::*;
+pub use plan_api::*;
+pub use route_api::*;
 pub use run_api::*;
+pub use segment_api::*;
 pub use stats_api::*;
+pub use workout_api::*;
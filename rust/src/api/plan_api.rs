@@ -0,0 +1,309 @@
+use chrono::{Duration, FixedOffset, NaiveDate, Utc};
+
+use crate::db::RunQuery;
+use crate::models::{PaceZones, PlanDay, PlanSessionKind, RaceTime, Run, TrainingPlan};
+use crate::training::fitness::{
+    estimate_fitness, pace_zones, weekly_volume, FITNESS_LOOKBACK_WEEKS, VOLUME_LOOKBACK_WEEKS,
+};
+use crate::training::{generate_plan, PlanRequest};
+
+use super::run_api::get_db;
+use super::workout_api::{start_session_for, WorkoutDto};
+
+/// DTO for a time over a distance (a race result or best effort)
+pub struct RaceTimeDto {
+    pub distance_meters: f64,
+    pub duration_ms: i64,
+}
+
+impl From<RaceTime> for RaceTimeDto {
+    fn from(time: RaceTime) -> Self {
+        Self {
+            distance_meters: time.distance_m,
+            duration_ms: time.duration_ms,
+        }
+    }
+}
+
+impl From<RaceTimeDto> for RaceTime {
+    fn from(dto: RaceTimeDto) -> Self {
+        Self {
+            distance_m: dto.distance_meters,
+            duration_ms: dto.duration_ms,
+        }
+    }
+}
+
+/// DTO for training paces in seconds per kilometer, slowest first
+pub struct PaceZonesDto {
+    pub easy_sec_per_km: f64,
+    pub marathon_sec_per_km: f64,
+    pub threshold_sec_per_km: f64,
+    pub interval_sec_per_km: f64,
+    pub repetition_sec_per_km: f64,
+}
+
+impl From<PaceZones> for PaceZonesDto {
+    fn from(zones: PaceZones) -> Self {
+        Self {
+            easy_sec_per_km: zones.easy_sec_per_km,
+            marathon_sec_per_km: zones.marathon_sec_per_km,
+            threshold_sec_per_km: zones.threshold_sec_per_km,
+            interval_sec_per_km: zones.interval_sec_per_km,
+            repetition_sec_per_km: zones.repetition_sec_per_km,
+        }
+    }
+}
+
+/// DTO for one day of a training plan
+pub struct PlanDayDto {
+    /// "YYYY-MM-DD"
+    pub date: String,
+    /// Plan week (1-indexed)
+    pub week: u32,
+    /// "base", "build", "peak" or "taper"
+    pub phase: String,
+    /// "rest", "easy", "long", "workout" or "race"
+    pub kind: String,
+    pub distance_meters: f64,
+    pub target_pace_sec_per_km: Option<f64>,
+    pub workout: Option<WorkoutDto>,
+    /// Run that completed the session, if any
+    pub run_id: Option<String>,
+}
+
+impl From<PlanDay> for PlanDayDto {
+    fn from(day: PlanDay) -> Self {
+        Self {
+            date: day.date.format(DATE_FORMAT).to_string(),
+            week: day.week,
+            phase: day.phase.as_str().to_string(),
+            kind: day.kind.as_str().to_string(),
+            distance_meters: day.distance_m,
+            target_pace_sec_per_km: day.target_pace_sec_per_km,
+            workout: day.workout.map(|w| w.into()),
+            run_id: day.run_id,
+        }
+    }
+}
+
+/// DTO for a training plan with every day up to race day
+pub struct TrainingPlanDto {
+    pub id: String,
+    pub name: String,
+    pub race_distance_meters: f64,
+    /// "YYYY-MM-DD"
+    pub race_date: String,
+    /// Fitness the paces were derived from
+    pub fitness: RaceTimeDto,
+    pub zones: PaceZonesDto,
+    pub start_weekly_volume_meters: f64,
+    pub created_at_ms: i64,
+    pub days: Vec<PlanDayDto>,
+    /// Running sessions with a run linked
+    pub done_sessions: u32,
+    /// Running sessions in the plan (everything but rest days)
+    pub total_sessions: u32,
+}
+
+impl From<TrainingPlan> for TrainingPlanDto {
+    fn from(plan: TrainingPlan) -> Self {
+        let sessions = plan.days.iter().filter(|d| d.kind != PlanSessionKind::Rest);
+        let total_sessions = sessions.clone().count() as u32;
+        let done_sessions = sessions.filter(|d| d.run_id.is_some()).count() as u32;
+
+        Self {
+            id: plan.id,
+            name: plan.name,
+            race_distance_meters: plan.race_distance_m,
+            race_date: plan.race_date.format(DATE_FORMAT).to_string(),
+            fitness: plan.fitness.into(),
+            zones: plan.zones.into(),
+            start_weekly_volume_meters: plan.start_weekly_volume_m,
+            created_at_ms: plan.created_at.timestamp_millis(),
+            days: plan.days.into_iter().map(|d| d.into()).collect(),
+            done_sessions,
+            total_sessions,
+        }
+    }
+}
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|_| format!("Invalid date: {date}"))
+}
+
+/// Full runs (with points) started in the last `weeks` weeks
+fn recent_runs(weeks: i64) -> Result<Vec<Run>, String> {
+    let db = get_db()?;
+    let page = db
+        .query_runs(&RunQuery {
+            start_after: Some(Utc::now() - Duration::weeks(weeks)),
+            limit: None,
+            ..RunQuery::default()
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut runs = Vec::with_capacity(page.runs.len());
+    for summary in page.runs {
+        if let Some(run) = db.get_run(&summary.id).map_err(|e| e.to_string())? {
+            runs.push(run);
+        }
+    }
+    Ok(runs)
+}
+
+/// Predict current fitness from the best efforts of recent runs. None if
+/// there's no recent effort of a mile or longer.
+pub fn get_predicted_fitness() -> Result<Option<RaceTimeDto>, String> {
+    let runs = recent_runs(FITNESS_LOOKBACK_WEEKS)?;
+    Ok(estimate_fitness(&runs).map(|f| f.into()))
+}
+
+/// Average weekly distance over the last few weeks
+pub fn get_recent_weekly_volume() -> Result<f64, String> {
+    let page = get_db()?
+        .query_runs(&RunQuery {
+            start_after: Some(Utc::now() - Duration::weeks(VOLUME_LOOKBACK_WEEKS)),
+            limit: None,
+            ..RunQuery::default()
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(weekly_volume(&page.runs, Utc::now(), VOLUME_LOOKBACK_WEEKS))
+}
+
+/// Training paces for a race result or best effort
+#[flutter_rust_bridge::frb(sync)]
+pub fn get_pace_zones(fitness: RaceTimeDto) -> Result<PaceZonesDto, String> {
+    if fitness.distance_meters <= 0.0 || fitness.duration_ms <= 0 {
+        return Err("Invalid fitness".to_string());
+    }
+    Ok(pace_zones(fitness.into()).into())
+}
+
+/// Generate and save a training plan running from `start_date` to the race
+/// (dates as "YYYY-MM-DD"). Without a weekly volume or recent race, the
+/// plan starts from recent runs; it fails if they give no fitness.
+pub fn generate_training_plan(
+    name: String,
+    race_distance_meters: f64,
+    race_date: String,
+    start_date: String,
+    runs_per_week: u32,
+    weekly_volume_meters: Option<f64>,
+    recent_race: Option<RaceTimeDto>,
+) -> Result<TrainingPlanDto, String> {
+    let fitness = match recent_race {
+        Some(race) => race.into(),
+        None => estimate_fitness(&recent_runs(FITNESS_LOOKBACK_WEEKS)?).ok_or_else(|| {
+            "No recent runs to predict fitness from; give a recent race time".to_string()
+        })?,
+    };
+    let weekly_volume_m = match weekly_volume_meters {
+        Some(volume) => volume,
+        None => get_recent_weekly_volume()?,
+    };
+
+    let plan = generate_plan(&PlanRequest {
+        name,
+        race_distance_m: race_distance_meters,
+        race_date: parse_date(&race_date)?,
+        start_date: parse_date(&start_date)?,
+        runs_per_week,
+        weekly_volume_m,
+        fitness,
+    })
+    .map_err(|e| e.to_string())?;
+
+    get_db()?
+        .save_training_plan(&plan)
+        .map_err(|e| e.to_string())?;
+    Ok(plan.into())
+}
+
+fn get_saved_plan(plan_id: &str) -> Result<TrainingPlan, String> {
+    get_db()?
+        .get_training_plan(plan_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Training plan not found".to_string())
+}
+
+/// Get a training plan
+pub fn get_training_plan(plan_id: String) -> Result<Option<TrainingPlanDto>, String> {
+    let plan = get_db()?
+        .get_training_plan(&plan_id)
+        .map_err(|e| e.to_string())?;
+    Ok(plan.map(|p| p.into()))
+}
+
+/// List training plans, latest race first
+pub fn list_training_plans() -> Result<Vec<TrainingPlanDto>, String> {
+    let plans = get_db()?.list_training_plans().map_err(|e| e.to_string())?;
+    Ok(plans.into_iter().map(|p| p.into()).collect())
+}
+
+/// Delete a training plan, returning false if it didn't exist
+pub fn delete_training_plan(plan_id: String) -> Result<bool, String> {
+    get_db()?
+        .delete_training_plan(&plan_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Mark a plan day done with the run that completed it. Returns false if
+/// the plan has no such day.
+pub fn link_run_to_plan_day(plan_id: String, date: String, run_id: String) -> Result<bool, String> {
+    let db = get_db()?;
+    if db.get_run(&run_id).map_err(|e| e.to_string())?.is_none() {
+        return Err("Run not found".to_string());
+    }
+    db.set_plan_day_run(&plan_id, parse_date(&date)?, Some(&run_id))
+        .map_err(|e| e.to_string())
+}
+
+/// Mark a plan day not done
+pub fn unlink_plan_day(plan_id: String, date: String) -> Result<bool, String> {
+    get_db()?
+        .set_plan_day_run(&plan_id, parse_date(&date)?, None)
+        .map_err(|e| e.to_string())
+}
+
+/// Link a run to the plan session on the day it was run, in the runner's
+/// time zone (`utc_offset_minutes`, e.g. 60 for UTC+1). Returns the day
+/// linked, or None if the plan has no running session that day.
+pub fn link_run_to_plan(
+    plan_id: String,
+    run_id: String,
+    utc_offset_minutes: i32,
+) -> Result<Option<String>, String> {
+    let offset = FixedOffset::east_opt(utc_offset_minutes * 60)
+        .ok_or_else(|| "Invalid UTC offset".to_string())?;
+    let db = get_db()?;
+    let run = db
+        .get_run(&run_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Run not found".to_string())?;
+    let date = run.start_time.with_timezone(&offset).date_naive();
+
+    let plan = get_saved_plan(&plan_id)?;
+    if plan
+        .day(date)
+        .is_none_or(|d| d.kind == PlanSessionKind::Rest)
+    {
+        return Ok(None);
+    }
+    db.set_plan_day_run(&plan_id, date, Some(&run_id))
+        .map_err(|e| e.to_string())?;
+    Ok(Some(date.format(DATE_FORMAT).to_string()))
+}
+
+/// Start a workout session for a plan day's structured workout. Returns a
+/// session ID for `update_workout_session`.
+pub fn start_plan_day_workout(plan_id: String, date: String) -> Result<String, String> {
+    let plan = get_saved_plan(&plan_id)?;
+    let workout = plan
+        .day(parse_date(&date)?)
+        .and_then(|d| d.workout.as_ref())
+        .ok_or_else(|| "No workout planned that day".to_string())?;
+    start_session_for(workout)
+}
@@ -0,0 +1,226 @@
+use crate::geo::elevation::{grade_profile, GradeSegment, GRADE_SAMPLE_SPACING_M};
+use crate::geo::simplify::{simplify, ROUTE_TOLERANCE_M};
+use crate::geo::IndexedTrack;
+use crate::import::parse_gpx_route;
+use crate::models::{ElevationSample, Route, RoutePoint, RouteSource, RouteSummary};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use super::banshee_api::RoutePointDto;
+use super::run_api::get_db;
+
+impl From<RoutePointDto> for RoutePoint {
+    fn from(dto: RoutePointDto) -> Self {
+        Self {
+            lat: dto.lat,
+            lon: dto.lon,
+            altitude: dto.altitude,
+        }
+    }
+}
+
+impl From<RoutePoint> for RoutePointDto {
+    fn from(point: RoutePoint) -> Self {
+        Self {
+            lat: point.lat,
+            lon: point.lon,
+            altitude: point.altitude,
+        }
+    }
+}
+
+/// DTO for the altitude at a distance along a route
+pub struct ElevationSampleDto {
+    pub distance_meters: f64,
+    pub altitude_meters: f64,
+}
+
+impl From<ElevationSample> for ElevationSampleDto {
+    fn from(sample: ElevationSample) -> Self {
+        Self {
+            distance_meters: sample.distance_m,
+            altitude_meters: sample.altitude_m,
+        }
+    }
+}
+
+/// DTO for a route listing, without points
+pub struct RouteSummaryDto {
+    pub id: String,
+    pub name: String,
+    /// "waypoints", "run" or "gpx"
+    pub kind: String,
+    /// Run the route was made from, for "run"
+    pub source_run_id: Option<String>,
+    pub distance_meters: f64,
+    pub elevation_gain_meters: f64,
+    pub elevation_loss_meters: f64,
+    pub created_at_ms: i64,
+}
+
+impl From<RouteSummary> for RouteSummaryDto {
+    fn from(route: RouteSummary) -> Self {
+        let (kind, source_run_id) = match route.source {
+            RouteSource::Waypoints => ("waypoints", None),
+            RouteSource::Run { run_id } => ("run", Some(run_id)),
+            RouteSource::Gpx { .. } => ("gpx", None),
+        };
+        Self {
+            id: route.id,
+            name: route.name,
+            kind: kind.to_string(),
+            source_run_id,
+            distance_meters: route.distance_meters,
+            elevation_gain_meters: route.elevation_gain_m,
+            elevation_loss_meters: route.elevation_loss_m,
+            created_at_ms: route.created_at.timestamp_millis(),
+        }
+    }
+}
+
+/// DTO for a route with its path and elevation profile
+pub struct RouteDto {
+    pub summary: RouteSummaryDto,
+    pub points: Vec<RoutePointDto>,
+    /// Empty if the route has no altitude
+    pub elevation_profile: Vec<ElevationSampleDto>,
+}
+
+impl From<Route> for RouteDto {
+    fn from(route: Route) -> Self {
+        Self {
+            summary: RouteSummary::from(&route).into(),
+            points: route.points.into_iter().map(|p| p.into()).collect(),
+            elevation_profile: route
+                .elevation_profile
+                .into_iter()
+                .map(|s| s.into())
+                .collect(),
+        }
+    }
+}
+
+fn save_new_route(
+    name: String,
+    source: RouteSource,
+    points: Vec<RoutePoint>,
+) -> Result<RouteDto, String> {
+    let route = Route::new(name, source, points).map_err(|e| e.to_string())?;
+    get_db()?.save_route(&route).map_err(|e| e.to_string())?;
+    Ok(route.into())
+}
+
+/// Save a route drawn through waypoints (in order)
+pub fn create_route_from_waypoints(
+    name: String,
+    waypoints: Vec<RoutePointDto>,
+) -> Result<RouteDto, String> {
+    let points = waypoints.into_iter().map(|p| p.into()).collect();
+    save_new_route(name, RouteSource::Waypoints, points)
+}
+
+/// Save the path of a recorded run as a route, simplified to drop GPS
+/// jitter. `tolerance_meters` defaults to a few meters; larger values give
+/// fewer points. The name defaults to the run's.
+pub fn create_route_from_run(
+    run_id: String,
+    name: Option<String>,
+    tolerance_meters: Option<f64>,
+) -> Result<RouteDto, String> {
+    let run = get_db()?
+        .get_run(&run_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Run not found".to_string())?;
+    let tolerance = tolerance_meters.unwrap_or(ROUTE_TOLERANCE_M);
+    if !tolerance.is_finite() || tolerance < 0.0 {
+        return Err("Invalid tolerance".to_string());
+    }
+
+    let points = simplify(&run.points, tolerance)
+        .iter()
+        .map(RoutePoint::from)
+        .collect();
+    let name = name.or(run.name).unwrap_or_else(|| "Route".to_string());
+    save_new_route(name, RouteSource::Run { run_id }, points)
+}
+
+/// Import a GPX route (or track) file as a route. The name defaults to
+/// the one in the file, then the file name.
+pub fn import_route_gpx(
+    bytes: Vec<u8>,
+    file_name: String,
+    name: Option<String>,
+) -> Result<RouteDto, String> {
+    let imported = parse_gpx_route(&bytes).map_err(|e| e.to_string())?;
+    let name = name
+        .or(imported.name)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| file_name.clone());
+    save_new_route(
+        name,
+        RouteSource::Gpx {
+            file_name: Some(file_name),
+        },
+        imported.points,
+    )
+}
+
+/// Get a route with its points and elevation profile
+pub fn get_route(route_id: String) -> Result<Option<RouteDto>, String> {
+    let route = get_db()?.get_route(&route_id).map_err(|e| e.to_string())?;
+    Ok(route.map(|r| r.into()))
+}
+
+/// List routes without their points, newest first
+pub fn list_routes() -> Result<Vec<RouteSummaryDto>, String> {
+    let routes = get_db()?.list_routes().map_err(|e| e.to_string())?;
+    Ok(routes.into_iter().map(|r| r.into()).collect())
+}
+
+/// Rename a route, returning false if it doesn't exist
+pub fn rename_route(route_id: String, name: String) -> Result<bool, String> {
+    get_db()?
+        .rename_route(&route_id, &name)
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a route, returning false if it didn't exist
+pub fn delete_route(route_id: String) -> Result<bool, String> {
+    route_cache().lock().unwrap().remove(&route_id);
+    get_db()?.delete_route(&route_id).map_err(|e| e.to_string())
+}
+
+/// A route ready to follow: its track and grades
+pub(crate) struct RouteCourse {
+    pub track: IndexedTrack,
+    pub grades: Vec<GradeSegment>,
+}
+
+/// Routes already loaded, keyed by ID. Route paths never change once
+/// saved, so entries only go when the route is deleted.
+fn route_cache() -> &'static Mutex<HashMap<String, Arc<RouteCourse>>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<RouteCourse>>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Load a saved route for pacers and route matching
+pub(crate) fn route_course(route_id: &str) -> Result<Arc<RouteCourse>, String> {
+    if let Some(course) = route_cache().lock().unwrap().get(route_id) {
+        return Ok(course.clone());
+    }
+
+    let route = get_db()?
+        .get_route(route_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Route not found".to_string())?;
+    let track = route.track();
+    let course = Arc::new(RouteCourse {
+        grades: grade_profile(&track, GRADE_SAMPLE_SPACING_M),
+        track,
+    });
+    route_cache()
+        .lock()
+        .unwrap()
+        .insert(route_id.to_string(), course.clone());
+    Ok(course)
+}
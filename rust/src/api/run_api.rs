@@ -1,6 +1,8 @@
-use crate::db::Database;
+use crate::db::{Database, RunPage, RunQuery, RunSortField, TRASH_RETENTION_DAYS};
 use crate::geo;
-use crate::models::{GpsPoint, Run, RunSummary};
+use crate::models::{
+    GpsPoint, Lap, Run, RunMetadata, RunMetadataPatch, RunSummary, RunType, TrashedRun,
+};
 use chrono::{DateTime, Utc};
 use std::sync::OnceLock;
 
@@ -9,12 +11,19 @@ static DATABASE: OnceLock<Database> = OnceLock::new();
 /// Initialize the database with the given path
 pub fn init_database(db_path: String) -> Result<(), String> {
     let db = Database::open(&db_path).map_err(|e| e.to_string())?;
+
+    // Purge runs that have been in the trash longer than the retention period
+    let cutoff = Utc::now() - chrono::Duration::days(TRASH_RETENTION_DAYS);
+    if let Err(e) = db.purge_deleted_before(cutoff) {
+        log::warn!("Failed to purge expired trash: {e}");
+    }
+
     DATABASE
         .set(db)
         .map_err(|_| "Database already initialized".to_string())
 }
 
-fn get_db() -> Result<&'static Database, String> {
+pub(crate) fn get_db() -> Result<&'static Database, String> {
     DATABASE
         .get()
         .ok_or_else(|| "Database not initialized".to_string())
@@ -78,10 +87,81 @@ impl From<RunDto> for Run {
             distance_meters: dto.distance_meters,
             duration_ms: dto.duration_ms,
             avg_pace_sec_per_km: dto.avg_pace_sec_per_km,
+            metadata: RunMetadata::default(),
+            pauses: Vec::new(),
+            laps: Vec::new(),
         }
     }
 }
 
+/// DTO for run metadata returned to Flutter
+pub struct RunMetadataDto {
+    pub notes: Option<String>,
+    /// One of "easy", "long", "tempo", "race", "workout"
+    pub run_type: Option<String>,
+    pub tags: Vec<String>,
+    pub perceived_exertion: Option<i32>,
+    pub weather: Option<String>,
+}
+
+impl From<RunMetadata> for RunMetadataDto {
+    fn from(metadata: RunMetadata) -> Self {
+        Self {
+            notes: metadata.notes,
+            run_type: metadata.run_type.map(|t| t.as_str().to_string()),
+            tags: metadata.tags,
+            perceived_exertion: metadata.perceived_exertion,
+            weather: metadata.weather,
+        }
+    }
+}
+
+/// DTO for editing a run's name and metadata from Flutter.
+/// Fields left as None are unchanged; empty strings clear text fields, and
+/// `clear_run_type` / `clear_perceived_exertion` clear those fields.
+pub struct RunMetadataPatchDto {
+    pub name: Option<String>,
+    pub notes: Option<String>,
+    pub run_type: Option<String>,
+    pub clear_run_type: bool,
+    pub tags: Option<Vec<String>>,
+    pub perceived_exertion: Option<i32>,
+    pub clear_perceived_exertion: bool,
+    pub weather: Option<String>,
+}
+
+impl TryFrom<RunMetadataPatchDto> for RunMetadataPatch {
+    type Error = String;
+
+    fn try_from(dto: RunMetadataPatchDto) -> Result<Self, Self::Error> {
+        let run_type = match (dto.run_type, dto.clear_run_type) {
+            (Some(_), true) => return Err("Can't both set and clear the run type".to_string()),
+            (Some(value), false) => Some(Some(
+                RunType::parse(&value).ok_or_else(|| format!("Invalid run type: {value}"))?,
+            )),
+            (None, true) => Some(None),
+            (None, false) => None,
+        };
+        let perceived_exertion = match (dto.perceived_exertion, dto.clear_perceived_exertion) {
+            (Some(_), true) => {
+                return Err("Can't both set and clear the perceived exertion".to_string())
+            }
+            (Some(rpe), false) => Some(Some(rpe)),
+            (None, true) => Some(None),
+            (None, false) => None,
+        };
+
+        Ok(RunMetadataPatch {
+            name: dto.name,
+            notes: dto.notes,
+            run_type,
+            tags: dto.tags,
+            perceived_exertion,
+            weather: dto.weather,
+        })
+    }
+}
+
 /// DTO for returning run summary to Flutter
 pub struct RunSummaryDto {
     pub id: String,
@@ -105,6 +185,87 @@ impl From<RunSummary> for RunSummaryDto {
     }
 }
 
+/// DTO for run history queries from Flutter
+pub struct RunQueryDto {
+    pub min_distance_m: Option<f64>,
+    pub max_distance_m: Option<f64>,
+    pub min_duration_ms: Option<i64>,
+    pub max_duration_ms: Option<i64>,
+    pub start_after_ms: Option<i64>,
+    pub start_before_ms: Option<i64>,
+    /// Runs must carry every one of these tags
+    pub tags: Vec<String>,
+    pub name_contains: Option<String>,
+    /// One of "start_time", "distance", "duration"
+    pub sort_by: String,
+    pub ascending: bool,
+    pub limit: u32,
+    /// `next_cursor` from the previous page, None for the first page
+    pub cursor: Option<String>,
+}
+
+impl TryFrom<RunQueryDto> for RunQuery {
+    type Error = String;
+
+    fn try_from(dto: RunQueryDto) -> Result<Self, Self::Error> {
+        let sort_by = RunSortField::parse(&dto.sort_by)
+            .ok_or_else(|| format!("Invalid sort field: {}", dto.sort_by))?;
+        let to_time = |ms: Option<i64>| {
+            ms.and_then(|ms| DateTime::from_timestamp_millis(ms).map(|dt| dt.with_timezone(&Utc)))
+        };
+
+        Ok(RunQuery {
+            min_distance_m: dto.min_distance_m,
+            max_distance_m: dto.max_distance_m,
+            min_duration_ms: dto.min_duration_ms,
+            max_duration_ms: dto.max_duration_ms,
+            start_after: to_time(dto.start_after_ms),
+            start_before: to_time(dto.start_before_ms),
+            tags: dto.tags,
+            name_contains: dto.name_contains,
+            sort_by,
+            ascending: dto.ascending,
+            limit: Some(dto.limit.max(1) as usize),
+            cursor: dto.cursor,
+        })
+    }
+}
+
+/// DTO for one page of run history returned to Flutter
+pub struct RunPageDto {
+    pub runs: Vec<RunSummaryDto>,
+    pub next_cursor: Option<String>,
+}
+
+impl From<RunPage> for RunPageDto {
+    fn from(page: RunPage) -> Self {
+        Self {
+            runs: page.runs.into_iter().map(|r| r.into()).collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+/// DTO for a run in the trash returned to Flutter
+pub struct TrashedRunDto {
+    pub run: RunSummaryDto,
+    pub deleted_at_ms: i64,
+    /// When the run will be purged automatically
+    pub purge_at_ms: i64,
+}
+
+impl From<TrashedRun> for TrashedRunDto {
+    fn from(trashed: TrashedRun) -> Self {
+        let purge_at = trashed.deleted_at + chrono::Duration::days(TRASH_RETENTION_DAYS);
+
+        Self {
+            run: trashed.summary.into(),
+            deleted_at_ms: trashed.deleted_at.timestamp_millis(),
+            purge_at_ms: purge_at.timestamp_millis(),
+        }
+    }
+}
+
 /// DTO for returning full run to Flutter
 pub struct RunDetailDto {
     pub id: String,
@@ -154,14 +315,27 @@ pub fn create_run() -> Result<String, String> {
 /// Save a run (creates or updates)
 pub fn save_run(run_dto: RunDto) -> Result<(), String> {
     let mut run: Run = run_dto.into();
+    let db = get_db()?;
+
+    // RunDto doesn't carry metadata, pauses or laps, so keep whatever is already stored
+    if let Some(metadata) = db.get_run_metadata(&run.id).map_err(|e| e.to_string())? {
+        run.metadata = metadata;
+    }
+    run.pauses = db.get_run_pauses(&run.id).map_err(|e| e.to_string())?;
+    run.laps = db.get_run_laps(&run.id).map_err(|e| e.to_string())?;
 
     // Recalculate distance and pace
-    run.distance_meters = geo::total_distance(&run.points);
-    if run.distance_meters > 0.0 && run.duration_ms > 0 {
-        run.avg_pace_sec_per_km = Some(geo::calculate_pace(run.distance_meters, run.duration_ms));
+    if run.pauses.is_empty() {
+        run.distance_meters = geo::total_distance(&run.points);
+        if run.distance_meters > 0.0 && run.duration_ms > 0 {
+            run.avg_pace_sec_per_km =
+                Some(geo::calculate_pace(run.distance_meters, run.duration_ms));
+        }
+    } else {
+        run.recalculate_stats();
     }
 
-    get_db()?.save_run(&run).map_err(|e| e.to_string())
+    db.save_run(&run).map_err(|e| e.to_string())
 }
 
 /// Get a run by ID
@@ -172,19 +346,199 @@ pub fn get_run(id: String) -> Result<Option<RunDetailDto>, String> {
         .map_err(|e| e.to_string())
 }
 
-/// Get all runs (summaries only)
+/// DTO for a lap of a run returned to Flutter
+pub struct LapDto {
+    pub start_time_ms: i64,
+    pub end_time_ms: i64,
+    pub duration_ms: i64,
+    pub distance_meters: f64,
+    pub avg_pace_sec_per_km: Option<f64>,
+    /// Workout step the lap was: "warmup", "work", "recovery" or "cooldown"
+    pub step_kind: Option<String>,
+    pub target_pace_sec_per_km: Option<f64>,
+}
+
+impl From<Lap> for LapDto {
+    fn from(lap: Lap) -> Self {
+        let duration_ms = lap.duration_ms();
+        Self {
+            start_time_ms: lap.start_time.timestamp_millis(),
+            end_time_ms: lap.end_time.timestamp_millis(),
+            duration_ms,
+            distance_meters: lap.distance_meters,
+            avg_pace_sec_per_km: (lap.distance_meters > 0.0 && duration_ms > 0)
+                .then(|| geo::calculate_pace(lap.distance_meters, duration_ms)),
+            step_kind: lap.step_kind.map(|k| k.as_str().to_string()),
+            target_pace_sec_per_km: lap.target_pace_sec_per_km,
+        }
+    }
+}
+
+/// Get a run's laps, in order
+pub fn get_run_laps(run_id: String) -> Result<Vec<LapDto>, String> {
+    let laps = get_db()?.get_run_laps(&run_id).map_err(|e| e.to_string())?;
+    Ok(laps.into_iter().map(|l| l.into()).collect())
+}
+
+/// Get a run's notes, type, tags, perceived exertion and weather
+pub fn get_run_metadata(run_id: String) -> Result<Option<RunMetadataDto>, String> {
+    get_db()?
+        .get_run_metadata(&run_id)
+        .map(|opt| opt.map(|m| m.into()))
+        .map_err(|e| e.to_string())
+}
+
+/// Edit a run's name and metadata without resubmitting its GPS points
+pub fn update_run_metadata(
+    run_id: String,
+    patch: RunMetadataPatchDto,
+) -> Result<RunMetadataDto, String> {
+    let patch: RunMetadataPatch = patch.try_into()?;
+    let db = get_db()?;
+
+    if !db
+        .update_run_metadata(&run_id, &patch)
+        .map_err(|e| e.to_string())?
+    {
+        return Err("Run not found".to_string());
+    }
+
+    db.get_run_metadata(&run_id)
+        .map_err(|e| e.to_string())?
+        .map(|m| m.into())
+        .ok_or_else(|| "Run not found".to_string())
+}
+
+/// Get all runs (summaries only), newest first.
+/// Loads every run; history screens should page through `query_runs` instead.
 pub fn get_all_runs() -> Result<Vec<RunSummaryDto>, String> {
+    let query = RunQuery {
+        limit: None,
+        ..Default::default()
+    };
+
+    get_db()?
+        .query_runs(&query)
+        .map(|page| page.runs.into_iter().map(|r| r.into()).collect())
+        .map_err(|e| e.to_string())
+}
+
+/// Query run history with filters, ordering and cursor pagination
+pub fn query_runs(query: RunQueryDto) -> Result<RunPageDto, String> {
+    let query: RunQuery = query.try_into()?;
+
     get_db()?
-        .get_all_runs()
+        .query_runs(&query)
+        .map(|page| page.into())
+        .map_err(|e| e.to_string())
+}
+
+/// Other runs on the same route as this one (same start, finish and path),
+/// fastest first. Handy for picking a recorded run to race as a banshee.
+pub fn find_runs_on_same_route(run_id: String) -> Result<Vec<RunSummaryDto>, String> {
+    get_db()?
+        .find_runs_on_same_route(&run_id)
         .map(|runs| runs.into_iter().map(|r| r.into()).collect())
         .map_err(|e| e.to_string())
 }
 
-/// Delete a run by ID
+/// Move a run to the trash (undo with `restore_run`)
 pub fn delete_run(id: String) -> Result<bool, String> {
     get_db()?.delete_run(&id).map_err(|e| e.to_string())
 }
 
+/// Restore a run from the trash
+pub fn restore_run(id: String) -> Result<bool, String> {
+    get_db()?.restore_run(&id).map_err(|e| e.to_string())
+}
+
+/// List runs in the trash, most recently deleted first
+pub fn list_trash() -> Result<Vec<TrashedRunDto>, String> {
+    get_db()?
+        .list_trash()
+        .map(|runs| runs.into_iter().map(|r| r.into()).collect())
+        .map_err(|e| e.to_string())
+}
+
+/// Permanently delete a run from the trash
+pub fn purge_run(id: String) -> Result<bool, String> {
+    get_db()?.purge_run(&id).map_err(|e| e.to_string())
+}
+
+/// Permanently delete everything in the trash, returning the number of runs purged
+pub fn empty_trash() -> Result<u32, String> {
+    get_db()?
+        .empty_trash()
+        .map(|n| n as u32)
+        .map_err(|e| e.to_string())
+}
+
+/// Merge several runs into one, recording the gaps between them as pauses.
+/// The originals are moved to the trash.
+pub fn merge_runs(ids: Vec<String>) -> Result<RunDetailDto, String> {
+    get_db()?
+        .merge_runs(&ids)
+        .map(|run| run.into())
+        .map_err(|e| e.to_string())
+}
+
+/// Split a run in two at the given timestamp. The original is moved to the trash.
+pub fn split_run(id: String, at_timestamp_ms: i64) -> Result<Vec<RunDetailDto>, String> {
+    let at = DateTime::from_timestamp_millis(at_timestamp_ms)
+        .ok_or_else(|| "Invalid timestamp".to_string())?;
+
+    let (before, after) = get_db()?.split_run(&id, at).map_err(|e| e.to_string())?;
+    Ok(vec![before.into(), after.into()])
+}
+
+/// Trim time off a run's start and end (e.g. after forgetting to stop).
+/// The original stays recoverable with `revert_run_edit` until `confirm_run_edit`.
+pub fn trim_run(
+    run_id: String,
+    start_offset_ms: i64,
+    end_offset_ms: i64,
+) -> Result<RunDetailDto, String> {
+    get_db()?
+        .trim_run(&run_id, start_offset_ms, end_offset_ms)
+        .map(|run| run.into())
+        .map_err(|e| e.to_string())
+}
+
+/// Trim distance off a run's start and end.
+/// The original stays recoverable with `revert_run_edit` until `confirm_run_edit`.
+pub fn trim_run_by_distance(
+    run_id: String,
+    start_m: f64,
+    end_m: f64,
+) -> Result<RunDetailDto, String> {
+    get_db()?
+        .trim_run_by_distance(&run_id, start_m, end_m)
+        .map(|run| run.into())
+        .map_err(|e| e.to_string())
+}
+
+/// Check whether a run has an unconfirmed edit
+pub fn has_pending_run_edit(run_id: String) -> Result<bool, String> {
+    get_db()?
+        .has_pending_edit(&run_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Keep a run's edits and discard the original
+pub fn confirm_run_edit(run_id: String) -> Result<bool, String> {
+    get_db()?
+        .confirm_run_edit(&run_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Undo a run's unconfirmed edits, returning the restored run
+pub fn revert_run_edit(run_id: String) -> Result<Option<RunDetailDto>, String> {
+    get_db()?
+        .revert_run_edit(&run_id)
+        .map(|opt| opt.map(|r| r.into()))
+        .map_err(|e| e.to_string())
+}
+
 /// Get total run count
 pub fn get_run_count() -> Result<i64, String> {
     get_db()?.run_count().map_err(|e| e.to_string())
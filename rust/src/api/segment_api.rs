@@ -0,0 +1,181 @@
+use crate::geo::segments::segment_on_track;
+use crate::geo::IndexedTrack;
+use crate::models::{Segment, SegmentEffort};
+
+use super::route_api::route_course;
+use super::run_api::get_db;
+
+/// DTO for a segment between two gates
+pub struct SegmentDto {
+    pub id: String,
+    pub name: String,
+    pub start_lat: f64,
+    pub start_lon: f64,
+    pub end_lat: f64,
+    pub end_lon: f64,
+    pub distance_meters: f64,
+    pub created_at_ms: i64,
+    /// Fastest effort, None if the segment hasn't been run yet
+    pub best_elapsed_ms: Option<i64>,
+    pub effort_count: u32,
+}
+
+impl SegmentDto {
+    fn new(segment: Segment, leaderboard: &[SegmentEffort]) -> Self {
+        Self {
+            id: segment.id,
+            name: segment.name,
+            start_lat: segment.start_gate.lat,
+            start_lon: segment.start_gate.lon,
+            end_lat: segment.end_gate.lat,
+            end_lon: segment.end_gate.lon,
+            distance_meters: segment.distance_meters,
+            created_at_ms: segment.created_at.timestamp_millis(),
+            best_elapsed_ms: leaderboard.first().map(|e| e.elapsed_ms),
+            effort_count: leaderboard.len() as u32,
+        }
+    }
+}
+
+/// DTO for one effort on a segment
+pub struct SegmentEffortDto {
+    pub segment_id: String,
+    pub run_id: String,
+    /// Place on the segment's leaderboard (1 = fastest)
+    pub rank: u32,
+    pub start_time_ms: i64,
+    pub elapsed_ms: i64,
+    pub distance_meters: f64,
+    pub pace_sec_per_km: f64,
+}
+
+impl SegmentEffortDto {
+    fn new(effort: SegmentEffort, rank: usize) -> Self {
+        Self {
+            pace_sec_per_km: crate::geo::calculate_pace(effort.distance_meters, effort.elapsed_ms),
+            segment_id: effort.segment_id,
+            run_id: effort.run_id,
+            rank: rank as u32,
+            start_time_ms: effort.start_time.timestamp_millis(),
+            elapsed_ms: effort.elapsed_ms,
+            distance_meters: effort.distance_meters,
+        }
+    }
+}
+
+fn segment_dto(segment: Segment) -> Result<SegmentDto, String> {
+    let leaderboard = get_db()?
+        .segment_leaderboard(&segment.id, None)
+        .map_err(|e| e.to_string())?;
+    Ok(SegmentDto::new(segment, &leaderboard))
+}
+
+/// Save a segment over part of a track and find efforts in every stored run
+fn save_new_segment(
+    name: String,
+    track: &IndexedTrack,
+    start_distance_m: f64,
+    end_distance_m: f64,
+) -> Result<SegmentDto, String> {
+    let segment = segment_on_track(name, track, start_distance_m, end_distance_m)
+        .map_err(|e| e.to_string())?;
+    get_db()?
+        .save_segment(&segment)
+        .map_err(|e| e.to_string())?;
+    segment_dto(segment)
+}
+
+/// Create a segment on a saved route, from `start_distance_m` to
+/// `end_distance_m` along it. Every stored run is scanned for efforts.
+pub fn create_segment_from_route(
+    route_id: String,
+    name: String,
+    start_distance_m: f64,
+    end_distance_m: f64,
+) -> Result<SegmentDto, String> {
+    let route = route_course(&route_id)?;
+    save_new_segment(name, &route.track, start_distance_m, end_distance_m)
+}
+
+/// Create a segment on part of a recorded run, from `start_distance_m` to
+/// `end_distance_m` along it. Every stored run is scanned for efforts.
+pub fn create_segment_from_run(
+    run_id: String,
+    name: String,
+    start_distance_m: f64,
+    end_distance_m: f64,
+) -> Result<SegmentDto, String> {
+    let run = get_db()?
+        .get_run(&run_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Run not found".to_string())?;
+    let track = IndexedTrack::new(run.points);
+    save_new_segment(name, &track, start_distance_m, end_distance_m)
+}
+
+/// Get a segment
+pub fn get_segment(segment_id: String) -> Result<Option<SegmentDto>, String> {
+    get_db()?
+        .get_segment(&segment_id)
+        .map_err(|e| e.to_string())?
+        .map(segment_dto)
+        .transpose()
+}
+
+/// List segments by name
+pub fn list_segments() -> Result<Vec<SegmentDto>, String> {
+    let segments = get_db()?.list_segments().map_err(|e| e.to_string())?;
+    segments.into_iter().map(segment_dto).collect()
+}
+
+/// Rename a segment, returning false if it doesn't exist
+pub fn rename_segment(segment_id: String, name: String) -> Result<bool, String> {
+    get_db()?
+        .rename_segment(&segment_id, &name)
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a segment and its leaderboard, returning false if it didn't exist
+pub fn delete_segment(segment_id: String) -> Result<bool, String> {
+    get_db()?
+        .delete_segment(&segment_id)
+        .map_err(|e| e.to_string())
+}
+
+/// A segment's leaderboard, fastest first (all efforts if no limit)
+pub fn get_segment_leaderboard(
+    segment_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<SegmentEffortDto>, String> {
+    let efforts = get_db()?
+        .segment_leaderboard(&segment_id, limit.map(|l| l as usize))
+        .map_err(|e| e.to_string())?;
+    Ok(efforts
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| SegmentEffortDto::new(e, i + 1))
+        .collect())
+}
+
+/// Segment efforts during a run, in the order they were run, with their
+/// place on each segment's leaderboard
+pub fn get_run_segment_efforts(run_id: String) -> Result<Vec<SegmentEffortDto>, String> {
+    let db = get_db()?;
+    let efforts = db
+        .get_run_segment_efforts(&run_id)
+        .map_err(|e| e.to_string())?;
+
+    efforts
+        .into_iter()
+        .map(|effort| {
+            let leaderboard = db
+                .segment_leaderboard(&effort.segment_id, None)
+                .map_err(|e| e.to_string())?;
+            let rank = leaderboard
+                .iter()
+                .position(|e| e == &effort)
+                .map_or(leaderboard.len() + 1, |i| i + 1);
+            Ok(SegmentEffortDto::new(effort, rank))
+        })
+        .collect()
+}
@@ -1,5 +1,7 @@
 use crate::geo::{self, pace};
 
+use super::run_api::get_db;
+
 /// Split information DTO for Flutter
 pub struct SplitDto {
     pub number: i32,
@@ -25,6 +27,43 @@ impl From<pace::Split> for SplitDto {
     }
 }
 
+/// Best effort DTO for Flutter
+pub struct BestEffortDto {
+    pub name: String,
+    pub distance_m: f64,
+    pub duration_ms: i64,
+    pub start_offset_ms: i64,
+    pub pace_sec_per_km: f64,
+    pub pace_formatted: String,
+}
+
+impl From<pace::BestEffort> for BestEffortDto {
+    fn from(effort: pace::BestEffort) -> Self {
+        Self {
+            pace_formatted: pace::format_pace(effort.pace_sec_per_km),
+            name: effort.name,
+            distance_m: effort.distance_m,
+            duration_ms: effort.duration_ms,
+            start_offset_ms: effort.start_offset_ms,
+            pace_sec_per_km: effort.pace_sec_per_km,
+        }
+    }
+}
+
+/// Get the fastest 400m, 1K, mile, 5K, ... within a run.
+/// Computed from the stored track, so it reflects any trims.
+pub fn get_best_efforts(run_id: String) -> Result<Vec<BestEffortDto>, String> {
+    let run = get_db()?
+        .get_run(&run_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Run not found".to_string())?;
+
+    Ok(pace::best_efforts(&run.points)
+        .into_iter()
+        .map(|e| e.into())
+        .collect())
+}
+
 /// Calculate distance between two GPS points
 #[flutter_rust_bridge::frb(sync)]
 pub fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
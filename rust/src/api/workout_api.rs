@@ -0,0 +1,347 @@
+use crate::banshee::{Registry, WorkoutEvent, WorkoutEventKind, WorkoutProgress, WorkoutSession};
+use crate::frb_generated::StreamSink;
+use crate::models::{RunMetadataPatch, RunType, StepKind, StepLength, Workout, WorkoutStep};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use super::run_api::{get_db, LapDto};
+
+/// DTO for one workout step. Give exactly one of `distance_meters` and
+/// `duration_ms`; leave the pace out for a step without a pacer.
+pub struct WorkoutStepDto {
+    /// "warmup", "work", "recovery" or "cooldown"
+    pub kind: String,
+    pub distance_meters: Option<f64>,
+    pub duration_ms: Option<i64>,
+    pub pace_sec_per_km: Option<f64>,
+}
+
+impl TryFrom<WorkoutStepDto> for WorkoutStep {
+    type Error = String;
+
+    fn try_from(dto: WorkoutStepDto) -> Result<Self, Self::Error> {
+        let kind =
+            StepKind::parse(&dto.kind).ok_or_else(|| format!("Invalid step kind: {}", dto.kind))?;
+        let length = match (dto.distance_meters, dto.duration_ms) {
+            (Some(meters), None) => StepLength::Distance { meters },
+            (None, Some(ms)) => StepLength::Time { ms },
+            _ => return Err("A step needs exactly one of a distance or a duration".to_string()),
+        };
+
+        Ok(WorkoutStep {
+            kind,
+            length,
+            pace_sec_per_km: dto.pace_sec_per_km,
+        })
+    }
+}
+
+impl From<WorkoutStep> for WorkoutStepDto {
+    fn from(step: WorkoutStep) -> Self {
+        let (distance_meters, duration_ms) = match step.length {
+            StepLength::Distance { meters } => (Some(meters), None),
+            StepLength::Time { ms } => (None, Some(ms)),
+        };
+        Self {
+            kind: step.kind.as_str().to_string(),
+            distance_meters,
+            duration_ms,
+            pace_sec_per_km: step.pace_sec_per_km,
+        }
+    }
+}
+
+/// DTO for a structured workout: warmup, `repeats` × (work, recovery), cooldown
+pub struct WorkoutDto {
+    pub id: String,
+    pub name: String,
+    pub warmup: Option<WorkoutStepDto>,
+    pub repeats: u32,
+    pub work: WorkoutStepDto,
+    pub recovery: Option<WorkoutStepDto>,
+    pub cooldown: Option<WorkoutStepDto>,
+    pub created_at_ms: i64,
+}
+
+impl From<Workout> for WorkoutDto {
+    fn from(workout: Workout) -> Self {
+        Self {
+            id: workout.id,
+            name: workout.name,
+            warmup: workout.warmup.map(|s| s.into()),
+            repeats: workout.repeats,
+            work: workout.work.into(),
+            recovery: workout.recovery.map(|s| s.into()),
+            cooldown: workout.cooldown.map(|s| s.into()),
+            created_at_ms: workout.created_at.timestamp_millis(),
+        }
+    }
+}
+
+fn step_from_dto(step: Option<WorkoutStepDto>) -> Result<Option<WorkoutStep>, String> {
+    step.map(WorkoutStep::try_from).transpose()
+}
+
+/// Build and check a workout from its parts
+fn workout_from_parts(
+    name: String,
+    warmup: Option<WorkoutStepDto>,
+    repeats: u32,
+    work: WorkoutStepDto,
+    recovery: Option<WorkoutStepDto>,
+    cooldown: Option<WorkoutStepDto>,
+) -> Result<Workout, String> {
+    let workout = Workout::new(
+        name,
+        step_from_dto(warmup)?,
+        repeats,
+        work.try_into()?,
+        step_from_dto(recovery)?,
+        step_from_dto(cooldown)?,
+    );
+    workout.validate().map_err(|e| e.to_string())?;
+    Ok(workout)
+}
+
+/// Save a new structured workout
+pub fn create_workout(
+    name: String,
+    warmup: Option<WorkoutStepDto>,
+    repeats: u32,
+    work: WorkoutStepDto,
+    recovery: Option<WorkoutStepDto>,
+    cooldown: Option<WorkoutStepDto>,
+) -> Result<WorkoutDto, String> {
+    let workout = workout_from_parts(name, warmup, repeats, work, recovery, cooldown)?;
+    get_db()?
+        .save_workout(&workout)
+        .map_err(|e| e.to_string())?;
+    Ok(workout.into())
+}
+
+/// Change a saved workout's name and steps
+pub fn update_workout(
+    workout_id: String,
+    name: String,
+    warmup: Option<WorkoutStepDto>,
+    repeats: u32,
+    work: WorkoutStepDto,
+    recovery: Option<WorkoutStepDto>,
+    cooldown: Option<WorkoutStepDto>,
+) -> Result<WorkoutDto, String> {
+    let existing = get_saved_workout(&workout_id)?;
+    let workout = Workout {
+        id: existing.id,
+        created_at: existing.created_at,
+        ..workout_from_parts(name, warmup, repeats, work, recovery, cooldown)?
+    };
+    get_db()?
+        .save_workout(&workout)
+        .map_err(|e| e.to_string())?;
+    Ok(workout.into())
+}
+
+fn get_saved_workout(workout_id: &str) -> Result<Workout, String> {
+    get_db()?
+        .get_workout(workout_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Workout not found".to_string())
+}
+
+/// Get a saved workout
+pub fn get_workout(workout_id: String) -> Result<Option<WorkoutDto>, String> {
+    let workout = get_db()?
+        .get_workout(&workout_id)
+        .map_err(|e| e.to_string())?;
+    Ok(workout.map(|w| w.into()))
+}
+
+/// List saved workouts by name
+pub fn list_workouts() -> Result<Vec<WorkoutDto>, String> {
+    let workouts = get_db()?.list_workouts().map_err(|e| e.to_string())?;
+    Ok(workouts.into_iter().map(|w| w.into()).collect())
+}
+
+/// Delete a saved workout, returning false if it didn't exist
+pub fn delete_workout(workout_id: String) -> Result<bool, String> {
+    get_db()?
+        .delete_workout(&workout_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Workout step changes pushed to Dart
+pub enum WorkoutEventKindDto {
+    StepStarted,
+    WorkoutFinished,
+}
+
+/// DTO for a workout step change, timed at the step boundary
+pub struct WorkoutEventDto {
+    pub kind: WorkoutEventKindDto,
+    /// Index of the step in the expanded workout (repeats unrolled)
+    pub step_index: u32,
+    pub step_count: u32,
+    pub step: WorkoutStepDto,
+    pub runner_distance_meters: f64,
+    pub runner_elapsed_ms: i64,
+}
+
+impl WorkoutEventDto {
+    fn new(event: WorkoutEvent, step_count: usize) -> Self {
+        Self {
+            kind: match event.kind {
+                WorkoutEventKind::StepStarted => WorkoutEventKindDto::StepStarted,
+                WorkoutEventKind::WorkoutFinished => WorkoutEventKindDto::WorkoutFinished,
+            },
+            step_index: event.step_index as u32,
+            step_count: step_count as u32,
+            step: event.step.into(),
+            runner_distance_meters: event.runner_distance_m,
+            runner_elapsed_ms: event.runner_elapsed_ms,
+        }
+    }
+}
+
+/// DTO for progress through a workout
+pub struct WorkoutProgressDto {
+    pub step_index: u32,
+    pub step_count: u32,
+    pub step: WorkoutStepDto,
+    pub step_elapsed_ms: i64,
+    pub step_distance_meters: f64,
+    /// Fraction of the step done, 0 to 1
+    pub step_fraction: f64,
+    /// Distance the step's pacer is ahead (positive = pacer ahead); None
+    /// for steps without a pace
+    pub pacer_gap_meters: Option<f64>,
+    pub finished: bool,
+}
+
+impl WorkoutProgressDto {
+    fn new(progress: WorkoutProgress, step_count: usize) -> Self {
+        Self {
+            step_index: progress.step_index as u32,
+            step_count: step_count as u32,
+            step: progress.step.into(),
+            step_elapsed_ms: progress.step_elapsed_ms,
+            step_distance_meters: progress.step_distance_m,
+            step_fraction: progress.step_fraction,
+            pacer_gap_meters: progress.pacer_gap_m,
+            finished: progress.finished,
+        }
+    }
+}
+
+fn workout_sessions() -> &'static Registry<Mutex<WorkoutSession>> {
+    static SESSIONS: OnceLock<Registry<Mutex<WorkoutSession>>> = OnceLock::new();
+    SESSIONS.get_or_init(Registry::new)
+}
+
+/// Event streams keyed by workout session ID
+fn workout_watchers() -> &'static Mutex<HashMap<String, StreamSink<WorkoutEventDto>>> {
+    static WATCHERS: OnceLock<Mutex<HashMap<String, StreamSink<WorkoutEventDto>>>> =
+        OnceLock::new();
+    WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn get_workout_session(session_id: &str) -> Result<Arc<Mutex<WorkoutSession>>, String> {
+    workout_sessions()
+        .get(session_id)
+        .ok_or_else(|| "Workout session not found".to_string())
+}
+
+/// Start running a saved workout. Returns a session ID for
+/// `update_workout_session`; the first update starts the first step.
+pub fn start_workout_session(workout_id: String) -> Result<String, String> {
+    start_session_for(&get_saved_workout(&workout_id)?)
+}
+
+/// Start a session for any workout, saved or not
+pub(crate) fn start_session_for(workout: &Workout) -> Result<String, String> {
+    let session = WorkoutSession::new(workout).map_err(|e| e.to_string())?;
+    Ok(workout_sessions().insert(Mutex::new(session)))
+}
+
+/// Stream a workout session's step changes to Dart. Events are raised as
+/// `update_workout_session` is called. Watching again replaces the stream.
+pub fn watch_workout_events(
+    session_id: String,
+    sink: StreamSink<WorkoutEventDto>,
+) -> Result<(), String> {
+    get_workout_session(&session_id)?;
+    workout_watchers().lock().unwrap().insert(session_id, sink);
+    Ok(())
+}
+
+/// Feed the runner's progress to a workout session, moving through the
+/// steps and pacing the current one
+#[flutter_rust_bridge::frb(sync)]
+pub fn update_workout_session(
+    session_id: String,
+    runner_distance_m: f64,
+    runner_elapsed_ms: i64,
+) -> Result<WorkoutProgressDto, String> {
+    let session = get_workout_session(&session_id)?;
+    let mut session = session.lock().unwrap();
+    let events = session.update(runner_distance_m, runner_elapsed_ms);
+    let step_count = session.steps().len();
+
+    let mut watchers = workout_watchers().lock().unwrap();
+    if let Some(sink) = watchers.get(&session_id) {
+        let delivered = events
+            .into_iter()
+            .all(|event| sink.add(WorkoutEventDto::new(event, step_count)).is_ok());
+        // Dart stopped listening
+        if !delivered {
+            watchers.remove(&session_id);
+        }
+    }
+
+    let progress = session.progress().expect("the session was just updated");
+    Ok(WorkoutProgressDto::new(progress, step_count))
+}
+
+/// End a workout session and return its laps, one per step started. With a
+/// run ID the laps are stored with the run (timed from its start), and the
+/// run is marked as a workout unless it already has a type. Without one
+/// they're timed as if the latest update was now.
+pub fn finish_workout_session(
+    session_id: String,
+    run_id: Option<String>,
+) -> Result<Vec<LapDto>, String> {
+    let session = get_workout_session(&session_id)?;
+    let db = get_db()?;
+
+    let laps = match &run_id {
+        Some(run_id) => {
+            let start_time = db
+                .get_run(run_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "Run not found".to_string())?
+                .start_time;
+            let laps = session.lock().unwrap().laps(start_time);
+            db.set_run_laps(run_id, &laps).map_err(|e| e.to_string())?;
+
+            let metadata = db.get_run_metadata(run_id).map_err(|e| e.to_string())?;
+            if metadata.is_some_and(|m| m.run_type.is_none()) {
+                let patch = RunMetadataPatch {
+                    run_type: Some(Some(RunType::Workout)),
+                    ..RunMetadataPatch::default()
+                };
+                db.update_run_metadata(run_id, &patch)
+                    .map_err(|e| e.to_string())?;
+            }
+            laps
+        }
+        None => {
+            let session = session.lock().unwrap();
+            let start_time =
+                chrono::Utc::now() - chrono::Duration::milliseconds(session.elapsed_ms());
+            session.laps(start_time)
+        }
+    };
+
+    workout_watchers().lock().unwrap().remove(&session_id);
+    workout_sessions().remove(&session_id);
+    Ok(laps.into_iter().map(|l| l.into()).collect())
+}
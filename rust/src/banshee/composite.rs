@@ -0,0 +1,178 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::geo::IndexedTrack;
+use crate::models::GpsPoint;
+
+/// Fastest split found for a composite ghost
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BestSplit {
+    /// Index of the input track the split came from
+    pub track_index: usize,
+    pub duration_ms: i64,
+}
+
+/// A composite ghost's track and where each split came from
+#[derive(Debug, Clone)]
+pub struct CompositeTrack {
+    pub points: Vec<GpsPoint>,
+    pub splits: Vec<BestSplit>,
+}
+
+/// Stitch together a "best-of" ghost from the fastest time over each split
+/// across `tracks`.
+///
+/// The first track is the reference: the ghost follows its path up to
+/// `limit_m` (or its end), and within each split keeps its pacing shape,
+/// squeezed into the best time any track covering the split managed.
+/// Tracks should cover the same route for the splits to be comparable.
+pub fn build_composite_track(
+    tracks: &[IndexedTrack],
+    split_distance_m: f64,
+    limit_m: Option<f64>,
+    start_time: DateTime<Utc>,
+) -> anyhow::Result<CompositeTrack> {
+    anyhow::ensure!(split_distance_m > 0.0, "Invalid split distance");
+    let reference = tracks
+        .first()
+        .filter(|t| t.points().len() >= 2 && t.total_distance() > 0.0)
+        .ok_or_else(|| anyhow::anyhow!("The reference run has no GPS track"))?;
+
+    let total = limit_m.map_or(reference.total_distance(), |limit| {
+        limit.min(reference.total_distance())
+    });
+    anyhow::ensure!(total > 0.0, "Invalid distance");
+
+    // Split boundaries along the reference: 0, s, 2s, ..., total
+    let mut boundaries: Vec<f64> = (0..)
+        .map(|i| i as f64 * split_distance_m)
+        .take_while(|&d| d < total)
+        .collect();
+    boundaries.push(total);
+
+    let split_time = |track: &IndexedTrack, from: f64, to: f64| {
+        Some(track.time_at_distance(to)? - track.time_at_distance(from)?)
+    };
+    let splits: Vec<BestSplit> = boundaries
+        .windows(2)
+        .map(|w| {
+            tracks
+                .iter()
+                .enumerate()
+                .filter_map(|(track_index, track)| {
+                    split_time(track, w[0], w[1]).map(|duration_ms| BestSplit {
+                        track_index,
+                        duration_ms,
+                    })
+                })
+                .min_by_key(|s| s.duration_ms)
+                .expect("the reference covers every split")
+        })
+        .collect();
+
+    // Ghost time at a reference distance: the best times of the splits
+    // before it, plus its share of the current split's best time
+    let mut split_starts_ms = vec![0];
+    for split in &splits {
+        split_starts_ms.push(split_starts_ms.last().unwrap() + split.duration_ms);
+    }
+    let ghost_time_at = |distance: f64| -> i64 {
+        let i = boundaries
+            .partition_point(|&b| b <= distance)
+            .saturating_sub(1)
+            .min(splits.len() - 1);
+        let reference_start = reference.time_at_distance(boundaries[i]).unwrap_or(0);
+        let reference_ms = split_time(reference, boundaries[i], boundaries[i + 1]).unwrap_or(0);
+        let into_split = reference.time_at_distance(distance).unwrap_or(0) - reference_start;
+        let fraction = if reference_ms > 0 {
+            into_split as f64 / reference_ms as f64
+        } else {
+            (distance - boundaries[i]) / (boundaries[i + 1] - boundaries[i])
+        };
+        split_starts_ms[i] + (splits[i].duration_ms as f64 * fraction.clamp(0.0, 1.0)) as i64
+    };
+
+    let retimed = |point: &GpsPoint, distance: f64| GpsPoint {
+        timestamp: start_time + Duration::milliseconds(ghost_time_at(distance)),
+        ..point.clone()
+    };
+    let mut points: Vec<GpsPoint> = reference
+        .points()
+        .iter()
+        .zip(reference.distances())
+        .take_while(|(_, &d)| d < total)
+        .map(|(p, &d)| retimed(p, d))
+        .collect();
+
+    // Finish exactly at the last boundary
+    let finish = reference
+        .position_at_distance(total)
+        .expect("the reference has points");
+    points.push(retimed(&finish, total));
+
+    Ok(CompositeTrack { points, splits })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Track heading north with one point per ~111m, timed per leg in seconds
+    fn track(leg_secs: &[i64]) -> IndexedTrack {
+        let start = Utc::now();
+        let mut elapsed = 0;
+        let mut points = vec![GpsPoint::new(51.5, -0.1, start)];
+        for (i, secs) in leg_secs.iter().enumerate() {
+            elapsed += secs;
+            points.push(GpsPoint::new(
+                51.5 + 0.001 * (i + 1) as f64,
+                -0.1,
+                start + Duration::seconds(elapsed),
+            ));
+        }
+        IndexedTrack::new(points)
+    }
+
+    #[test]
+    fn test_composite_takes_best_split() {
+        // ~445m each: one run fast then slow, the other slow then fast
+        let fast_start = track(&[30, 30, 60, 60]);
+        let fast_finish = track(&[60, 60, 30, 30]);
+        let half = fast_start.total_distance() / 2.0;
+
+        let composite =
+            build_composite_track(&[fast_start.clone(), fast_finish], half, None, Utc::now())
+                .unwrap();
+
+        assert_eq!(composite.splits.len(), 2);
+        assert_eq!(composite.splits[0].track_index, 0);
+        assert_eq!(composite.splits[1].track_index, 1);
+
+        let ghost = IndexedTrack::new(composite.points);
+        assert_eq!(ghost.duration_ms(), 120_000);
+        assert!((ghost.total_distance() - fast_start.total_distance()).abs() < 1e-6);
+        assert!((ghost.time_at_distance(half).unwrap() - 60_000).abs() <= 5);
+    }
+
+    #[test]
+    fn test_composite_limit_and_short_runs() {
+        let reference = track(&[40, 40, 40, 40]);
+        // Faster but too short to cover the second split
+        let short = track(&[20, 20]);
+        let limit = 300.0;
+
+        let composite =
+            build_composite_track(&[reference, short], 200.0, Some(limit), Utc::now()).unwrap();
+
+        assert_eq!(composite.splits.len(), 2);
+        assert_eq!(composite.splits[0].track_index, 1);
+        assert_eq!(composite.splits[1].track_index, 0);
+        let ghost = IndexedTrack::new(composite.points);
+        assert!((ghost.total_distance() - limit).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_composite_needs_a_reference() {
+        assert!(build_composite_track(&[], 1000.0, None, Utc::now()).is_err());
+        assert!(build_composite_track(&[track(&[])], 1000.0, None, Utc::now()).is_err());
+    }
+}
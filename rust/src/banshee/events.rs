@@ -0,0 +1,380 @@
+/// Tuning for banshee race events
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventConfig {
+    /// Dead band around level: the lead only changes hands once the new
+    /// leader is this many meters ahead, so GPS jitter doesn't flip it
+    pub lead_hysteresis_m: f64,
+    /// Gaps (either way) that raise a `GapGrew` event when first passed
+    pub gap_thresholds_m: Vec<f64>,
+    /// A gap that had grown past a threshold counts as closed within this
+    pub gap_closed_m: f64,
+    /// Minimum time between two lead-change or two gap events for a banshee
+    pub cooldown_ms: i64,
+    /// Race distance; the runner (and pacers) finish here. None for open runs.
+    pub finish_distance_m: Option<f64>,
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            lead_hysteresis_m: 5.0,
+            gap_thresholds_m: vec![50.0, 100.0, 200.0],
+            gap_closed_m: 10.0,
+            cooldown_ms: 10_000,
+            finish_distance_m: None,
+        }
+    }
+}
+
+impl EventConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(self.lead_hysteresis_m >= 0.0, "Invalid lead hysteresis");
+        anyhow::ensure!(self.gap_closed_m >= 0.0, "Invalid gap-closed distance");
+        anyhow::ensure!(self.cooldown_ms >= 0, "Invalid cooldown");
+        anyhow::ensure!(
+            self.gap_thresholds_m.iter().all(|&t| t > self.gap_closed_m),
+            "Gap thresholds must be larger than the gap-closed distance"
+        );
+        if let Some(finish) = self.finish_distance_m {
+            anyhow::ensure!(finish > 0.0, "Invalid finish distance");
+        }
+        Ok(())
+    }
+}
+
+/// What happened
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BansheeEventKind {
+    /// The banshee passed the runner
+    Overtaken,
+    /// The runner passed the banshee
+    Overtook,
+    /// The gap grew past one of the configured thresholds
+    GapGrew {
+        threshold_m: f64,
+    },
+    /// A gap that had grown past a threshold closed again
+    GapClosed,
+    BansheeFinished,
+    RunnerFinished,
+}
+
+/// An event raised during a race
+#[derive(Debug, Clone, PartialEq)]
+pub struct BansheeEvent {
+    pub kind: BansheeEventKind,
+    /// Banshee the event is about (None for `RunnerFinished`)
+    pub banshee_index: Option<usize>,
+    /// Distance the banshee is ahead of the runner (positive = banshee ahead)
+    pub gap_m: f64,
+    pub runner_distance_m: f64,
+    pub runner_elapsed_ms: i64,
+}
+
+/// A banshee's progress at one runner sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BansheeSample {
+    /// Distance the banshee is ahead of the runner (positive = banshee ahead)
+    pub gap_m: f64,
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Leader {
+    Runner,
+    Banshee,
+}
+
+/// Event state for one banshee
+#[derive(Debug, Clone, Default)]
+struct BansheeTracker {
+    /// Who leads right now, after hysteresis
+    leader: Option<Leader>,
+    /// Who leads as far as the runner has been told; a change blocked by the
+    /// cooldown stays pending until the two agree again
+    announced_leader: Option<Leader>,
+    /// Index of the largest gap threshold announced and not yet fallen back from
+    announced_threshold: Option<usize>,
+    /// A threshold was passed and the gap hasn't closed since
+    gap_open: bool,
+    finished: bool,
+    last_lead_event_ms: Option<i64>,
+    last_gap_event_ms: Option<i64>,
+}
+
+/// Turns a stream of runner/banshee samples into race events. Unlike
+/// comparing two samples, it remembers who leads and which gaps were already
+/// announced, so nothing is missed between samples and nothing repeats.
+#[derive(Debug, Clone)]
+pub struct RaceEventTracker {
+    config: EventConfig,
+    banshees: Vec<BansheeTracker>,
+    runner_finished: bool,
+}
+
+impl RaceEventTracker {
+    pub fn new(banshee_count: usize, mut config: EventConfig) -> anyhow::Result<Self> {
+        config.validate()?;
+        config.gap_thresholds_m.sort_by(f64::total_cmp);
+        config.gap_thresholds_m.dedup();
+
+        Ok(Self {
+            config,
+            banshees: vec![BansheeTracker::default(); banshee_count],
+            runner_finished: false,
+        })
+    }
+
+    pub fn config(&self) -> &EventConfig {
+        &self.config
+    }
+
+    /// Feed the runner's progress and each banshee's sample (in race order),
+    /// returning the events raised by this sample
+    pub fn update(
+        &mut self,
+        runner_distance_m: f64,
+        runner_elapsed_ms: i64,
+        samples: &[BansheeSample],
+    ) -> Vec<BansheeEvent> {
+        let mut events = Vec::new();
+        let config = &self.config;
+        let cooled_down =
+            |last: Option<i64>| last.is_none_or(|t| runner_elapsed_ms - t >= config.cooldown_ms);
+
+        for (idx, (tracker, sample)) in self.banshees.iter_mut().zip(samples).enumerate() {
+            let mut emit = |kind| {
+                events.push(BansheeEvent {
+                    kind,
+                    banshee_index: Some(idx),
+                    gap_m: sample.gap_m,
+                    runner_distance_m,
+                    runner_elapsed_ms,
+                })
+            };
+
+            // Lead changes, ignoring the dead band around level
+            let leader = if sample.gap_m > config.lead_hysteresis_m {
+                Some(Leader::Banshee)
+            } else if sample.gap_m < -config.lead_hysteresis_m {
+                Some(Leader::Runner)
+            } else {
+                tracker.leader
+            };
+            tracker.leader = leader;
+            match (tracker.announced_leader, leader) {
+                // Who leads at the start isn't an event
+                (None, Some(_)) => tracker.announced_leader = leader,
+                (Some(before), Some(now))
+                    if before != now && cooled_down(tracker.last_lead_event_ms) =>
+                {
+                    emit(match now {
+                        Leader::Banshee => BansheeEventKind::Overtaken,
+                        Leader::Runner => BansheeEventKind::Overtook,
+                    });
+                    tracker.announced_leader = leader;
+                    tracker.last_lead_event_ms = Some(runner_elapsed_ms);
+                }
+                _ => {}
+            }
+
+            // Gap thresholds: announce the largest newly passed one, and
+            // re-arm thresholds once the gap falls back below them. Anything
+            // blocked by the cooldown is retried on the next sample.
+            let gap = sample.gap_m.abs();
+            let passed = config.gap_thresholds_m.iter().rposition(|&t| gap >= t);
+            if passed > tracker.announced_threshold {
+                if cooled_down(tracker.last_gap_event_ms) {
+                    let threshold_m = config.gap_thresholds_m[passed.unwrap()];
+                    emit(BansheeEventKind::GapGrew { threshold_m });
+                    tracker.last_gap_event_ms = Some(runner_elapsed_ms);
+                    tracker.announced_threshold = passed;
+                    tracker.gap_open = true;
+                }
+            } else if tracker.gap_open && gap <= config.gap_closed_m {
+                if cooled_down(tracker.last_gap_event_ms) {
+                    emit(BansheeEventKind::GapClosed);
+                    tracker.last_gap_event_ms = Some(runner_elapsed_ms);
+                    tracker.announced_threshold = None;
+                    tracker.gap_open = false;
+                }
+            } else {
+                let armed = config
+                    .gap_thresholds_m
+                    .iter()
+                    .rposition(|&t| gap >= t - config.lead_hysteresis_m);
+                tracker.announced_threshold = tracker.announced_threshold.min(armed);
+            }
+
+            if sample.finished && !tracker.finished {
+                emit(BansheeEventKind::BansheeFinished);
+                tracker.finished = true;
+            }
+        }
+
+        let finish = self.config.finish_distance_m;
+        if !self.runner_finished && finish.is_some_and(|f| runner_distance_m >= f) {
+            events.push(BansheeEvent {
+                kind: BansheeEventKind::RunnerFinished,
+                banshee_index: None,
+                gap_m: 0.0,
+                runner_distance_m,
+                runner_elapsed_ms,
+            });
+            self.runner_finished = true;
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(config: EventConfig) -> RaceEventTracker {
+        RaceEventTracker::new(1, config).unwrap()
+    }
+
+    fn kinds(events: &[BansheeEvent]) -> Vec<BansheeEventKind> {
+        events.iter().map(|e| e.kind).collect()
+    }
+
+    fn sample(gap_m: f64) -> [BansheeSample; 1] {
+        [BansheeSample {
+            gap_m,
+            finished: false,
+        }]
+    }
+
+    #[test]
+    fn test_lead_changes_with_hysteresis() {
+        let mut events = tracker(EventConfig {
+            cooldown_ms: 0,
+            ..EventConfig::default()
+        });
+
+        // Who leads at the start isn't an event
+        assert!(events.update(0.0, 0, &sample(8.0)).is_empty());
+        // Level-ish jitter inside the dead band doesn't flip the lead
+        assert!(events.update(10.0, 1000, &sample(-3.0)).is_empty());
+        assert!(events.update(20.0, 2000, &sample(2.0)).is_empty());
+
+        let passed = events.update(30.0, 3000, &sample(-6.0));
+        assert_eq!(kinds(&passed), vec![BansheeEventKind::Overtook]);
+        assert_eq!(passed[0].banshee_index, Some(0));
+        assert_eq!(passed[0].runner_elapsed_ms, 3000);
+
+        let caught = events.update(40.0, 4000, &sample(6.0));
+        assert_eq!(kinds(&caught), vec![BansheeEventKind::Overtaken]);
+    }
+
+    #[test]
+    fn test_cooldown_suppresses_flip_flops() {
+        let mut events = tracker(EventConfig::default());
+        events.update(0.0, 0, &sample(10.0));
+
+        assert_eq!(
+            kinds(&events.update(10.0, 1000, &sample(-10.0))),
+            vec![BansheeEventKind::Overtook]
+        );
+        // Back and forth within the cooldown: nothing
+        assert!(events.update(20.0, 2000, &sample(10.0)).is_empty());
+        assert!(events.update(30.0, 3000, &sample(-10.0)).is_empty());
+        // Leader changes again after the cooldown
+        assert_eq!(
+            kinds(&events.update(40.0, 12_000, &sample(10.0))),
+            vec![BansheeEventKind::Overtaken]
+        );
+    }
+
+    #[test]
+    fn test_change_inside_cooldown_fires_once_cooled_down() {
+        let mut events = tracker(EventConfig::default());
+        events.update(0.0, 0, &sample(-20.0));
+
+        assert_eq!(
+            kinds(&events.update(10.0, 1000, &sample(10.0))),
+            vec![BansheeEventKind::Overtaken]
+        );
+        // The runner retakes the lead inside the cooldown and keeps it
+        assert!(events.update(20.0, 2000, &sample(-10.0)).is_empty());
+        assert!(events.update(30.0, 5000, &sample(-8.0)).is_empty());
+        assert_eq!(
+            kinds(&events.update(40.0, 11_000, &sample(-8.0))),
+            vec![BansheeEventKind::Overtook]
+        );
+        assert!(events.update(50.0, 22_000, &sample(-8.0)).is_empty());
+
+        // Same for a gap threshold passed inside the cooldown
+        assert_eq!(
+            kinds(&events.update(100.0, 23_000, &sample(-60.0))),
+            vec![BansheeEventKind::GapGrew { threshold_m: 50.0 }]
+        );
+        assert!(events.update(150.0, 24_000, &sample(-120.0)).is_empty());
+        assert_eq!(
+            kinds(&events.update(200.0, 33_000, &sample(-120.0))),
+            vec![BansheeEventKind::GapGrew { threshold_m: 100.0 }]
+        );
+    }
+
+    #[test]
+    fn test_gap_thresholds_and_closing() {
+        let mut events = tracker(EventConfig {
+            cooldown_ms: 0,
+            ..EventConfig::default()
+        });
+        events.update(0.0, 0, &sample(-20.0));
+
+        // Jumping straight past two thresholds announces only the largest
+        assert_eq!(
+            kinds(&events.update(100.0, 1000, &sample(-120.0))),
+            vec![BansheeEventKind::GapGrew { threshold_m: 100.0 }]
+        );
+        // Hovering around a passed threshold doesn't repeat it
+        assert!(events.update(100.0, 2000, &sample(-98.0)).is_empty());
+        assert!(events.update(100.0, 3000, &sample(-101.0)).is_empty());
+        // Dropping clearly below re-arms it
+        assert!(events.update(100.0, 4000, &sample(-80.0)).is_empty());
+        assert_eq!(
+            kinds(&events.update(100.0, 5000, &sample(-105.0))),
+            vec![BansheeEventKind::GapGrew { threshold_m: 100.0 }]
+        );
+
+        assert_eq!(
+            kinds(&events.update(100.0, 6000, &sample(-8.0))),
+            vec![BansheeEventKind::GapClosed]
+        );
+        assert!(events.update(100.0, 7000, &sample(-4.0)).is_empty());
+    }
+
+    #[test]
+    fn test_finish_events_fire_once() {
+        let mut events = tracker(EventConfig {
+            finish_distance_m: Some(5000.0),
+            ..EventConfig::default()
+        });
+        let finished = [BansheeSample {
+            gap_m: 0.0,
+            finished: true,
+        }];
+
+        assert_eq!(
+            kinds(&events.update(4990.0, 1000, &finished)),
+            vec![BansheeEventKind::BansheeFinished]
+        );
+        let runner = events.update(5000.0, 2000, &finished);
+        assert_eq!(kinds(&runner), vec![BansheeEventKind::RunnerFinished]);
+        assert_eq!(runner[0].banshee_index, None);
+        assert!(events.update(5010.0, 3000, &finished).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let config = EventConfig {
+            gap_thresholds_m: vec![5.0],
+            ..EventConfig::default()
+        };
+        assert!(RaceEventTracker::new(1, config).is_err());
+    }
+}
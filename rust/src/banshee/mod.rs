@@ -0,0 +1,19 @@
+pub mod composite;
+pub mod events;
+pub mod pacer;
+pub mod race;
+pub mod registry;
+pub mod report;
+pub mod session;
+pub mod workout;
+
+pub use composite::{build_composite_track, BestSplit, CompositeTrack};
+pub use events::{BansheeEvent, BansheeEventKind, BansheeSample, EventConfig, RaceEventTracker};
+pub use pacer::{
+    project_finish, target_finish_plan, AiPacer, Course, CourseGeometry, FinishProjection,
+};
+pub use race::{LeaderboardEntry, Opponent, RaceBanshee, RaceSession, RaceStandings};
+pub use registry::Registry;
+pub use report::{build_race_report, REPORT_SPLIT_DISTANCE_M};
+pub use session::{BansheeSession, DistanceComparison};
+pub use workout::{WorkoutEvent, WorkoutEventKind, WorkoutProgress, WorkoutSession};
@@ -0,0 +1,596 @@
+use chrono::Utc;
+
+use crate::geo::distance::{bearing, destination_point, haversine_distance};
+use crate::geo::elevation::{grade_adjustment_factor, GradeSegment, GRADE_SAMPLE_SPACING_M};
+use crate::geo::IndexedTrack;
+use crate::models::{BansheeType, GpsPoint, PacePlan, PaceSegment};
+
+/// Runners need to cover this much ground before a finish projection is meaningful
+const MIN_PROJECTION_DISTANCE_M: f64 = 100.0;
+
+/// The runner has to move this far from the start before their initial bearing is trusted
+const INITIAL_BEARING_DISTANCE_M: f64 = 20.0;
+
+/// An AI pacer running at a constant target pace or following a pace plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiPacer {
+    /// Target pace in seconds per kilometer (the average pace for planned pacers)
+    pub target_pace_sec_per_km: f64,
+    /// Variable pace plan; None means a constant target pace
+    pub pace_plan: Option<PacePlan>,
+}
+
+impl AiPacer {
+    pub fn new(target_pace_sec_per_km: f64) -> anyhow::Result<Self> {
+        anyhow::ensure!(target_pace_sec_per_km > 0.0, "Invalid pace");
+        Ok(Self {
+            target_pace_sec_per_km,
+            pace_plan: None,
+        })
+    }
+
+    /// Create a pacer that follows a pace plan
+    pub fn with_plan(pace_plan: PacePlan) -> anyhow::Result<Self> {
+        pace_plan.validate()?;
+        Ok(Self {
+            target_pace_sec_per_km: pace_plan.average_pace(),
+            pace_plan: Some(pace_plan),
+        })
+    }
+
+    /// Speed at the target pace in meters per second
+    pub fn speed_m_per_sec(&self) -> f64 {
+        1000.0 / self.target_pace_sec_per_km
+    }
+
+    /// Distance the pacer has covered after `elapsed_ms`
+    pub fn distance_at(&self, elapsed_ms: i64) -> f64 {
+        let elapsed_sec = elapsed_ms.max(0) as f64 / 1000.0;
+        match &self.pace_plan {
+            Some(plan) => plan.distance_at_time(elapsed_sec),
+            None => self.speed_m_per_sec() * elapsed_sec,
+        }
+    }
+
+    /// Elapsed milliseconds at which the pacer reaches `distance_m`
+    pub fn time_at_distance(&self, distance_m: f64) -> i64 {
+        let seconds = match &self.pace_plan {
+            Some(plan) => plan.time_at_distance(distance_m),
+            None => distance_m.max(0.0) / self.speed_m_per_sec(),
+        };
+        (seconds * 1000.0) as i64
+    }
+
+    /// Pace in seconds per kilometer averaged over `[start_m, end_m]`
+    fn pace_between(&self, start_m: f64, end_m: f64) -> f64 {
+        match &self.pace_plan {
+            Some(plan) if end_m > start_m => {
+                (plan.time_at_distance(end_m) - plan.time_at_distance(start_m)) / (end_m - start_m)
+                    * 1000.0
+            }
+            _ => self.target_pace_sec_per_km,
+        }
+    }
+
+    /// Make the pacer run by effort rather than pace on a hilly route.
+    ///
+    /// The pacer's own pace (constant or planned) is treated as its flat
+    /// effort, so on each stretch it slows or speeds up by the grade's cost
+    /// factor: steady effort, uneven pace. Past the profile it runs unadjusted.
+    pub fn adjusted_for_terrain(&self, grades: &[GradeSegment]) -> AiPacer {
+        let mut segments: Vec<PaceSegment> = Vec::new();
+
+        for grade in grades.iter().filter(|g| g.length() > 0.0) {
+            let pace = self.pace_between(grade.start_m, grade.end_m)
+                * grade_adjustment_factor(grade.grade);
+            match segments.last_mut() {
+                Some(last) if (last.pace_sec_per_km - pace).abs() < 1e-9 => {
+                    last.until_distance_m = grade.end_m
+                }
+                _ => segments.push(PaceSegment {
+                    until_distance_m: grade.end_m,
+                    pace_sec_per_km: pace,
+                }),
+            }
+        }
+
+        let Some(profile_end) = segments.last().map(|s| s.until_distance_m) else {
+            return self.clone();
+        };
+
+        // Carry on with the original plan (or target pace) past the profile
+        match &self.pace_plan {
+            Some(plan @ PacePlan::Segments(rest)) if plan.planned_distance() > profile_end => {
+                segments.extend(
+                    rest.iter()
+                        .filter(|l| l.until_distance_m > profile_end)
+                        .copied(),
+                );
+            }
+            Some(plan) if plan.planned_distance() > profile_end => {
+                // Approximate the rest of a progression in short legs
+                let mut start = profile_end;
+                while start < plan.planned_distance() {
+                    let end = (start + GRADE_SAMPLE_SPACING_M).min(plan.planned_distance());
+                    segments.push(PaceSegment {
+                        until_distance_m: end,
+                        pace_sec_per_km: self.pace_between(start, end),
+                    });
+                    start = end;
+                }
+            }
+            _ => {
+                let pace = self.pace_between(profile_end, profile_end + 1000.0);
+                segments.push(PaceSegment {
+                    until_distance_m: profile_end + 1000.0,
+                    pace_sec_per_km: pace,
+                });
+            }
+        }
+
+        AiPacer {
+            target_pace_sec_per_km: self.target_pace_sec_per_km,
+            pace_plan: Some(PacePlan::Segments(segments)),
+        }
+    }
+}
+
+impl TryFrom<BansheeType> for AiPacer {
+    type Error = anyhow::Error;
+
+    fn try_from(banshee_type: BansheeType) -> Result<Self, Self::Error> {
+        match banshee_type {
+            BansheeType::AiPacer {
+                pace_plan: Some(plan),
+                ..
+            } => AiPacer::with_plan(plan),
+            BansheeType::AiPacer {
+                target_pace_sec_per_km,
+                pace_plan: None,
+            } => AiPacer::new(target_pace_sec_per_km),
+            BansheeType::RecordedRun { .. } => anyhow::bail!("Banshee is not an AI pacer"),
+        }
+    }
+}
+
+/// Build a pace plan that finishes `distance_m` in `target_time_ms`.
+///
+/// With a grade profile of the route, the pace slows on climbs and quickens on
+/// descents so the effort stays even; without one the pace is constant.
+/// Ground past the end of the profile is treated as flat.
+pub fn target_finish_plan(
+    distance_m: f64,
+    target_time_ms: i64,
+    grades: &[GradeSegment],
+) -> anyhow::Result<PacePlan> {
+    anyhow::ensure!(distance_m > 0.0, "Target distance must be positive");
+    anyhow::ensure!(target_time_ms > 0, "Target time must be positive");
+
+    // (end of leg, effort factor) for each stretch of the course
+    let mut legs: Vec<(f64, f64)> = Vec::new();
+    for grade in grades {
+        if grade.start_m >= distance_m {
+            break;
+        }
+        let factor = grade_adjustment_factor(grade.grade);
+        let end = grade.end_m.min(distance_m);
+        match legs.last_mut() {
+            Some(last) if (last.1 - factor).abs() < 1e-9 => last.0 = end,
+            _ => legs.push((end, factor)),
+        }
+    }
+    match legs.last_mut() {
+        Some(last) if last.0 >= distance_m => {}
+        Some(last) if (last.1 - 1.0).abs() < 1e-9 => last.0 = distance_m,
+        _ => legs.push((distance_m, 1.0)),
+    }
+
+    // Choose the flat pace so the legs add up to the target time
+    let mut start = 0.0;
+    let weighted_distance: f64 = legs
+        .iter()
+        .map(|&(end, factor)| {
+            let length = end - start;
+            start = end;
+            length * factor
+        })
+        .sum();
+    let flat_pace = target_time_ms as f64 / weighted_distance;
+
+    Ok(PacePlan::Segments(
+        legs.into_iter()
+            .map(|(end, factor)| PaceSegment {
+                until_distance_m: end,
+                pace_sec_per_km: flat_pace * factor,
+            })
+            .collect(),
+    ))
+}
+
+/// Where the runner is heading relative to a target finish time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FinishProjection {
+    /// Projected finish time in milliseconds
+    pub projected_finish_ms: i64,
+    /// Projected finish minus target time (positive = slower than target)
+    pub finish_delta_ms: i64,
+    /// Average pace needed over the rest of the distance to hit the target,
+    /// None once the target time has passed or the distance is done
+    pub required_pace_sec_per_km: Option<f64>,
+}
+
+/// Project the runner's finish time against a target, assuming they keep
+/// running relative to the plan as they have so far (so a slow climb that the
+/// plan expected doesn't count against them)
+pub fn project_finish(
+    plan: &PacePlan,
+    distance_m: f64,
+    target_time_ms: i64,
+    runner_distance_m: f64,
+    runner_elapsed_ms: i64,
+) -> Option<FinishProjection> {
+    if runner_distance_m < MIN_PROJECTION_DISTANCE_M || runner_elapsed_ms <= 0 {
+        return None;
+    }
+
+    let planned_ms = plan.time_at_distance(runner_distance_m.min(distance_m)) * 1000.0;
+    if planned_ms <= 0.0 {
+        return None;
+    }
+
+    let ratio = runner_elapsed_ms as f64 / planned_ms;
+    let projected_finish_ms = (target_time_ms as f64 * ratio) as i64;
+
+    let remaining_ms = target_time_ms - runner_elapsed_ms;
+    let remaining_m = distance_m - runner_distance_m;
+    let required_pace_sec_per_km = if remaining_ms > 0 && remaining_m > 0.0 {
+        Some(remaining_ms as f64 / remaining_m)
+    } else {
+        None
+    };
+
+    Some(FinishProjection {
+        projected_finish_ms,
+        finish_delta_ms: projected_finish_ms - target_time_ms,
+        required_pace_sec_per_km,
+    })
+}
+
+/// How a pacer moves on the map when there's no planned route
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CourseGeometry {
+    /// Stay at the start point
+    Stationary,
+    /// Run in a straight line along the runner's initial bearing
+    InitialBearing,
+    /// Run out along the runner's initial bearing and turn back at `turnaround_m`
+    OutAndBack { turnaround_m: f64 },
+    /// Follow the runner's own path so far
+    MirrorRunner,
+}
+
+/// Where a pacer is drawn on the map
+#[derive(Debug, Clone)]
+pub struct Course {
+    start_lat: f64,
+    start_lon: f64,
+    path: CoursePath,
+}
+
+#[derive(Debug, Clone)]
+enum CoursePath {
+    /// No geometry: the pacer stays at the start point
+    Stationary,
+    /// Along a planned route, stopping at its end
+    Route(IndexedTrack),
+    /// At the start point until the runner has set off, then along their
+    /// bearing (and back, with a turnaround)
+    AwaitingBearing { turnaround_m: Option<f64> },
+    /// In a straight line from the start
+    Bearing { bearing_deg: f64 },
+    /// Out along a bearing, back to the start after the turnaround
+    OutAndBack { bearing_deg: f64, turnaround_m: f64 },
+    /// Along the runner's own path, carrying on in their direction once
+    /// the pacer gets ahead of them
+    Mirror(IndexedTrack),
+}
+
+impl Course {
+    /// Follow the route if there is one, otherwise stay at the start point
+    pub fn new(start_lat: f64, start_lon: f64, route: Option<IndexedTrack>) -> Self {
+        let path = match route.filter(|r| !r.is_empty()) {
+            Some(route) => CoursePath::Route(route),
+            None => CoursePath::Stationary,
+        };
+        Self {
+            start_lat,
+            start_lon,
+            path,
+        }
+    }
+
+    /// A course without a planned route, which takes its shape from the
+    /// runner's GPS fixes as they come in through `add_runner_position`
+    pub fn from_geometry(
+        start_lat: f64,
+        start_lon: f64,
+        geometry: CourseGeometry,
+    ) -> anyhow::Result<Self> {
+        let path = match geometry {
+            CourseGeometry::Stationary => CoursePath::Stationary,
+            CourseGeometry::InitialBearing => CoursePath::AwaitingBearing { turnaround_m: None },
+            CourseGeometry::OutAndBack { turnaround_m } => {
+                anyhow::ensure!(turnaround_m > 0.0, "Invalid turnaround distance");
+                CoursePath::AwaitingBearing {
+                    turnaround_m: Some(turnaround_m),
+                }
+            }
+            CourseGeometry::MirrorRunner => CoursePath::Mirror(IndexedTrack::new(Vec::new())),
+        };
+        Ok(Self {
+            start_lat,
+            start_lon,
+            path,
+        })
+    }
+
+    /// Take in the runner's latest GPS fix. A bearing course sets off once
+    /// the runner is far enough from the start to give a bearing, and a
+    /// mirroring course extends its path; other courses don't change.
+    pub fn add_runner_position(&mut self, lat: f64, lon: f64) {
+        match &mut self.path {
+            CoursePath::AwaitingBearing { turnaround_m } => {
+                let from_start = haversine_distance(self.start_lat, self.start_lon, lat, lon);
+                if from_start < INITIAL_BEARING_DISTANCE_M {
+                    return;
+                }
+                let bearing_deg = bearing(self.start_lat, self.start_lon, lat, lon);
+                self.path = match *turnaround_m {
+                    Some(turnaround_m) => CoursePath::OutAndBack {
+                        bearing_deg,
+                        turnaround_m,
+                    },
+                    None => CoursePath::Bearing { bearing_deg },
+                };
+            }
+            CoursePath::Mirror(path) => path.push(GpsPoint::new(lat, lon, Utc::now())),
+            _ => {}
+        }
+    }
+
+    /// Length of a planned route; None for courses without an end
+    pub fn length_m(&self) -> Option<f64> {
+        match &self.path {
+            CoursePath::Route(route) => Some(route.total_distance()),
+            _ => None,
+        }
+    }
+
+    /// Position after covering `distance_m`
+    pub fn position_at_distance(&self, distance_m: f64) -> (f64, f64) {
+        let along = |bearing_deg: f64, d: f64| {
+            destination_point(self.start_lat, self.start_lon, bearing_deg, d.max(0.0))
+        };
+
+        match &self.path {
+            CoursePath::Stationary | CoursePath::AwaitingBearing { .. } => {
+                (self.start_lat, self.start_lon)
+            }
+            CoursePath::Route(route) => route
+                .position_at_distance(distance_m)
+                .map(|p| (p.lat, p.lon))
+                .unwrap_or((self.start_lat, self.start_lon)),
+            CoursePath::Bearing { bearing_deg } => along(*bearing_deg, distance_m),
+            CoursePath::OutAndBack {
+                bearing_deg,
+                turnaround_m,
+            } => {
+                let out = if distance_m <= *turnaround_m {
+                    distance_m
+                } else {
+                    2.0 * turnaround_m - distance_m
+                };
+                along(*bearing_deg, out)
+            }
+            CoursePath::Mirror(path) => {
+                Self::mirror_position(path, distance_m).unwrap_or((self.start_lat, self.start_lon))
+            }
+        }
+    }
+
+    fn mirror_position(path: &IndexedTrack, distance_m: f64) -> Option<(f64, f64)> {
+        let beyond = distance_m - path.total_distance();
+        if beyond <= 0.0 {
+            return path
+                .position_at_distance(distance_m)
+                .map(|p| (p.lat, p.lon));
+        }
+
+        // Ahead of the runner: extend their latest heading
+        let points = path.points();
+        let last = points.last()?;
+        let heading_from = points
+            .iter()
+            .rev()
+            .find(|p| (p.lat, p.lon) != (last.lat, last.lon));
+        match heading_from {
+            Some(prev) => {
+                let heading = bearing(prev.lat, prev.lon, last.lat, last.lon);
+                Some(destination_point(last.lat, last.lon, heading, beyond))
+            }
+            None => Some((last.lat, last.lon)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flat_target_finish_plan() {
+        // 10K in 50:00 is 5:00/km all the way
+        let plan = target_finish_plan(10_000.0, 50 * 60 * 1000, &[]).unwrap();
+        assert_eq!(
+            plan,
+            PacePlan::Segments(vec![PaceSegment {
+                until_distance_m: 10_000.0,
+                pace_sec_per_km: 300.0,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_hilly_target_finish_plan() {
+        // 2km out: flat, 5% climb, 5% descent, flat
+        let grades = [
+            GradeSegment {
+                start_m: 0.0,
+                end_m: 500.0,
+                grade: 0.0,
+            },
+            GradeSegment {
+                start_m: 500.0,
+                end_m: 1000.0,
+                grade: 0.05,
+            },
+            GradeSegment {
+                start_m: 1000.0,
+                end_m: 1500.0,
+                grade: -0.05,
+            },
+        ];
+        let target_ms = 10 * 60 * 1000;
+        let plan = target_finish_plan(2000.0, target_ms, &grades).unwrap();
+
+        let PacePlan::Segments(segments) = &plan else {
+            panic!("expected segments");
+        };
+        assert_eq!(segments.len(), 4);
+        assert!(segments[1].pace_sec_per_km > segments[0].pace_sec_per_km);
+        assert!(segments[2].pace_sec_per_km < segments[0].pace_sec_per_km);
+        assert_eq!(segments[3].until_distance_m, 2000.0);
+        assert!((plan.time_at_distance(2000.0) * 1000.0 - target_ms as f64).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_adjusted_for_terrain() {
+        let grades = [
+            GradeSegment {
+                start_m: 0.0,
+                end_m: 1000.0,
+                grade: 0.0,
+            },
+            GradeSegment {
+                start_m: 1000.0,
+                end_m: 2000.0,
+                grade: 0.06,
+            },
+            GradeSegment {
+                start_m: 2000.0,
+                end_m: 3000.0,
+                grade: -0.06,
+            },
+        ];
+        let flat = AiPacer::new(300.0).unwrap();
+        let hilly = flat.adjusted_for_terrain(&grades);
+
+        // Same pace on the flat, slower up, faster down
+        assert_eq!(hilly.time_at_distance(1000.0), 300_000);
+        let climb_ms = hilly.time_at_distance(2000.0) - hilly.time_at_distance(1000.0);
+        let descent_ms = hilly.time_at_distance(3000.0) - hilly.time_at_distance(2000.0);
+        assert!(climb_ms > 300_000);
+        assert!(descent_ms < 300_000);
+        // Back to target pace past the end of the route
+        let after_ms = hilly.time_at_distance(3500.0) - hilly.time_at_distance(3000.0);
+        assert_eq!(after_ms, 150_000);
+        assert_eq!(hilly.target_pace_sec_per_km, 300.0);
+
+        // Pacer moves less distance over a climb in the same time
+        assert!(hilly.distance_at(600_000) < flat.distance_at(600_000));
+
+        // A planned pacer keeps its plan shape under the terrain adjustment
+        let progression = AiPacer::with_plan(PacePlan::Progression {
+            start_pace_sec_per_km: 330.0,
+            finish_pace_sec_per_km: 270.0,
+            distance_m: 6000.0,
+        })
+        .unwrap()
+        .adjusted_for_terrain(&grades);
+        let last_leg_ms =
+            progression.time_at_distance(6000.0) - progression.time_at_distance(3000.0);
+        assert!((last_leg_ms - 855_000).abs() <= 1);
+        let first_km_ms =
+            progression.time_at_distance(3100.0) - progression.time_at_distance(3000.0);
+        let last_km_ms =
+            progression.time_at_distance(6000.0) - progression.time_at_distance(5900.0);
+        assert!(first_km_ms > last_km_ms);
+    }
+
+    #[test]
+    fn test_project_finish() {
+        let plan = target_finish_plan(10_000.0, 3_000_000, &[]).unwrap();
+
+        // 2km in 9:40 against a 10:00 plan
+        let projection = project_finish(&plan, 10_000.0, 3_000_000, 2000.0, 580_000).unwrap();
+        assert_eq!(projection.projected_finish_ms, 2_900_000);
+        assert_eq!(projection.finish_delta_ms, -100_000);
+        assert!((projection.required_pace_sec_per_km.unwrap() - 302.5).abs() < 1e-9);
+
+        assert!(project_finish(&plan, 10_000.0, 3_000_000, 50.0, 15_000).is_none());
+    }
+
+    #[test]
+    fn test_courses_without_route() {
+        use crate::geo::distance::haversine_distance;
+
+        let from_start = |course: &Course, d: f64| {
+            let (lat, lon) = course.position_at_distance(d);
+            haversine_distance(51.5, -0.1, lat, lon)
+        };
+        let course = |geometry| Course::from_geometry(51.5, -0.1, geometry).unwrap();
+
+        let mut stationary = course(CourseGeometry::Stationary);
+        stationary.add_runner_position(51.501, -0.1);
+        assert_eq!(stationary.position_at_distance(500.0), (51.5, -0.1));
+
+        // Waits at the start until the runner has given a bearing (east)
+        let mut straight = course(CourseGeometry::InitialBearing);
+        straight.add_runner_position(51.5, -0.09995);
+        assert_eq!(straight.position_at_distance(500.0), (51.5, -0.1));
+        straight.add_runner_position(51.5, -0.0995);
+        assert!((from_start(&straight, 500.0) - 500.0).abs() < 0.5);
+        let (lat, lon) = straight.position_at_distance(500.0);
+        assert!((lat - 51.5).abs() < 1e-4 && lon > -0.1);
+        // The bearing is kept once set
+        straight.add_runner_position(51.6, -0.1);
+        assert_eq!(straight.position_at_distance(500.0), (lat, lon));
+
+        let mut out_and_back = course(CourseGeometry::OutAndBack {
+            turnaround_m: 1000.0,
+        });
+        out_and_back.add_runner_position(51.501, -0.1);
+        assert!((from_start(&out_and_back, 600.0) - 600.0).abs() < 0.5);
+        assert!((from_start(&out_and_back, 1400.0) - 600.0).abs() < 0.5);
+        assert!(from_start(&out_and_back, 2500.0) < 0.5);
+        assert!(Course::from_geometry(
+            51.5,
+            -0.1,
+            CourseGeometry::OutAndBack { turnaround_m: 0.0 }
+        )
+        .is_err());
+
+        // Runner heading north for ~222m; a pacer ahead carries on north
+        let mut mirror = course(CourseGeometry::MirrorRunner);
+        assert_eq!(mirror.position_at_distance(100.0), (51.5, -0.1));
+        for lat in [51.5, 51.501, 51.502] {
+            mirror.add_runner_position(lat, -0.1);
+        }
+        let total = haversine_distance(51.5, -0.1, 51.502, -0.1);
+        let (lat, lon) = mirror.position_at_distance(total / 2.0);
+        assert!((lat - 51.501).abs() < 1e-6 && (lon + 0.1).abs() < 1e-9);
+        let (lat, lon) = mirror.position_at_distance(total + 100.0);
+        assert!(lat > 51.502 && (lon + 0.1).abs() < 1e-6);
+        assert!((from_start(&mirror, total + 100.0) - (total + 100.0)).abs() < 0.5);
+    }
+}
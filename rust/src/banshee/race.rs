@@ -0,0 +1,366 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+
+use crate::models::BansheeState;
+
+use super::events::BansheeSample;
+use super::pacer::{AiPacer, Course};
+use super::BansheeSession;
+
+/// What drives a banshee in a race
+#[derive(Debug, Clone)]
+pub enum Opponent {
+    /// Replay of a recorded run
+    Recorded(BansheeSession),
+    /// AI pacer on the race course
+    Pacer(AiPacer),
+}
+
+/// One banshee taking part in a race
+#[derive(Debug, Clone)]
+pub struct RaceBanshee {
+    pub name: String,
+    pub opponent: Opponent,
+    /// Library entry the banshee was loaded from, to record the result against
+    pub saved_id: Option<String>,
+}
+
+impl RaceBanshee {
+    /// Distance the banshee has covered at an elapsed time
+    pub fn distance_at(&self, elapsed_ms: i64) -> f64 {
+        match &self.opponent {
+            Opponent::Recorded(session) => session.distance_at(elapsed_ms),
+            Opponent::Pacer(pacer) => pacer.distance_at(elapsed_ms),
+        }
+    }
+
+    /// Elapsed time at which the banshee reached a distance; None if a
+    /// recorded run never got that far
+    pub fn time_at_distance(&self, distance_m: f64) -> Option<i64> {
+        match &self.opponent {
+            Opponent::Recorded(session) => session.time_at_distance(distance_m),
+            Opponent::Pacer(pacer) => Some(pacer.time_at_distance(distance_m)),
+        }
+    }
+
+    /// Whether the banshee is done at an elapsed time: a recorded run once
+    /// its replay ends, a pacer at the race distance or the end of its plan.
+    /// Banshees also finish on reaching the race distance early.
+    pub fn has_finished(&self, elapsed_ms: i64, finish_distance_m: Option<f64>) -> bool {
+        match &self.opponent {
+            Opponent::Recorded(session) => {
+                elapsed_ms >= session.duration_ms()
+                    || finish_distance_m
+                        .is_some_and(|f| session.state_at(elapsed_ms).distance_meters >= f)
+            }
+            Opponent::Pacer(pacer) => finish_distance_m
+                .or(pacer.pace_plan.as_ref().map(|p| p.planned_distance()))
+                .is_some_and(|f| pacer.distance_at(elapsed_ms) >= f),
+        }
+    }
+}
+
+/// A row in the live leaderboard
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    /// 1-based position
+    pub position: u32,
+    pub name: String,
+    /// True for the runner's own row
+    pub is_runner: bool,
+    /// Index into the race's banshees (None for the runner)
+    pub banshee_index: Option<usize>,
+    pub distance_meters: f64,
+    /// Distance ahead of the runner in meters (positive = ahead of the runner)
+    pub gap_to_runner_meters: f64,
+}
+
+/// Banshee states and leaderboard at one moment in a race
+#[derive(Debug, Clone)]
+pub struct RaceStandings {
+    /// State of each banshee, in the order they were added to the race
+    pub banshees: Vec<BansheeState>,
+    /// Runner and banshees ordered by distance covered, leader first
+    pub leaderboard: Vec<LeaderboardEntry>,
+}
+
+/// A race against several banshees at once
+#[derive(Debug, Clone)]
+pub struct RaceSession {
+    banshees: Vec<RaceBanshee>,
+    course: Arc<Mutex<Course>>,
+}
+
+impl RaceSession {
+    pub fn new(banshees: Vec<RaceBanshee>, course: Course) -> anyhow::Result<Self> {
+        Self::on_shared_course(banshees, Arc::new(Mutex::new(course)))
+    }
+
+    /// Race on a course that is still taking shape from the runner's GPS
+    /// fixes (see `Course::add_runner_position`), fed in by its other owner
+    pub fn on_shared_course(
+        banshees: Vec<RaceBanshee>,
+        course: Arc<Mutex<Course>>,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(!banshees.is_empty(), "A race needs at least one banshee");
+        Ok(Self { banshees, course })
+    }
+
+    pub fn banshees(&self) -> &[RaceBanshee] {
+        &self.banshees
+    }
+
+    /// State of one banshee relative to the runner
+    pub fn banshee_state(
+        &self,
+        banshee: &RaceBanshee,
+        runner_distance_m: f64,
+        runner_elapsed_ms: i64,
+    ) -> BansheeState {
+        let mut state = match &banshee.opponent {
+            Opponent::Recorded(session) => {
+                // Past the end of the recording the banshee is projected on
+                // at its average pace, so a runner who's gone further still
+                // shows as ahead
+                let mut state = session.state_at(runner_elapsed_ms);
+                state.time_delta_ms =
+                    runner_elapsed_ms - session.projected_time_at_distance(runner_distance_m);
+                state
+            }
+            Opponent::Pacer(pacer) => {
+                // Pacers stop at the end of a planned route
+                let course = self.course.lock().unwrap();
+                let distance = pacer.distance_at(runner_elapsed_ms);
+                let distance = course.length_m().map_or(distance, |end| distance.min(end));
+                let (lat, lon) = course.position_at_distance(distance);
+                let mut state = BansheeState::new(lat, lon, distance);
+                state.time_delta_ms = runner_elapsed_ms - pacer.time_at_distance(runner_distance_m);
+                state
+            }
+        };
+
+        state.distance_delta_meters = state.distance_meters - runner_distance_m;
+        state
+    }
+
+    /// Event samples for every banshee from standings at `runner_elapsed_ms`
+    pub fn event_samples(
+        &self,
+        standings: &RaceStandings,
+        runner_elapsed_ms: i64,
+        finish_distance_m: Option<f64>,
+    ) -> Vec<BansheeSample> {
+        // The end of a planned route is a finish line too
+        let route_end = self.course.lock().unwrap().length_m();
+        let finish = match (finish_distance_m, route_end) {
+            (Some(finish), Some(end)) => Some(finish.min(end)),
+            (finish, end) => finish.or(end),
+        };
+
+        self.banshees
+            .iter()
+            .zip(&standings.banshees)
+            .map(|(banshee, state)| BansheeSample {
+                gap_m: state.distance_delta_meters,
+                finished: banshee.has_finished(runner_elapsed_ms, finish),
+            })
+            .collect()
+    }
+
+    /// Positions of every banshee and the leaderboard for the runner's progress
+    pub fn standings(&self, runner_distance_m: f64, runner_elapsed_ms: i64) -> RaceStandings {
+        let banshees: Vec<BansheeState> = self
+            .banshees
+            .iter()
+            .map(|b| self.banshee_state(b, runner_distance_m, runner_elapsed_ms))
+            .collect();
+
+        let mut leaderboard: Vec<LeaderboardEntry> = banshees
+            .iter()
+            .zip(&self.banshees)
+            .enumerate()
+            .map(|(idx, (state, banshee))| LeaderboardEntry {
+                position: 0,
+                name: banshee.name.clone(),
+                is_runner: false,
+                banshee_index: Some(idx),
+                distance_meters: state.distance_meters,
+                gap_to_runner_meters: state.distance_delta_meters,
+            })
+            .collect();
+
+        leaderboard.push(LeaderboardEntry {
+            position: 0,
+            name: "You".to_string(),
+            is_runner: true,
+            banshee_index: None,
+            distance_meters: runner_distance_m,
+            gap_to_runner_meters: 0.0,
+        });
+
+        // Leader first; the runner wins ties
+        leaderboard.sort_by(|a, b| {
+            b.distance_meters
+                .partial_cmp(&a.distance_meters)
+                .unwrap_or(Ordering::Equal)
+                .then(b.is_runner.cmp(&a.is_runner))
+        });
+        for (idx, entry) in leaderboard.iter_mut().enumerate() {
+            entry.position = idx as u32 + 1;
+        }
+
+        RaceStandings {
+            banshees,
+            leaderboard,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::banshee::CourseGeometry;
+    use crate::geo::IndexedTrack;
+    use crate::models::GpsPoint;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_standings() {
+        // Recorded banshee at ~111m per minute (~9:00/km)
+        let start = Utc::now();
+        let points = (0..=10)
+            .map(|i| GpsPoint::new(51.5 + 0.001 * i as f64, -0.1, start + Duration::minutes(i)))
+            .collect();
+        let session = BansheeSession::new("run".to_string(), points).unwrap();
+
+        let race = RaceSession::new(
+            vec![
+                RaceBanshee {
+                    name: "Last week".to_string(),
+                    opponent: Opponent::Recorded(session),
+                    saved_id: None,
+                },
+                RaceBanshee {
+                    name: "5:00/km".to_string(),
+                    opponent: Opponent::Pacer(AiPacer::new(300.0).unwrap()),
+                    saved_id: None,
+                },
+            ],
+            Course::new(51.5, -0.1, None),
+        )
+        .unwrap();
+
+        // After 5 minutes the pacer has done 1000m and last week ~556m
+        let standings = race.standings(700.0, 5 * 60 * 1000);
+        assert_eq!(standings.banshees.len(), 2);
+        assert!((standings.banshees[1].distance_meters - 1000.0).abs() < 1e-6);
+        assert!((standings.banshees[1].distance_delta_meters - 300.0).abs() < 1e-6);
+
+        let order: Vec<&str> = standings
+            .leaderboard
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(order, vec!["5:00/km", "You", "Last week"]);
+        assert_eq!(standings.leaderboard[1].position, 2);
+        assert!(standings.leaderboard[2].gap_to_runner_meters < 0.0);
+    }
+
+    #[test]
+    fn test_pacer_stops_at_route_end() {
+        // ~556m route north
+        let route = IndexedTrack::new(
+            (0..=5)
+                .map(|i| GpsPoint::new(51.5 + 0.001 * i as f64, -0.1, Utc::now()))
+                .collect(),
+        );
+        let end = route.total_distance();
+        let race = RaceSession::new(
+            vec![RaceBanshee {
+                name: "5:00/km".to_string(),
+                opponent: Opponent::Pacer(AiPacer::new(300.0).unwrap()),
+                saved_id: None,
+            }],
+            Course::new(51.5, -0.1, Some(route)),
+        )
+        .unwrap();
+
+        // Five minutes at 5:00/km would be 1000m
+        let elapsed = 5 * 60 * 1000;
+        let standings = race.standings(400.0, elapsed);
+        assert!((standings.banshees[0].distance_meters - end).abs() < 1e-6);
+        assert!((standings.banshees[0].distance_delta_meters - (end - 400.0)).abs() < 1e-6);
+        assert!((standings.leaderboard[0].distance_meters - end).abs() < 1e-6);
+
+        assert!(race.event_samples(&standings, elapsed, None)[0].finished);
+        let early = race.standings(100.0, 60_000);
+        assert!(!race.event_samples(&early, 60_000, None)[0].finished);
+    }
+
+    #[test]
+    fn test_race_on_shared_course() {
+        let course = Arc::new(Mutex::new(
+            Course::from_geometry(51.5, -0.1, CourseGeometry::InitialBearing).unwrap(),
+        ));
+        let race = RaceSession::on_shared_course(
+            vec![RaceBanshee {
+                name: "5:00/km".to_string(),
+                opponent: Opponent::Pacer(AiPacer::new(300.0).unwrap()),
+                saved_id: None,
+            }],
+            course.clone(),
+        )
+        .unwrap();
+
+        let pacer_at = |race: &RaceSession| {
+            let state = &race.standings(100.0, 60_000).banshees[0];
+            (state.lat, state.lon)
+        };
+        assert_eq!(pacer_at(&race), (51.5, -0.1));
+
+        // The runner sets off north; the race's pacer follows
+        course.lock().unwrap().add_runner_position(51.501, -0.1);
+        let (lat, lon) = pacer_at(&race);
+        assert!(lat > 51.5 && (lon + 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_event_samples() {
+        let start = Utc::now();
+        let points = (0..=10)
+            .map(|i| GpsPoint::new(51.5 + 0.001 * i as f64, -0.1, start + Duration::minutes(i)))
+            .collect();
+        let race = RaceSession::new(
+            vec![
+                RaceBanshee {
+                    name: "Last week".to_string(),
+                    opponent: Opponent::Recorded(
+                        BansheeSession::new("run".to_string(), points).unwrap(),
+                    ),
+                    saved_id: None,
+                },
+                RaceBanshee {
+                    name: "5:00/km".to_string(),
+                    opponent: Opponent::Pacer(AiPacer::new(300.0).unwrap()),
+                    saved_id: None,
+                },
+            ],
+            Course::new(51.5, -0.1, None),
+        )
+        .unwrap();
+
+        // At 10 minutes the recorded run is over; the pacer is at 2000m
+        let elapsed = 10 * 60 * 1000;
+        let standings = race.standings(1500.0, elapsed);
+        let open = race.event_samples(&standings, elapsed, None);
+        assert!(open[0].finished && !open[1].finished);
+        assert!((open[1].gap_m - 500.0).abs() < 1e-6);
+        // 1500m is past the end of last week's ~1112m run; at its pace it
+        // would have got there ~3.5 minutes later
+        let behind_ms = -standings.banshees[0].time_delta_ms;
+        assert!((205_000..215_000).contains(&behind_ms));
+
+        let samples = race.event_samples(&standings, elapsed, Some(2000.0));
+        assert!(samples[1].finished);
+    }
+}
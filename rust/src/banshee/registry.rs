@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Live objects (sessions, races) held in Rust between FFI calls, keyed by a
+/// generated ID that Flutter passes back on each call
+pub struct Registry<T> {
+    items: Mutex<HashMap<String, Arc<T>>>,
+}
+
+impl<T> Registry<T> {
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Store an item and return its new ID
+    pub fn insert(&self, item: T) -> String {
+        let id = Uuid::new_v4().to_string();
+        self.items
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Arc::new(item));
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<T>> {
+        self.items.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn remove(&self, id: &str) -> Option<Arc<T>> {
+        self.items.lock().unwrap().remove(id)
+    }
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,193 @@
+use crate::geo::IndexedTrack;
+use crate::models::{GapSample, LeadChange, RaceReport, SplitComparison};
+
+use super::events::{BansheeEventKind, BansheeSample, EventConfig, RaceEventTracker};
+use super::race::{Opponent, RaceBanshee};
+
+/// Time between samples in a report's gap series
+pub const REPORT_SAMPLE_INTERVAL_MS: i64 = 10_000;
+
+/// Split length used to compare runner and banshee
+pub const REPORT_SPLIT_DISTANCE_M: f64 = 1000.0;
+
+/// Dead band around level before the lead counts as changed hands
+const LEAD_HYSTERESIS_M: f64 = 5.0;
+
+/// Replay a finished race: the runner's recorded track against a banshee
+pub fn build_race_report(
+    runner: &IndexedTrack,
+    banshee: &RaceBanshee,
+    split_distance_m: f64,
+) -> anyhow::Result<RaceReport> {
+    anyhow::ensure!(runner.points().len() >= 2, "Run has no GPS track");
+    anyhow::ensure!(split_distance_m > 0.0, "Invalid split distance");
+
+    let finish_ms = runner.duration_ms();
+    let finish_m = runner.total_distance();
+
+    let gap_series: Vec<GapSample> = (0..)
+        .map(|i| i * REPORT_SAMPLE_INTERVAL_MS)
+        .take_while(|&t| t < finish_ms)
+        .chain(std::iter::once(finish_ms))
+        .map(|elapsed_ms| {
+            let runner_distance_m = runner.distance_at_time(elapsed_ms);
+            GapSample {
+                elapsed_ms,
+                runner_distance_m,
+                gap_m: banshee.distance_at(elapsed_ms) - runner_distance_m,
+            }
+        })
+        .collect();
+
+    // Lead changes as the live race would have announced them
+    let mut tracker = RaceEventTracker::new(
+        1,
+        EventConfig {
+            lead_hysteresis_m: LEAD_HYSTERESIS_M,
+            gap_thresholds_m: Vec::new(),
+            gap_closed_m: 0.0,
+            cooldown_ms: 0,
+            finish_distance_m: None,
+        },
+    )?;
+    let lead_changes = gap_series
+        .iter()
+        .flat_map(|s| {
+            let sample = BansheeSample {
+                gap_m: s.gap_m,
+                finished: false,
+            };
+            tracker.update(s.runner_distance_m, s.elapsed_ms, &[sample])
+        })
+        .filter_map(|event| {
+            let runner_took_lead = match event.kind {
+                BansheeEventKind::Overtook => true,
+                BansheeEventKind::Overtaken => false,
+                _ => return None,
+            };
+            Some(LeadChange {
+                elapsed_ms: event.runner_elapsed_ms,
+                runner_distance_m: event.runner_distance_m,
+                runner_took_lead,
+            })
+        })
+        .collect();
+
+    let largest_lead = gap_series
+        .iter()
+        .filter(|s| s.gap_m < 0.0)
+        .min_by(|a, b| a.gap_m.total_cmp(&b.gap_m))
+        .copied();
+    let largest_deficit = gap_series
+        .iter()
+        .filter(|s| s.gap_m > 0.0)
+        .max_by(|a, b| a.gap_m.total_cmp(&b.gap_m))
+        .copied();
+
+    // Splits both covered, compared on time over the same ground
+    let mut splits = Vec::new();
+    let mut previous = (0, 0);
+    for number in 1.. {
+        let distance = split_distance_m * number as f64;
+        let (Some(runner_ms), Some(banshee_ms)) = (
+            runner.time_at_distance(distance),
+            banshee.time_at_distance(distance),
+        ) else {
+            break;
+        };
+        splits.push(SplitComparison {
+            number,
+            runner_ms: runner_ms - previous.0,
+            banshee_ms: banshee_ms - previous.1,
+        });
+        previous = (runner_ms, banshee_ms);
+    }
+
+    let banshee_run_id = match &banshee.opponent {
+        Opponent::Recorded(session) => Some(session.run_id.clone()),
+        Opponent::Pacer(_) => None,
+    };
+
+    Ok(RaceReport {
+        banshee_name: banshee.name.clone(),
+        banshee_run_id,
+        gap_series,
+        lead_changes,
+        largest_lead,
+        largest_deficit,
+        split_distance_m,
+        splits,
+        final_time_delta_ms: banshee.time_at_distance(finish_m).map(|t| finish_ms - t),
+        final_distance_delta_m: banshee.distance_at(finish_ms) - finish_m,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::banshee::AiPacer;
+    use crate::models::GpsPoint;
+    use chrono::{Duration, Utc};
+
+    /// Runner doing ~111m per `secs` seconds per point, from a list of
+    /// per-point seconds
+    fn runner(secs: &[i64]) -> IndexedTrack {
+        let start = Utc::now();
+        let mut elapsed = 0;
+        let mut points = vec![GpsPoint::new(51.5, -0.1, start)];
+        for (i, s) in secs.iter().enumerate() {
+            elapsed += s;
+            points.push(GpsPoint::new(
+                51.5 + 0.001 * (i + 1) as f64,
+                -0.1,
+                start + Duration::seconds(elapsed),
+            ));
+        }
+        IndexedTrack::new(points)
+    }
+
+    fn pacer(pace: f64) -> RaceBanshee {
+        RaceBanshee {
+            name: "Pacer".to_string(),
+            opponent: Opponent::Pacer(AiPacer::new(pace).unwrap()),
+            saved_id: None,
+        }
+    }
+
+    #[test]
+    fn test_report_against_pacer() {
+        // ~2.2km: first km slow (40s per 111m), then fast (20s per 111m)
+        let mut legs = vec![40; 9];
+        legs.extend([20; 11]);
+        let track = runner(&legs);
+        let report = build_race_report(&track, &pacer(300.0), REPORT_SPLIT_DISTANCE_M).unwrap();
+
+        // Falls behind a 5:00/km pacer, then passes it
+        assert_eq!(report.banshee_name, "Pacer");
+        assert!(report.banshee_run_id.is_none());
+        assert_eq!(report.lead_changes.len(), 1);
+        assert!(report.lead_changes[0].runner_took_lead);
+        assert!(report.largest_deficit.unwrap().gap_m > 0.0);
+        assert!(report.largest_lead.unwrap().gap_m < 0.0);
+
+        // Lost the first km, won the second
+        assert_eq!(report.splits.len(), 2);
+        assert!(!report.splits[0].runner_won());
+        assert!(report.splits[1].runner_won());
+        assert_eq!(report.splits_won(), 1);
+
+        assert!(report.runner_won());
+        assert!(report.final_time_delta_ms.unwrap() < 0);
+        assert!(report.final_distance_delta_m < 0.0);
+
+        let last = report.gap_series.last().unwrap();
+        assert_eq!(last.elapsed_ms, track.duration_ms());
+        assert_eq!(report.gap_series[1].elapsed_ms, REPORT_SAMPLE_INTERVAL_MS);
+    }
+
+    #[test]
+    fn test_report_needs_a_track() {
+        let track = runner(&[]);
+        assert!(build_race_report(&track, &pacer(300.0), 1000.0).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::geo::IndexedTrack;
+use crate::models::{BansheeState, GpsPoint, ReplayHandicap};
+
+/// Runner and banshee compared at the same distance along the course
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceComparison {
+    /// Distance along the course the comparison was made at
+    pub distance_m: f64,
+    /// Runner's elapsed time at this distance
+    pub runner_time_ms: i64,
+    /// Banshee's elapsed time when it reached this distance
+    pub banshee_time_ms: i64,
+    /// Runner time minus banshee time (positive = banshee ahead)
+    pub time_delta_ms: i64,
+}
+
+impl DistanceComparison {
+    /// Returns true if the banshee reached this point first
+    pub fn is_banshee_ahead(&self) -> bool {
+        self.time_delta_ms > 0
+    }
+}
+
+/// A recorded banshee loaded once and kept in memory for the length of a race.
+///
+/// The track's cumulative distances and times are computed up front, so each
+/// position query is a binary search rather than a database load and a scan.
+/// A handicap shifts and scales the replay's clock; every position and time
+/// the session reports is on the runner's clock with the handicap applied.
+#[derive(Debug, Clone)]
+pub struct BansheeSession {
+    /// ID of the run the banshee replays
+    pub run_id: String,
+    track: IndexedTrack,
+    handicap: ReplayHandicap,
+}
+
+impl BansheeSession {
+    /// Create a session from a recorded run's GPS points
+    pub fn new(run_id: String, points: Vec<GpsPoint>) -> anyhow::Result<Self> {
+        anyhow::ensure!(!points.is_empty(), "Run has no GPS points");
+
+        Ok(Self {
+            run_id,
+            track: IndexedTrack::new(points),
+            handicap: ReplayHandicap::default(),
+        })
+    }
+
+    /// Replay the run with a time and speed handicap
+    pub fn with_handicap(mut self, handicap: ReplayHandicap) -> anyhow::Result<Self> {
+        handicap.validate()?;
+        self.handicap = handicap;
+        Ok(self)
+    }
+
+    pub fn handicap(&self) -> ReplayHandicap {
+        self.handicap
+    }
+
+    /// The banshee's precomputed track, on the recording's own clock
+    pub fn track(&self) -> &IndexedTrack {
+        &self.track
+    }
+
+    /// Time into the recording at the runner's elapsed time (0 until the
+    /// banshee sets off)
+    fn replay_ms(&self, elapsed_ms: i64) -> i64 {
+        let since_start = (elapsed_ms - self.handicap.start_offset_ms).max(0);
+        (since_start as f64 * self.handicap.speed_factor).round() as i64
+    }
+
+    /// Runner's elapsed time at a point in the recording
+    fn race_ms(&self, replay_ms: i64) -> i64 {
+        (replay_ms as f64 / self.handicap.speed_factor).round() as i64
+            + self.handicap.start_offset_ms
+    }
+
+    /// Distance the banshee has covered at the runner's elapsed time
+    pub fn distance_at(&self, elapsed_ms: i64) -> f64 {
+        self.track.distance_at_time(self.replay_ms(elapsed_ms))
+    }
+
+    /// Runner's elapsed time at which the banshee reached a distance; None
+    /// if its run never got that far
+    pub fn time_at_distance(&self, distance_m: f64) -> Option<i64> {
+        self.track
+            .time_at_distance(distance_m)
+            .map(|t| self.race_ms(t))
+    }
+
+    /// Runner's elapsed time at which the banshee reached a distance, or
+    /// would have if it had kept going at its average pace after its run ended
+    pub fn projected_time_at_distance(&self, distance_m: f64) -> i64 {
+        if let Some(time) = self.time_at_distance(distance_m) {
+            return time;
+        }
+        let total = self.track.total_distance();
+        if total <= 0.0 {
+            return self.duration_ms();
+        }
+        let replay_ms = self.track.duration_ms() as f64 * distance_m / total;
+        self.race_ms(replay_ms.round() as i64)
+    }
+
+    /// Total distance of the banshee's run in meters
+    pub fn total_distance(&self) -> f64 {
+        self.track.total_distance()
+    }
+
+    /// Runner's elapsed time when the banshee's replay ends, in milliseconds
+    pub fn duration_ms(&self) -> i64 {
+        self.race_ms(self.track.duration_ms()).max(0)
+    }
+
+    /// Banshee position and distance at an elapsed time
+    pub fn state_at(&self, elapsed_ms: i64) -> BansheeState {
+        let replay_ms = self.replay_ms(elapsed_ms);
+        // The track is never empty, so there is always a position
+        let position = self
+            .track
+            .position_at_time(replay_ms)
+            .unwrap_or_else(|| self.track.points()[0].clone());
+
+        BansheeState::new(
+            position.lat,
+            position.lon,
+            self.track.distance_at_time(replay_ms),
+        )
+    }
+
+    /// Compare the runner with the banshee at the runner's current distance:
+    /// how much earlier or later the banshee got to the same point.
+    /// Returns None once the runner has gone further than the banshee's run.
+    pub fn compare_at_distance(
+        &self,
+        runner_distance_m: f64,
+        runner_elapsed_ms: i64,
+    ) -> Option<DistanceComparison> {
+        let banshee_time_ms = self.time_at_distance(runner_distance_m)?;
+
+        Some(DistanceComparison {
+            distance_m: runner_distance_m,
+            runner_time_ms: runner_elapsed_ms,
+            banshee_time_ms,
+            time_delta_ms: runner_elapsed_ms - banshee_time_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn banshee() -> BansheeSession {
+        // ~111m per minute heading north
+        let start = Utc::now();
+        let points = (0..=10)
+            .map(|i| GpsPoint::new(51.5 + 0.001 * i as f64, -0.1, start + Duration::minutes(i)))
+            .collect();
+        BansheeSession::new("run".to_string(), points).unwrap()
+    }
+
+    #[test]
+    fn test_state_at() {
+        let session = banshee();
+        let state = session.state_at(5 * 60 * 1000);
+        assert!((state.lat - 51.505).abs() < 1e-9);
+        assert!((state.distance_meters - session.total_distance() / 2.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_compare_at_distance() {
+        let session = banshee();
+        let halfway = session.total_distance() / 2.0;
+
+        // Runner got halfway in 4:48, the banshee took 5:00
+        let comparison = session.compare_at_distance(halfway, 288_000).unwrap();
+        assert!((comparison.banshee_time_ms - 300_000).abs() < 10);
+        assert!(!comparison.is_banshee_ahead());
+        assert!((comparison.time_delta_ms + 12_000).abs() < 10);
+
+        assert!(session
+            .compare_at_distance(session.total_distance() + 10.0, 600_000)
+            .is_none());
+    }
+
+    #[test]
+    fn test_projected_time_past_finish() {
+        let session = banshee();
+        let total = session.total_distance();
+
+        assert_eq!(
+            session.projected_time_at_distance(total / 2.0),
+            session.time_at_distance(total / 2.0).unwrap()
+        );
+        // Half as far again at the same average pace: 15 minutes
+        let projected = session.projected_time_at_distance(total * 1.5);
+        assert!((projected - 900_000).abs() < 10);
+    }
+
+    #[test]
+    fn test_handicap() {
+        // 30s head start for the runner, and the banshee 2% faster
+        let session = banshee()
+            .with_handicap(ReplayHandicap {
+                start_offset_ms: 30_000,
+                speed_factor: 1.02,
+            })
+            .unwrap();
+        let halfway = session.total_distance() / 2.0;
+
+        assert_eq!(session.state_at(20_000).distance_meters, 0.0);
+        // 5:00 into the recording is 30s + 300s / 1.02 on the runner's clock
+        let at = 30_000 + (300_000.0 / 1.02) as i64;
+        assert!((session.state_at(at).distance_meters - halfway).abs() < 0.5);
+        let comparison = session.compare_at_distance(halfway, 300_000).unwrap();
+        assert!((comparison.banshee_time_ms - at).abs() < 10);
+        assert_eq!(
+            session.duration_ms(),
+            30_000 + (600_000.0_f64 / 1.02).round() as i64
+        );
+
+        // Starting a minute early puts the banshee a tenth of the way along
+        let early = banshee()
+            .with_handicap(ReplayHandicap {
+                start_offset_ms: -60_000,
+                speed_factor: 1.0,
+            })
+            .unwrap();
+        assert!((early.distance_at(0) - early.total_distance() / 10.0).abs() < 0.5);
+
+        let invalid = ReplayHandicap {
+            start_offset_ms: 0,
+            speed_factor: -1.0,
+        };
+        assert!(banshee().with_handicap(invalid).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::{Lap, StepLength, Workout, WorkoutStep};
+
+use super::pacer::AiPacer;
+
+/// What happened
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorkoutEventKind {
+    /// A step began (the previous one, if any, just ended)
+    StepStarted,
+    /// The last step ended
+    WorkoutFinished,
+}
+
+/// A step change, timed at the boundary rather than the sample that found it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorkoutEvent {
+    pub kind: WorkoutEventKind,
+    /// Index into `Workout::steps` of the step that started (or, when
+    /// finished, the last step)
+    pub step_index: usize,
+    pub step: WorkoutStep,
+    pub runner_distance_m: f64,
+    pub runner_elapsed_ms: i64,
+}
+
+/// Where the runner is in the workout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorkoutProgress {
+    /// Current step (the last one once finished)
+    pub step_index: usize,
+    pub step: WorkoutStep,
+    pub step_elapsed_ms: i64,
+    pub step_distance_m: f64,
+    /// Fraction of the step done, 0 to 1
+    pub step_fraction: f64,
+    /// Distance the step's pacer is ahead of the runner (positive = pacer
+    /// ahead); None for steps without a pace
+    pub pacer_gap_m: Option<f64>,
+    pub finished: bool,
+}
+
+/// A step's start or end
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Mark {
+    elapsed_ms: i64,
+    distance_m: f64,
+}
+
+/// Runs a structured workout against the runner's progress: moves through
+/// the steps as each one's distance or time is covered, and paces each step
+/// with an AI pacer that sets off when the step starts.
+#[derive(Debug, Clone)]
+pub struct WorkoutSession {
+    steps: Vec<WorkoutStep>,
+    /// Start of each step reached so far
+    starts: Vec<Mark>,
+    /// End of the last step, once finished
+    finish: Option<Mark>,
+    last_sample: Option<Mark>,
+}
+
+impl WorkoutSession {
+    pub fn new(workout: &Workout) -> anyhow::Result<Self> {
+        workout.validate()?;
+        Ok(Self {
+            steps: workout.steps(),
+            starts: Vec::new(),
+            finish: None,
+            last_sample: None,
+        })
+    }
+
+    pub fn steps(&self) -> &[WorkoutStep] {
+        &self.steps
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finish.is_some()
+    }
+
+    /// Runner's elapsed time at the latest sample
+    pub fn elapsed_ms(&self) -> i64 {
+        self.last_sample.map_or(0, |s| s.elapsed_ms)
+    }
+
+    /// Pacer for a step, if it has a pace
+    fn pacer(step: &WorkoutStep) -> Option<AiPacer> {
+        step.pace_sec_per_km.and_then(|p| AiPacer::new(p).ok())
+    }
+
+    /// When the current step ends, if it ends between `from` and `to`
+    fn step_end(step: &WorkoutStep, start: Mark, from: Mark, to: Mark) -> Option<Mark> {
+        let interpolate = |fraction: f64| Mark {
+            elapsed_ms: from.elapsed_ms
+                + ((to.elapsed_ms - from.elapsed_ms) as f64 * fraction).round() as i64,
+            distance_m: from.distance_m + (to.distance_m - from.distance_m) * fraction,
+        };
+
+        match step.length {
+            StepLength::Distance { meters } => {
+                let end = start.distance_m + meters;
+                if to.distance_m < end {
+                    return None;
+                }
+                let covered = to.distance_m - from.distance_m;
+                let fraction = if covered > 0.0 {
+                    ((end - from.distance_m) / covered).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                Some(Mark {
+                    distance_m: end,
+                    ..interpolate(fraction)
+                })
+            }
+            StepLength::Time { ms } => {
+                let end = start.elapsed_ms + ms;
+                if to.elapsed_ms < end {
+                    return None;
+                }
+                let span = to.elapsed_ms - from.elapsed_ms;
+                let fraction = if span > 0 {
+                    ((end - from.elapsed_ms) as f64 / span as f64).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                Some(Mark {
+                    elapsed_ms: end,
+                    ..interpolate(fraction)
+                })
+            }
+        }
+    }
+
+    /// Feed the runner's progress, returning the step changes it caused.
+    /// The first sample starts the first step.
+    pub fn update(&mut self, runner_distance_m: f64, runner_elapsed_ms: i64) -> Vec<WorkoutEvent> {
+        let sample = Mark {
+            elapsed_ms: runner_elapsed_ms,
+            distance_m: runner_distance_m,
+        };
+        let mut events = Vec::new();
+        if self.finish.is_some() {
+            return events;
+        }
+
+        let mut event = |kind, step_index: usize, mark: Mark, steps: &[WorkoutStep]| {
+            events.push(WorkoutEvent {
+                kind,
+                step_index,
+                step: steps[step_index],
+                runner_distance_m: mark.distance_m,
+                runner_elapsed_ms: mark.elapsed_ms,
+            })
+        };
+
+        let mut from = match self.last_sample {
+            Some(last) => last,
+            None => {
+                self.starts.push(sample);
+                event(WorkoutEventKind::StepStarted, 0, sample, &self.steps);
+                sample
+            }
+        };
+
+        // A long gap between samples can end several steps at once
+        loop {
+            let index = self.starts.len() - 1;
+            let start = self.starts[index];
+            let Some(end) = Self::step_end(&self.steps[index], start, from, sample) else {
+                break;
+            };
+
+            if index + 1 == self.steps.len() {
+                self.finish = Some(end);
+                event(WorkoutEventKind::WorkoutFinished, index, end, &self.steps);
+                break;
+            }
+            self.starts.push(end);
+            event(WorkoutEventKind::StepStarted, index + 1, end, &self.steps);
+            from = end;
+        }
+
+        self.last_sample = Some(sample);
+        events
+    }
+
+    /// Progress at the latest sample; None before the first
+    pub fn progress(&self) -> Option<WorkoutProgress> {
+        let sample = self.last_sample?;
+        let step_index = self.starts.len() - 1;
+        let step = self.steps[step_index];
+        let start = self.starts[step_index];
+        let now = self.finish.unwrap_or(sample);
+
+        let step_elapsed_ms = now.elapsed_ms - start.elapsed_ms;
+        let step_distance_m = now.distance_m - start.distance_m;
+        let step_fraction = match step.length {
+            StepLength::Distance { meters } => step_distance_m / meters,
+            StepLength::Time { ms } => step_elapsed_ms as f64 / ms as f64,
+        };
+        let pacer_gap_m = Self::pacer(&step).map(|pacer| {
+            let pacer_distance = match step.length {
+                StepLength::Distance { meters } => pacer.distance_at(step_elapsed_ms).min(meters),
+                StepLength::Time { .. } => pacer.distance_at(step_elapsed_ms),
+            };
+            pacer_distance - step_distance_m
+        });
+
+        Some(WorkoutProgress {
+            step_index,
+            step,
+            step_elapsed_ms,
+            step_distance_m,
+            step_fraction: step_fraction.clamp(0.0, 1.0),
+            pacer_gap_m,
+            finished: self.finish.is_some(),
+        })
+    }
+
+    /// One lap per step started so far, the current one up to the latest
+    /// sample. `start_time` is the wall-clock time of elapsed zero.
+    pub fn laps(&self, start_time: DateTime<Utc>) -> Vec<Lap> {
+        let ends = self
+            .starts
+            .iter()
+            .skip(1)
+            .copied()
+            .chain(self.finish.or(self.last_sample));
+        let at = |mark: Mark| start_time + Duration::milliseconds(mark.elapsed_ms);
+
+        self.starts
+            .iter()
+            .zip(ends)
+            .zip(&self.steps)
+            .filter(|((start, end), _)| end.elapsed_ms > start.elapsed_ms)
+            .map(|((&start, end), step)| Lap {
+                start_time: at(start),
+                end_time: at(end),
+                distance_meters: end.distance_m - start.distance_m,
+                step_kind: Some(step.kind),
+                target_pace_sec_per_km: step.pace_sec_per_km,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::StepKind;
+
+    /// 10 min warmup, 3 x (400m at 4:00/km, 1 min standing), 400m cooldown
+    fn workout() -> Workout {
+        Workout::new(
+            "Intervals".to_string(),
+            Some(WorkoutStep {
+                kind: StepKind::Warmup,
+                length: StepLength::Time { ms: 600_000 },
+                pace_sec_per_km: None,
+            }),
+            3,
+            WorkoutStep {
+                kind: StepKind::Work,
+                length: StepLength::Distance { meters: 400.0 },
+                pace_sec_per_km: Some(240.0),
+            },
+            Some(WorkoutStep {
+                kind: StepKind::Recovery,
+                length: StepLength::Time { ms: 60_000 },
+                pace_sec_per_km: None,
+            }),
+            Some(WorkoutStep {
+                kind: StepKind::Cooldown,
+                length: StepLength::Distance { meters: 400.0 },
+                pace_sec_per_km: None,
+            }),
+        )
+    }
+
+    fn kinds(events: &[WorkoutEvent]) -> Vec<(WorkoutEventKind, usize)> {
+        events.iter().map(|e| (e.kind, e.step_index)).collect()
+    }
+
+    #[test]
+    fn test_steps_advance_at_boundaries() {
+        let mut session = WorkoutSession::new(&workout()).unwrap();
+        assert!(session.progress().is_none());
+
+        assert_eq!(
+            kinds(&session.update(0.0, 0)),
+            vec![(WorkoutEventKind::StepStarted, 0)]
+        );
+        assert!(session.update(1500.0, 590_000).is_empty());
+
+        // The warmup ends at 10:00, between these samples
+        let events = session.update(1530.0, 610_000);
+        assert_eq!(kinds(&events), vec![(WorkoutEventKind::StepStarted, 1)]);
+        assert_eq!(events[0].runner_elapsed_ms, 600_000);
+        assert!((events[0].runner_distance_m - 1515.0).abs() < 1e-9);
+
+        // 10s into a 4:00/km rep the pacer has done ~41.7m
+        let progress = session.progress().unwrap();
+        assert_eq!(progress.step.kind, StepKind::Work);
+        assert_eq!(progress.step_elapsed_ms, 10_000);
+        assert!((progress.pacer_gap_m.unwrap() - (41.667 - 15.0)).abs() < 0.01);
+
+        // Rep done at 1915m
+        let events = session.update(1935.0, 700_000);
+        assert_eq!(kinds(&events), vec![(WorkoutEventKind::StepStarted, 2)]);
+        assert!((events[0].runner_distance_m - 1915.0).abs() < 1e-9);
+        assert_eq!(session.progress().unwrap().pacer_gap_m, None);
+    }
+
+    #[test]
+    fn test_finish_and_laps() {
+        let mut session = WorkoutSession::new(&workout()).unwrap();
+        session.update(0.0, 0);
+
+        // One sample that covers everything: every step change is raised
+        let events = session.update(5000.0, 5_000_000);
+        assert_eq!(events.len(), session.steps().len());
+        assert_eq!(
+            events.last().map(|e| e.kind),
+            Some(WorkoutEventKind::WorkoutFinished)
+        );
+        assert!(session.is_finished());
+        assert!(session.update(6000.0, 6_000_000).is_empty());
+
+        let start = Utc::now();
+        let laps = session.laps(start);
+        assert_eq!(laps.len(), session.steps().len());
+        assert_eq!(laps[0].start_time, start);
+        assert_eq!(laps[0].duration_ms(), 600_000);
+        assert_eq!(laps[1].step_kind, Some(StepKind::Work));
+        assert!((laps[1].distance_meters - 400.0).abs() < 1e-9);
+        assert_eq!(laps[1].target_pace_sec_per_km, Some(240.0));
+        for pair in laps.windows(2) {
+            assert_eq!(pair[0].end_time, pair[1].start_time);
+        }
+    }
+
+    #[test]
+    fn test_laps_include_the_step_in_progress() {
+        let mut session = WorkoutSession::new(&workout()).unwrap();
+        session.update(0.0, 0);
+        session.update(1600.0, 650_000);
+
+        let laps = session.laps(Utc::now());
+        assert_eq!(laps.len(), 2);
+        assert_eq!(laps[1].duration_ms(), 50_000);
+        assert!(!session.is_finished());
+    }
+}
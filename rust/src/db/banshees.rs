@@ -0,0 +1,165 @@
+use anyhow::Result;
+use rusqlite::OptionalExtension;
+
+use super::Database;
+use crate::models::{Banshee, SavedBanshee};
+
+impl Database {
+    /// Save a banshee to the library. Saving an existing entry updates its
+    /// configuration and keeps its record.
+    pub fn save_banshee(&self, saved: &SavedBanshee) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO banshees (id, name, config_json, created_at, last_used_at, wins, losses)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET
+                 name = excluded.name,
+                 config_json = excluded.config_json",
+            rusqlite::params![
+                saved.id,
+                saved.banshee.name,
+                serde_json::to_string(&saved.banshee)?,
+                saved.created_at.to_rfc3339(),
+                saved.last_used_at.map(|t| t.to_rfc3339()),
+                saved.wins,
+                saved.losses,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a saved banshee
+    pub fn get_banshee(&self, id: &str) -> Result<Option<SavedBanshee>> {
+        let conn = self.conn.lock().unwrap();
+        let saved = conn
+            .query_row(
+                "SELECT id, config_json, created_at, last_used_at, wins, losses
+                 FROM banshees WHERE id = ?1",
+                [id],
+                saved_banshee_from_row,
+            )
+            .optional()?;
+        Ok(saved)
+    }
+
+    /// List saved banshees, most recently used first, then never-used ones by name
+    pub fn list_banshees(&self) -> Result<Vec<SavedBanshee>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, config_json, created_at, last_used_at, wins, losses
+             FROM banshees
+             ORDER BY last_used_at IS NULL, last_used_at DESC, name COLLATE NOCASE, id",
+        )?;
+
+        let banshees = stmt.query_map([], saved_banshee_from_row)?;
+        Ok(banshees.filter_map(|b| b.ok()).collect())
+    }
+
+    /// Delete a saved banshee. Returns false if it didn't exist.
+    pub fn delete_banshee(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM banshees WHERE id = ?1", [id])?;
+        Ok(rows > 0)
+    }
+
+    /// Note that a saved banshee is being raced now. Returns false if it doesn't exist.
+    pub fn mark_banshee_used(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE banshees SET last_used_at = ?2 WHERE id = ?1",
+            rusqlite::params![id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Add a race result to a saved banshee's record. Returns false if it doesn't exist.
+    pub fn record_banshee_result(&self, id: &str, runner_won: bool) -> Result<bool> {
+        let column = if runner_won { "wins" } else { "losses" };
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            &format!(
+                "UPDATE banshees SET {column} = {column} + 1, last_used_at = ?2 WHERE id = ?1"
+            ),
+            rusqlite::params![id, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(rows > 0)
+    }
+}
+
+/// Build a saved banshee from `id, config_json, created_at, last_used_at, wins, losses`
+fn saved_banshee_from_row(row: &rusqlite::Row) -> rusqlite::Result<SavedBanshee> {
+    let config_json: String = row.get(1)?;
+    let banshee: Banshee = serde_json::from_str(&config_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    let parse_time = |s: String| {
+        chrono::DateTime::parse_from_rfc3339(&s)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .ok()
+    };
+
+    Ok(SavedBanshee {
+        id: row.get(0)?,
+        banshee,
+        created_at: parse_time(row.get(2)?).unwrap_or_else(chrono::Utc::now),
+        last_used_at: row.get::<_, Option<String>>(3)?.and_then(parse_time),
+        wins: row.get(4)?,
+        losses: row.get(5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BansheeType, PacePlan, PaceSegment};
+
+    #[test]
+    fn test_banshee_library() {
+        let db = Database::open(":memory:").unwrap();
+
+        let plan = PacePlan::Segments(vec![PaceSegment {
+            until_distance_m: 5000.0,
+            pace_sec_per_km: 290.0,
+        }]);
+        let mut pacer = SavedBanshee::new(Banshee::ai_pacer_with_plan(plan, "Sub 25".to_string()));
+        let ghost = SavedBanshee::new(Banshee::from_run("run-1".to_string(), "PB".to_string()));
+        db.save_banshee(&pacer).unwrap();
+        db.save_banshee(&ghost).unwrap();
+
+        let loaded = db.get_banshee(&pacer.id).unwrap().unwrap();
+        assert_eq!(loaded.banshee.banshee_type, pacer.banshee.banshee_type);
+        assert!(loaded.last_used_at.is_none());
+
+        // Results update the record and move the banshee to the top
+        assert!(db.record_banshee_result(&ghost.id, true).unwrap());
+        assert!(db.record_banshee_result(&ghost.id, false).unwrap());
+        assert!(db.record_banshee_result(&ghost.id, true).unwrap());
+        let list = db.list_banshees().unwrap();
+        assert_eq!(list[0].id, ghost.id);
+        assert_eq!((list[0].wins, list[0].losses), (2, 1));
+        assert!(list[0].last_used_at.is_some());
+
+        // Editing keeps the record
+        pacer.banshee.name = "Sub 24".to_string();
+        db.save_banshee(&pacer).unwrap();
+        db.record_banshee_result(&pacer.id, false).unwrap();
+        let mut renamed = SavedBanshee::new(Banshee::ai_pacer(300.0, "x".to_string()));
+        renamed.id = pacer.id.clone();
+        db.save_banshee(&renamed).unwrap();
+        let edited = db.get_banshee(&pacer.id).unwrap().unwrap();
+        assert_eq!(edited.banshee.name, "x");
+        assert!(matches!(
+            edited.banshee.banshee_type,
+            BansheeType::AiPacer {
+                pace_plan: None,
+                ..
+            }
+        ));
+        assert_eq!(edited.losses, 1);
+
+        assert!(db.mark_banshee_used(&pacer.id).unwrap());
+        assert!(db.delete_banshee(&pacer.id).unwrap());
+        assert!(db.get_banshee(&pacer.id).unwrap().is_none());
+        assert!(!db.record_banshee_result(&pacer.id, true).unwrap());
+    }
+}
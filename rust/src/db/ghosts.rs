@@ -0,0 +1,213 @@
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension};
+
+use super::Database;
+use crate::models::{GhostSource, GhostTrack, GhostTrackSummary, GpsPoint};
+
+impl Database {
+    /// Save a ghost track and its points, replacing any with the same ID
+    pub fn save_ghost_track(&self, ghost: &GhostTrack) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO ghost_tracks
+             (id, name, source_json, created_at, distance_meters, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                ghost.id,
+                ghost.name,
+                serde_json::to_string(&ghost.source)?,
+                ghost.created_at.to_rfc3339(),
+                ghost.distance_meters,
+                ghost.duration_ms,
+            ],
+        )?;
+
+        tx.execute("DELETE FROM ghost_points WHERE ghost_id = ?1", [&ghost.id])?;
+        for (idx, point) in ghost.points.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO ghost_points (ghost_id, point_index, lat, lon, altitude, timestamp, accuracy, speed)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                rusqlite::params![
+                    ghost.id,
+                    idx as i64,
+                    point.lat,
+                    point.lon,
+                    point.altitude,
+                    point.timestamp.to_rfc3339(),
+                    point.accuracy,
+                    point.speed,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a ghost track with its points
+    pub fn get_ghost_track(&self, id: &str) -> Result<Option<GhostTrack>> {
+        let conn = self.conn.lock().unwrap();
+
+        let summary = conn
+            .query_row(
+                "SELECT id, name, source_json, created_at, distance_meters, duration_ms
+                 FROM ghost_tracks WHERE id = ?1",
+                [id],
+                ghost_summary_from_row,
+            )
+            .optional()?;
+        let Some(summary) = summary else {
+            return Ok(None);
+        };
+
+        Ok(Some(GhostTrack {
+            points: load_ghost_points(&conn, id)?,
+            id: summary.id,
+            name: summary.name,
+            source: summary.source,
+            created_at: summary.created_at,
+            distance_meters: summary.distance_meters,
+            duration_ms: summary.duration_ms,
+        }))
+    }
+
+    /// List ghost tracks without their points, newest first
+    pub fn list_ghost_tracks(&self) -> Result<Vec<GhostTrackSummary>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, source_json, created_at, distance_meters, duration_ms
+             FROM ghost_tracks ORDER BY created_at DESC, id",
+        )?;
+
+        let ghosts = stmt.query_map([], ghost_summary_from_row)?;
+        Ok(ghosts.filter_map(|g| g.ok()).collect())
+    }
+
+    /// Delete a ghost track and its points. Returns false if it didn't exist.
+    pub fn delete_ghost_track(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM ghost_points WHERE ghost_id = ?1", [id])?;
+        let rows = conn.execute("DELETE FROM ghost_tracks WHERE id = ?1", [id])?;
+        Ok(rows > 0)
+    }
+}
+
+/// Build a ghost summary from `id, name, source_json, created_at,
+/// distance_meters, duration_ms`
+fn ghost_summary_from_row(row: &rusqlite::Row) -> rusqlite::Result<GhostTrackSummary> {
+    let source_json: String = row.get(2)?;
+    let source: GhostSource = serde_json::from_str(&source_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    let created_at_str: String = row.get(3)?;
+    let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+
+    Ok(GhostTrackSummary {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source,
+        created_at,
+        distance_meters: row.get(4)?,
+        duration_ms: row.get(5)?,
+    })
+}
+
+/// Load a ghost track's points in order
+fn load_ghost_points(conn: &Connection, ghost_id: &str) -> Result<Vec<GpsPoint>> {
+    let mut stmt = conn.prepare(
+        "SELECT lat, lon, altitude, timestamp, accuracy, speed
+         FROM ghost_points WHERE ghost_id = ?1 ORDER BY point_index",
+    )?;
+
+    let points = stmt.query_map([ghost_id], |row| {
+        let timestamp_str: String = row.get(3)?;
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&timestamp_str)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now());
+
+        Ok(GpsPoint {
+            lat: row.get(0)?,
+            lon: row.get(1)?,
+            altitude: row.get(2)?,
+            timestamp,
+            accuracy: row.get(4)?,
+            speed: row.get(5)?,
+        })
+    })?;
+
+    Ok(points.filter_map(|p| p.ok()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CompositeSplit;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_ghost_tracks() {
+        let db = Database::open(":memory:").unwrap();
+        let start = Utc::now();
+        let points = (0..5)
+            .map(|i| {
+                GpsPoint::new(
+                    51.5 + 0.001 * i as f64,
+                    -0.1,
+                    start + Duration::seconds(30 * i),
+                )
+                .with_altitude(10.0)
+            })
+            .collect();
+        let source = GhostSource::Composite {
+            split_distance_m: 1000.0,
+            splits: vec![CompositeSplit {
+                number: 1,
+                run_id: "run".to_string(),
+                duration_ms: 120_000,
+            }],
+        };
+        let ghost = GhostTrack::new("Best of".to_string(), source, points);
+        db.save_ghost_track(&ghost).unwrap();
+
+        let loaded = db.get_ghost_track(&ghost.id).unwrap().unwrap();
+        assert_eq!(loaded.points.len(), 5);
+        assert_eq!(loaded.points[4].altitude, Some(10.0));
+        assert_eq!(loaded.source, ghost.source);
+        assert_eq!(loaded.duration_ms, 120_000);
+        assert!((loaded.distance_meters - ghost.distance_meters).abs() < 1e-9);
+
+        let list = db.list_ghost_tracks().unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].name, "Best of");
+
+        assert!(db.delete_ghost_track(&ghost.id).unwrap());
+        assert!(db.get_ghost_track(&ghost.id).unwrap().is_none());
+        assert!(!db.delete_ghost_track(&ghost.id).unwrap());
+    }
+
+    #[test]
+    fn test_imported_ghost_is_not_a_run() {
+        let db = Database::open(":memory:").unwrap();
+        let start = Utc::now();
+        let points = vec![
+            GpsPoint::new(51.5, -0.1, start),
+            GpsPoint::new(51.501, -0.1, start + Duration::seconds(30)),
+        ];
+        let source = GhostSource::Imported {
+            format: "gpx".to_string(),
+            file_name: Some("parkrun.gpx".to_string()),
+        };
+        let ghost = GhostTrack::new("Parkrun".to_string(), source, points);
+        db.save_ghost_track(&ghost).unwrap();
+
+        let loaded = db.get_ghost_track(&ghost.id).unwrap().unwrap();
+        assert_eq!(loaded.source, ghost.source);
+        assert!(db.get_run(&ghost.id).unwrap().is_none());
+        let runs = db.query_runs(&crate::db::RunQuery::default()).unwrap();
+        assert!(runs.runs.is_empty());
+    }
+}
@@ -1,11 +1,29 @@
+mod banshees;
+mod ghosts;
+mod plans;
+pub mod query;
+mod routes;
+mod same_route;
 pub mod schema;
+mod segments;
+mod settings;
+mod workouts;
+
+pub use query::{RunPage, RunQuery, RunSortField};
 
 use anyhow::Result;
 use rusqlite::Connection;
 use std::path::Path;
 use std::sync::Mutex;
 
-use crate::models::{GpsPoint, Run, RunSummary};
+use crate::models::run::normalize_tags;
+use crate::models::{
+    GpsPoint, Lap, Pause, RaceReport, Run, RunMetadata, RunMetadataPatch, RunSummary, RunType,
+    StepKind, TrashedRun,
+};
+
+/// How long deleted runs stay in the trash before being purged
+pub const TRASH_RETENTION_DAYS: i64 = 30;
 
 /// Database wrapper for SQLite operations
 pub struct Database {
@@ -16,6 +34,9 @@ impl Database {
     /// Open or create a database at the given path
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let conn = Connection::open(path)?;
+        // SQLite leaves foreign keys off per connection; the schema's
+        // ON DELETE CASCADE clauses rely on them
+        conn.pragma_update(None, "foreign_keys", true)?;
         let db = Self {
             conn: Mutex::new(conn),
         };
@@ -25,183 +46,464 @@ impl Database {
 
     /// Initialize database schema
     fn init_schema(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
         conn.execute_batch(schema::CREATE_TABLES)?;
+
+        // Apply any migrations this database hasn't seen yet. Each one commits
+        // together with its version bump, so an interrupted migration is
+        // simply run again on the next open.
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (idx, migration) in schema::MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", (idx + 1) as i64)?;
+            tx.commit()?;
+        }
+
         Ok(())
     }
 
     /// Save a run to the database
     pub fn save_run(&self, run: &Run) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        save_run(&tx, run)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a run by ID (runs in the trash are not returned)
+    pub fn get_run(&self, id: &str) -> Result<Option<Run>> {
         let conn = self.conn.lock().unwrap();
+        load_run(&conn, id)
+    }
 
-        // Insert or replace run
-        conn.execute(
-            "INSERT OR REPLACE INTO runs (id, name, start_time, end_time, distance_meters, duration_ms, avg_pace_sec_per_km)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            rusqlite::params![
-                run.id,
-                run.name,
-                run.start_time.to_rfc3339(),
-                run.end_time.map(|t| t.to_rfc3339()),
-                run.distance_meters,
-                run.duration_ms,
-                run.avg_pace_sec_per_km,
-            ],
+    /// Get a run's user-editable metadata without loading its GPS points
+    pub fn get_run_metadata(&self, id: &str) -> Result<Option<RunMetadata>> {
+        let conn = self.conn.lock().unwrap();
+
+        let metadata = conn.query_row(
+            "SELECT notes, run_type, perceived_exertion, weather FROM runs
+             WHERE id = ?1 AND deleted_at IS NULL",
+            [id],
+            |row| metadata_from_row(row, 0),
+        );
+
+        match metadata {
+            Ok(mut metadata) => {
+                metadata.tags = load_tags(&conn, id)?;
+                Ok(Some(metadata))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get a run's pauses without loading its GPS points
+    pub fn get_run_pauses(&self, id: &str) -> Result<Vec<Pause>> {
+        let conn = self.conn.lock().unwrap();
+        load_pauses(&conn, id)
+    }
+
+    /// Get a run's laps without loading its GPS points
+    pub fn get_run_laps(&self, id: &str) -> Result<Vec<Lap>> {
+        let conn = self.conn.lock().unwrap();
+        load_laps(&conn, id)
+    }
+
+    /// Replace a run's laps. Returns false if the run doesn't exist.
+    pub fn set_run_laps(&self, id: &str, laps: &[Lap]) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM runs WHERE id = ?1 AND deleted_at IS NULL)",
+            [id],
+            |row| row.get(0),
         )?;
+        if exists {
+            save_laps(&conn, id, laps)?;
+        }
+        Ok(exists)
+    }
 
-        // Delete existing points for this run
-        conn.execute("DELETE FROM gps_points WHERE run_id = ?1", [&run.id])?;
-
-        // Insert all GPS points
-        for (idx, point) in run.points.iter().enumerate() {
-            conn.execute(
-                "INSERT INTO gps_points (run_id, point_index, lat, lon, altitude, timestamp, accuracy, speed)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                rusqlite::params![
-                    run.id,
-                    idx as i64,
-                    point.lat,
-                    point.lon,
-                    point.altitude,
-                    point.timestamp.to_rfc3339(),
-                    point.accuracy,
-                    point.speed,
-                ],
-            )?;
+    /// Merge several runs into a new run. The originals go to the trash so
+    /// the merge can be undone by restoring them and deleting the new run.
+    pub fn merge_runs(&self, ids: &[String]) -> Result<Run> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let runs = ids
+            .iter()
+            .map(|id| load_run(&tx, id)?.ok_or_else(|| anyhow::anyhow!("Run not found: {id}")))
+            .collect::<Result<Vec<_>>>()?;
+
+        let merged = Run::merge(runs)?;
+        save_run(&tx, &merged)?;
+        for id in ids {
+            trash_run(&tx, id)?;
         }
 
+        tx.commit()?;
+        Ok(merged)
+    }
+
+    /// Split a run in two at `at`. The original goes to the trash so the
+    /// split can be undone.
+    pub fn split_run(&self, id: &str, at: chrono::DateTime<chrono::Utc>) -> Result<(Run, Run)> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let run = load_run(&tx, id)?.ok_or_else(|| anyhow::anyhow!("Run not found: {id}"))?;
+
+        let (before, after) = run.split_at(at)?;
+        save_run(&tx, &before)?;
+        save_run(&tx, &after)?;
+        trash_run(&tx, id)?;
+
+        tx.commit()?;
+        Ok((before, after))
+    }
+
+    /// Trim a run's start and end by elapsed time, keeping the original
+    /// recoverable until `confirm_run_edit` or `revert_run_edit`
+    pub fn trim_run(&self, id: &str, start_offset_ms: i64, end_offset_ms: i64) -> Result<Run> {
+        self.edit_run_with_backup(id, |run| run.trim(start_offset_ms, end_offset_ms))
+    }
+
+    /// Trim a run's start and end by distance, keeping the original
+    /// recoverable until `confirm_run_edit` or `revert_run_edit`
+    pub fn trim_run_by_distance(&self, id: &str, start_m: f64, end_m: f64) -> Result<Run> {
+        self.edit_run_with_backup(id, |run| run.trim_by_distance(start_m, end_m))
+    }
+
+    /// Apply `edit` to a run and save it. The first unconfirmed edit stores
+    /// a backup of the original; further edits build on the edited run but
+    /// keep that first backup.
+    fn edit_run_with_backup<F>(&self, id: &str, edit: F) -> Result<Run>
+    where
+        F: FnOnce(&mut Run) -> Result<()>,
+    {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut run = load_run(&tx, id)?.ok_or_else(|| anyhow::anyhow!("Run not found: {id}"))?;
+        let original = serde_json::to_string(&run)?;
+
+        edit(&mut run)?;
+
+        tx.execute(
+            "INSERT OR IGNORE INTO run_backups (run_id, run_json, created_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![id, original, chrono::Utc::now().to_rfc3339()],
+        )?;
+        save_run(&tx, &run)?;
+
+        tx.commit()?;
+        Ok(run)
+    }
+
+    /// Check whether a run has an unconfirmed edit that can be reverted
+    pub fn has_pending_edit(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM run_backups WHERE run_id = ?1",
+            [id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Keep a run's edits and discard the backup of the original
+    pub fn confirm_run_edit(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM run_backups WHERE run_id = ?1", [id])?;
+        Ok(rows > 0)
+    }
+
+    /// Restore a run to how it was before its unconfirmed edits.
+    /// Returns the restored run, or None if there was nothing to revert.
+    pub fn revert_run_edit(&self, id: &str) -> Result<Option<Run>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let json: String = match tx.query_row(
+            "SELECT run_json FROM run_backups WHERE run_id = ?1",
+            [id],
+            |row| row.get(0),
+        ) {
+            Ok(json) => json,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let original: Run = serde_json::from_str(&json)?;
+        save_run(&tx, &original)?;
+        tx.execute("DELETE FROM run_backups WHERE run_id = ?1", [id])?;
+
+        tx.commit()?;
+        Ok(Some(original))
+    }
+
+    /// Store the race reports for a run, replacing any it already had
+    pub fn save_race_reports(&self, run_id: &str, reports: &[RaceReport]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM race_reports WHERE run_id = ?1", [run_id])?;
+        let now = chrono::Utc::now().to_rfc3339();
+        for report in reports {
+            tx.execute(
+                "INSERT INTO race_reports (run_id, report_json, created_at) VALUES (?1, ?2, ?3)",
+                rusqlite::params![run_id, serde_json::to_string(report)?, now],
+            )?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
-    /// Get a run by ID
-    pub fn get_run(&self, id: &str) -> Result<Option<Run>> {
+    /// Get the race reports stored with a run, in the order they were saved
+    pub fn get_race_reports(&self, run_id: &str) -> Result<Vec<RaceReport>> {
         let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT report_json FROM race_reports WHERE run_id = ?1 ORDER BY id")?;
+        let rows = stmt.query_map([run_id], |row| row.get::<_, String>(0))?;
 
-        let mut stmt = conn.prepare(
-            "SELECT id, name, start_time, end_time, distance_meters, duration_ms, avg_pace_sec_per_km
-             FROM runs WHERE id = ?1",
-        )?;
+        let mut reports = Vec::new();
+        for json in rows {
+            reports.push(serde_json::from_str(&json?)?);
+        }
+        Ok(reports)
+    }
 
-        let run = stmt.query_row([id], |row| {
-            let id: String = row.get(0)?;
-            let name: Option<String> = row.get(1)?;
-            let start_time_str: String = row.get(2)?;
-            let end_time_str: Option<String> = row.get(3)?;
-            let distance_meters: f64 = row.get(4)?;
-            let duration_ms: i64 = row.get(5)?;
-            let avg_pace_sec_per_km: Option<f64> = row.get(6)?;
+    /// Update a run's name and metadata in place, leaving its GPS points untouched.
+    /// Returns false if the run doesn't exist.
+    pub fn update_run_metadata(&self, id: &str, patch: &RunMetadataPatch) -> Result<bool> {
+        if let Some(Some(rpe)) = patch.perceived_exertion {
+            anyhow::ensure!(
+                (1..=10).contains(&rpe),
+                "Perceived exertion must be between 1 and 10"
+            );
+        }
 
-            let start_time = chrono::DateTime::parse_from_rfc3339(&start_time_str)
-                .map(|dt| dt.with_timezone(&chrono::Utc))
-                .unwrap_or_else(|_| chrono::Utc::now());
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let current = tx.query_row(
+            "SELECT name, notes, run_type, perceived_exertion, weather FROM runs
+             WHERE id = ?1 AND deleted_at IS NULL",
+            [id],
+            |row| Ok((row.get::<_, Option<String>>(0)?, metadata_from_row(row, 1)?)),
+        );
+
+        let (mut name, mut metadata) = match current {
+            Ok(current) => current,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        metadata.tags = load_tags(&tx, id)?;
 
-            let end_time = end_time_str.and_then(|s| {
-                chrono::DateTime::parse_from_rfc3339(&s)
-                    .map(|dt| dt.with_timezone(&chrono::Utc))
-                    .ok()
-            });
+        patch.apply(&mut name, &mut metadata);
 
-            Ok(Run {
+        tx.execute(
+            "UPDATE runs SET name = ?2, notes = ?3, run_type = ?4, perceived_exertion = ?5, weather = ?6
+             WHERE id = ?1",
+            rusqlite::params![
                 id,
                 name,
-                start_time,
-                end_time,
-                points: Vec::new(),
-                distance_meters,
-                duration_ms,
-                avg_pace_sec_per_km,
-            })
-        });
+                metadata.notes,
+                metadata.run_type.map(|t| t.as_str()),
+                metadata.perceived_exertion,
+                metadata.weather,
+            ],
+        )?;
+        save_tags(&tx, id, &metadata.tags)?;
+
+        tx.commit()?;
+        Ok(true)
+    }
 
-        match run {
-            Ok(mut run) => {
-                // Load GPS points
-                let mut point_stmt = conn.prepare(
-                    "SELECT lat, lon, altitude, timestamp, accuracy, speed
-                     FROM gps_points WHERE run_id = ?1 ORDER BY point_index",
-                )?;
-
-                let points = point_stmt.query_map([id], |row| {
-                    let lat: f64 = row.get(0)?;
-                    let lon: f64 = row.get(1)?;
-                    let altitude: Option<f64> = row.get(2)?;
-                    let timestamp_str: String = row.get(3)?;
-                    let accuracy: Option<f64> = row.get(4)?;
-                    let speed: Option<f64> = row.get(5)?;
-
-                    let timestamp = chrono::DateTime::parse_from_rfc3339(&timestamp_str)
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                        .unwrap_or_else(|_| chrono::Utc::now());
-
-                    Ok(GpsPoint {
-                        lat,
-                        lon,
-                        altitude,
-                        timestamp,
-                        accuracy,
-                        speed,
-                    })
-                })?;
-
-                run.points = points.filter_map(|p| p.ok()).collect();
-                Ok(Some(run))
+    /// Query run summaries with filters, ordering and cursor pagination
+    pub fn query_runs(&self, query: &RunQuery) -> Result<RunPage> {
+        use rusqlite::types::Value;
+
+        let conn = self.conn.lock().unwrap();
+
+        let mut conditions: Vec<String> = vec!["deleted_at IS NULL".to_string()];
+        let mut params: Vec<Value> = Vec::new();
+
+        let mut push = |condition: &str, value: Value| {
+            params.push(value);
+            conditions.push(condition.replace('?', &format!("?{}", params.len())));
+        };
+
+        if let Some(min) = query.min_distance_m {
+            push("distance_meters >= ?", Value::Real(min));
+        }
+        if let Some(max) = query.max_distance_m {
+            push("distance_meters <= ?", Value::Real(max));
+        }
+        if let Some(min) = query.min_duration_ms {
+            push("duration_ms >= ?", Value::Integer(min));
+        }
+        if let Some(max) = query.max_duration_ms {
+            push("duration_ms <= ?", Value::Integer(max));
+        }
+        if let Some(after) = query.start_after {
+            push("start_time >= ?", Value::Text(after.to_rfc3339()));
+        }
+        if let Some(before) = query.start_before {
+            push("start_time < ?", Value::Text(before.to_rfc3339()));
+        }
+        for tag in normalize_tags(&query.tags) {
+            push(
+                "EXISTS (SELECT 1 FROM run_tags t WHERE t.run_id = runs.id AND t.tag = ?)",
+                Value::Text(tag),
+            );
+        }
+        if let Some(text) = query.name_contains.as_deref().map(str::trim) {
+            if !text.is_empty() {
+                push(
+                    "name LIKE ? ESCAPE '\\'",
+                    Value::Text(format!("%{}%", query::escape_like(text))),
+                );
             }
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
         }
+
+        let column = query.sort_by.column();
+        let (direction, comparison) = if query.ascending {
+            ("ASC", ">")
+        } else {
+            ("DESC", "<")
+        };
+
+        if let Some(cursor) = &query.cursor {
+            let (key, id) = query::decode_cursor(query.sort_by, cursor)
+                .ok_or_else(|| anyhow::anyhow!("Invalid cursor"))?;
+            params.push(key);
+            let key_param = params.len();
+            params.push(Value::Text(id));
+            let id_param = params.len();
+            conditions.push(format!(
+                "({column} {comparison} ?{key_param} OR ({column} = ?{key_param} AND id {comparison} ?{id_param}))"
+            ));
+        }
+
+        let mut sql = String::from(
+            "SELECT id, name, start_time, distance_meters, duration_ms, avg_pace_sec_per_km FROM runs",
+        );
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+        sql.push_str(&format!(" ORDER BY {column} {direction}, id {direction}"));
+        if let Some(limit) = query.limit {
+            // Fetch one extra row to find out whether there's another page
+            sql.push_str(&format!(" LIMIT {}", limit + 1));
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let mut runs: Vec<RunSummary> = stmt
+            .query_map(rusqlite::params_from_iter(params), summary_from_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let next_cursor = match query.limit {
+            Some(limit) if runs.len() > limit => {
+                runs.truncate(limit);
+                runs.last()
+                    .map(|last| query::encode_cursor(query.sort_by, last))
+            }
+            _ => None,
+        };
+
+        Ok(RunPage { runs, next_cursor })
+    }
+
+    /// Move a run to the trash. It stays recoverable with `restore_run`
+    /// until it is purged.
+    pub fn delete_run(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        trash_run(&conn, id)
+    }
+
+    /// Take a run back out of the trash
+    pub fn restore_run(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+
+        let rows = conn.execute(
+            "UPDATE runs SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            [id],
+        )?;
+
+        Ok(rows > 0)
     }
 
-    /// Get all runs (summary only, no GPS points)
-    pub fn get_all_runs(&self) -> Result<Vec<RunSummary>> {
+    /// List runs in the trash, most recently deleted first
+    pub fn list_trash(&self) -> Result<Vec<TrashedRun>> {
         let conn = self.conn.lock().unwrap();
 
         let mut stmt = conn.prepare(
-            "SELECT id, name, start_time, distance_meters, duration_ms, avg_pace_sec_per_km
-             FROM runs ORDER BY start_time DESC",
+            "SELECT id, name, start_time, distance_meters, duration_ms, avg_pace_sec_per_km, deleted_at
+             FROM runs WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
         )?;
 
         let runs = stmt.query_map([], |row| {
-            let id: String = row.get(0)?;
-            let name: Option<String> = row.get(1)?;
-            let start_time_str: String = row.get(2)?;
-            let distance_meters: f64 = row.get(3)?;
-            let duration_ms: i64 = row.get(4)?;
-            let avg_pace_sec_per_km: Option<f64> = row.get(5)?;
-
-            let start_time = chrono::DateTime::parse_from_rfc3339(&start_time_str)
+            let summary = summary_from_row(row)?;
+            let deleted_at_str: String = row.get(6)?;
+            let deleted_at = chrono::DateTime::parse_from_rfc3339(&deleted_at_str)
                 .map(|dt| dt.with_timezone(&chrono::Utc))
                 .unwrap_or_else(|_| chrono::Utc::now());
 
-            Ok(RunSummary {
-                id,
-                name,
-                start_time,
-                distance_meters,
-                duration_ms,
-                avg_pace_sec_per_km,
+            Ok(TrashedRun {
+                summary,
+                deleted_at,
             })
         })?;
 
         Ok(runs.filter_map(|r| r.ok()).collect())
     }
 
-    /// Delete a run by ID
-    pub fn delete_run(&self, id: &str) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+    /// Permanently delete a run that is in the trash
+    pub fn purge_run(&self, id: &str) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
-        // Delete GPS points first (foreign key)
-        conn.execute("DELETE FROM gps_points WHERE run_id = ?1", [id])?;
-
-        // Delete run
-        let rows = conn.execute("DELETE FROM runs WHERE id = ?1", [id])?;
+        let rows = purge_runs_where(&tx, "id = ?1 AND deleted_at IS NOT NULL", &[&id])?;
 
+        tx.commit()?;
         Ok(rows > 0)
     }
 
+    /// Permanently delete runs that were moved to the trash before `cutoff`.
+    /// Returns the number of runs purged.
+    pub fn purge_deleted_before(&self, cutoff: chrono::DateTime<chrono::Utc>) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let rows = purge_runs_where(
+            &tx,
+            "deleted_at IS NOT NULL AND deleted_at < ?1",
+            &[&cutoff.to_rfc3339()],
+        )?;
+
+        tx.commit()?;
+        Ok(rows)
+    }
+
+    /// Permanently delete every run in the trash, returning the number purged
+    pub fn empty_trash(&self) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let rows = purge_runs_where(&tx, "deleted_at IS NOT NULL", &[])?;
+
+        tx.commit()?;
+        Ok(rows)
+    }
+
     /// Get run count
     pub fn run_count(&self) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM runs", [], |row| row.get(0))?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM runs WHERE deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
         Ok(count)
     }
 
@@ -209,7 +511,7 @@ impl Database {
     pub fn total_distance(&self) -> Result<f64> {
         let conn = self.conn.lock().unwrap();
         let total: f64 = conn.query_row(
-            "SELECT COALESCE(SUM(distance_meters), 0) FROM runs",
+            "SELECT COALESCE(SUM(distance_meters), 0) FROM runs WHERE deleted_at IS NULL",
             [],
             |row| row.get(0),
         )?;
@@ -217,6 +519,368 @@ impl Database {
     }
 }
 
+/// Read notes, run type, perceived exertion and weather starting at column `offset`.
+/// Tags live in their own table and are loaded separately.
+fn metadata_from_row(row: &rusqlite::Row, offset: usize) -> rusqlite::Result<RunMetadata> {
+    let run_type: Option<String> = row.get(offset + 1)?;
+
+    Ok(RunMetadata {
+        notes: row.get(offset)?,
+        run_type: run_type.as_deref().and_then(RunType::parse),
+        tags: Vec::new(),
+        perceived_exertion: row.get(offset + 2)?,
+        weather: row.get(offset + 3)?,
+    })
+}
+
+/// Map an `id, name, start_time, distance_meters, duration_ms, avg_pace_sec_per_km` row
+fn summary_from_row(row: &rusqlite::Row) -> rusqlite::Result<RunSummary> {
+    let start_time_str: String = row.get(2)?;
+    let start_time = chrono::DateTime::parse_from_rfc3339(&start_time_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+
+    Ok(RunSummary {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        start_time,
+        distance_meters: row.get(3)?,
+        duration_ms: row.get(4)?,
+        avg_pace_sec_per_km: row.get(5)?,
+    })
+}
+
+/// Insert or update a run with its points, tags, pauses and laps
+fn save_run(conn: &Connection, run: &Run) -> Result<()> {
+    // Insert or update run, leaving columns it doesn't own (e.g. deleted_at) alone
+    conn.execute(
+        "INSERT INTO runs (id, name, start_time, end_time, distance_meters, duration_ms, avg_pace_sec_per_km,
+                           notes, run_type, perceived_exertion, weather)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name, start_time = excluded.start_time, end_time = excluded.end_time,
+            distance_meters = excluded.distance_meters, duration_ms = excluded.duration_ms,
+            avg_pace_sec_per_km = excluded.avg_pace_sec_per_km, notes = excluded.notes,
+            run_type = excluded.run_type, perceived_exertion = excluded.perceived_exertion,
+            weather = excluded.weather",
+        rusqlite::params![
+            run.id,
+            run.name,
+            run.start_time.to_rfc3339(),
+            run.end_time.map(|t| t.to_rfc3339()),
+            run.distance_meters,
+            run.duration_ms,
+            run.avg_pace_sec_per_km,
+            run.metadata.notes,
+            run.metadata.run_type.map(|t| t.as_str()),
+            run.metadata.perceived_exertion,
+            run.metadata.weather,
+        ],
+    )?;
+
+    save_tags(conn, &run.id, &run.metadata.tags)?;
+    save_pauses(conn, &run.id, &run.pauses)?;
+    save_laps(conn, &run.id, &run.laps)?;
+
+    // Delete existing points for this run
+    conn.execute("DELETE FROM gps_points WHERE run_id = ?1", [&run.id])?;
+
+    // Insert all GPS points
+    for (idx, point) in run.points.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO gps_points (run_id, point_index, lat, lon, altitude, timestamp, accuracy, speed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                run.id,
+                idx as i64,
+                point.lat,
+                point.lon,
+                point.altitude,
+                point.timestamp.to_rfc3339(),
+                point.accuracy,
+                point.speed,
+            ],
+        )?;
+    }
+
+    // Finished runs (including edits of them) are matched against segments
+    if run.end_time.is_some() {
+        segments::match_run(conn, &run.id, &run.points)?;
+    }
+
+    Ok(())
+}
+
+/// Load a run with its points, tags, pauses and laps (None if missing or trashed)
+fn load_run(conn: &Connection, id: &str) -> Result<Option<Run>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, start_time, end_time, distance_meters, duration_ms, avg_pace_sec_per_km,
+                notes, run_type, perceived_exertion, weather
+         FROM runs WHERE id = ?1 AND deleted_at IS NULL",
+    )?;
+
+    let run = stmt.query_row([id], |row| {
+        let id: String = row.get(0)?;
+        let name: Option<String> = row.get(1)?;
+        let start_time_str: String = row.get(2)?;
+        let end_time_str: Option<String> = row.get(3)?;
+        let distance_meters: f64 = row.get(4)?;
+        let duration_ms: i64 = row.get(5)?;
+        let avg_pace_sec_per_km: Option<f64> = row.get(6)?;
+        let metadata = metadata_from_row(row, 7)?;
+
+        let start_time = chrono::DateTime::parse_from_rfc3339(&start_time_str)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now());
+
+        let end_time = end_time_str.and_then(|s| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .ok()
+        });
+
+        Ok(Run {
+            id,
+            name,
+            start_time,
+            end_time,
+            points: Vec::new(),
+            distance_meters,
+            duration_ms,
+            avg_pace_sec_per_km,
+            metadata,
+            pauses: Vec::new(),
+            laps: Vec::new(),
+        })
+    });
+
+    match run {
+        Ok(mut run) => {
+            run.metadata.tags = load_tags(conn, id)?;
+            run.pauses = load_pauses(conn, id)?;
+            run.laps = load_laps(conn, id)?;
+
+            run.points = load_points(conn, id)?;
+            Ok(Some(run))
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Move a run to the trash, returning false if it wasn't there to move
+fn trash_run(conn: &Connection, id: &str) -> Result<bool> {
+    let rows = conn.execute(
+        "UPDATE runs SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+        rusqlite::params![id, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(rows > 0)
+}
+
+/// Permanently delete the runs matching `condition`, along with their points and tags
+fn purge_runs_where(
+    conn: &Connection,
+    condition: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> Result<usize> {
+    // Delete GPS points, tags, pauses, laps, backups, reports and segment efforts first (foreign key)
+    conn.execute(
+        &format!("DELETE FROM gps_points WHERE run_id IN (SELECT id FROM runs WHERE {condition})"),
+        params,
+    )?;
+    conn.execute(
+        &format!("DELETE FROM run_tags WHERE run_id IN (SELECT id FROM runs WHERE {condition})"),
+        params,
+    )?;
+    conn.execute(
+        &format!("DELETE FROM run_pauses WHERE run_id IN (SELECT id FROM runs WHERE {condition})"),
+        params,
+    )?;
+    conn.execute(
+        &format!("DELETE FROM run_laps WHERE run_id IN (SELECT id FROM runs WHERE {condition})"),
+        params,
+    )?;
+    conn.execute(
+        &format!("DELETE FROM run_backups WHERE run_id IN (SELECT id FROM runs WHERE {condition})"),
+        params,
+    )?;
+    conn.execute(
+        &format!(
+            "DELETE FROM race_reports WHERE run_id IN (SELECT id FROM runs WHERE {condition})"
+        ),
+        params,
+    )?;
+
+    conn.execute(
+        &format!(
+            "DELETE FROM segment_efforts WHERE run_id IN (SELECT id FROM runs WHERE {condition})"
+        ),
+        params,
+    )?;
+
+    // Plan sessions stay in their plan, just no longer done
+    conn.execute(
+        &format!(
+            "UPDATE plan_days SET run_id = NULL WHERE run_id IN (SELECT id FROM runs WHERE {condition})"
+        ),
+        params,
+    )?;
+
+    let rows = conn.execute(&format!("DELETE FROM runs WHERE {condition}"), params)?;
+    Ok(rows)
+}
+
+/// Load a run's GPS points in order
+fn load_points(conn: &Connection, run_id: &str) -> Result<Vec<GpsPoint>> {
+    let mut stmt = conn.prepare(
+        "SELECT lat, lon, altitude, timestamp, accuracy, speed
+         FROM gps_points WHERE run_id = ?1 ORDER BY point_index",
+    )?;
+
+    let points = stmt.query_map([run_id], |row| {
+        let lat: f64 = row.get(0)?;
+        let lon: f64 = row.get(1)?;
+        let altitude: Option<f64> = row.get(2)?;
+        let timestamp_str: String = row.get(3)?;
+        let accuracy: Option<f64> = row.get(4)?;
+        let speed: Option<f64> = row.get(5)?;
+
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&timestamp_str)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now());
+
+        Ok(GpsPoint {
+            lat,
+            lon,
+            altitude,
+            timestamp,
+            accuracy,
+            speed,
+        })
+    })?;
+
+    Ok(points.filter_map(|p| p.ok()).collect())
+}
+
+/// Load the tags for a run, sorted
+fn load_tags(conn: &Connection, run_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT tag FROM run_tags WHERE run_id = ?1 ORDER BY tag")?;
+    let tags = stmt.query_map([run_id], |row| row.get(0))?;
+    Ok(tags.filter_map(|t| t.ok()).collect())
+}
+
+/// Replace the tags for a run
+fn save_tags(conn: &Connection, run_id: &str, tags: &[String]) -> Result<()> {
+    conn.execute("DELETE FROM run_tags WHERE run_id = ?1", [run_id])?;
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO run_tags (run_id, tag) VALUES (?1, ?2)",
+            rusqlite::params![run_id, tag],
+        )?;
+    }
+    Ok(())
+}
+
+/// Load the pauses for a run, in time order
+fn load_pauses(conn: &Connection, run_id: &str) -> Result<Vec<Pause>> {
+    let mut stmt = conn.prepare(
+        "SELECT start_time, end_time FROM run_pauses WHERE run_id = ?1 ORDER BY start_time",
+    )?;
+
+    let pauses = stmt.query_map([run_id], |row| {
+        let start_str: String = row.get(0)?;
+        let end_str: String = row.get(1)?;
+        Ok((start_str, end_str))
+    })?;
+
+    Ok(pauses
+        .filter_map(|p| p.ok())
+        .filter_map(|(start, end)| {
+            Some(Pause {
+                start_time: chrono::DateTime::parse_from_rfc3339(&start)
+                    .ok()?
+                    .with_timezone(&chrono::Utc),
+                end_time: chrono::DateTime::parse_from_rfc3339(&end)
+                    .ok()?
+                    .with_timezone(&chrono::Utc),
+            })
+        })
+        .collect())
+}
+
+/// Replace the pauses for a run
+fn save_pauses(conn: &Connection, run_id: &str, pauses: &[Pause]) -> Result<()> {
+    conn.execute("DELETE FROM run_pauses WHERE run_id = ?1", [run_id])?;
+    for pause in pauses {
+        conn.execute(
+            "INSERT INTO run_pauses (run_id, start_time, end_time) VALUES (?1, ?2, ?3)",
+            rusqlite::params![
+                run_id,
+                pause.start_time.to_rfc3339(),
+                pause.end_time.to_rfc3339()
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn load_laps(conn: &Connection, run_id: &str) -> Result<Vec<Lap>> {
+    let mut stmt = conn.prepare(
+        "SELECT start_time, end_time, distance_meters, step_kind, target_pace_sec_per_km
+         FROM run_laps WHERE run_id = ?1 ORDER BY lap_index",
+    )?;
+
+    let laps = stmt.query_map([run_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, f64>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<f64>>(4)?,
+        ))
+    })?;
+
+    let parse_time = |s: &str| {
+        chrono::DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+    };
+    Ok(laps
+        .filter_map(|l| l.ok())
+        .filter_map(|(start, end, distance_meters, step_kind, pace)| {
+            Some(Lap {
+                start_time: parse_time(&start)?,
+                end_time: parse_time(&end)?,
+                distance_meters,
+                step_kind: step_kind.and_then(|k| StepKind::parse(&k)),
+                target_pace_sec_per_km: pace,
+            })
+        })
+        .collect())
+}
+
+/// Replace the laps for a run
+fn save_laps(conn: &Connection, run_id: &str, laps: &[Lap]) -> Result<()> {
+    conn.execute("DELETE FROM run_laps WHERE run_id = ?1", [run_id])?;
+    for (idx, lap) in laps.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO run_laps (run_id, lap_index, start_time, end_time, distance_meters,
+                                   step_kind, target_pace_sec_per_km)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                run_id,
+                idx as i64,
+                lap.start_time.to_rfc3339(),
+                lap.end_time.to_rfc3339(),
+                lap.distance_meters,
+                lap.step_kind.map(|k| k.as_str()),
+                lap.target_pace_sec_per_km,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,11 +907,376 @@ mod tests {
         assert_eq!(loaded.points.len(), 2);
 
         // List
-        let all = db.get_all_runs().unwrap();
-        assert_eq!(all.len(), 1);
+        let all = db.query_runs(&RunQuery::default()).unwrap();
+        assert_eq!(all.runs.len(), 1);
 
         // Delete
         assert!(db.delete_run(&run.id).unwrap());
         assert!(db.get_run(&run.id).unwrap().is_none());
     }
+
+    #[test]
+    fn test_update_run_metadata() {
+        let db = Database::open(":memory:").unwrap();
+
+        let mut run = Run::new();
+        run.name = Some("Morning Run".to_string());
+        run.add_point(GpsPoint::new(51.5074, -0.1278, Utc::now()));
+        db.save_run(&run).unwrap();
+
+        let patch = RunMetadataPatch {
+            notes: Some("Felt strong".to_string()),
+            run_type: Some(Some(RunType::Tempo)),
+            tags: Some(vec![
+                "Track".to_string(),
+                "club ".to_string(),
+                "track".to_string(),
+            ]),
+            perceived_exertion: Some(Some(7)),
+            ..Default::default()
+        };
+        assert!(db.update_run_metadata(&run.id, &patch).unwrap());
+
+        let loaded = db.get_run(&run.id).unwrap().unwrap();
+        assert_eq!(loaded.name, Some("Morning Run".to_string()));
+        assert_eq!(loaded.points.len(), 1);
+        assert_eq!(loaded.metadata.notes, Some("Felt strong".to_string()));
+        assert_eq!(loaded.metadata.run_type, Some(RunType::Tempo));
+        assert_eq!(loaded.metadata.tags, vec!["club", "track"]);
+        assert_eq!(loaded.metadata.perceived_exertion, Some(7));
+
+        // Empty strings clear text fields
+        let patch = RunMetadataPatch {
+            notes: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(db.update_run_metadata(&run.id, &patch).unwrap());
+        let metadata = db.get_run_metadata(&run.id).unwrap().unwrap();
+        assert_eq!(metadata.notes, None);
+        assert_eq!(metadata.run_type, Some(RunType::Tempo));
+
+        // Run type and exertion can be cleared without touching the rest
+        let patch = RunMetadataPatch {
+            run_type: Some(None),
+            perceived_exertion: Some(None),
+            ..Default::default()
+        };
+        assert!(db.update_run_metadata(&run.id, &patch).unwrap());
+        let metadata = db.get_run_metadata(&run.id).unwrap().unwrap();
+        assert_eq!(metadata.run_type, None);
+        assert_eq!(metadata.perceived_exertion, None);
+        assert_eq!(metadata.tags, vec!["club", "track"]);
+
+        // Out-of-range exertion is rejected
+        let patch = RunMetadataPatch {
+            perceived_exertion: Some(Some(11)),
+            ..Default::default()
+        };
+        assert!(db.update_run_metadata(&run.id, &patch).is_err());
+
+        // Unknown run
+        assert!(!db
+            .update_run_metadata("missing", &RunMetadataPatch::default())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_query_runs() {
+        use chrono::Duration;
+
+        let db = Database::open(":memory:").unwrap();
+        let base = Utc::now() - Duration::days(30);
+
+        for i in 0..5 {
+            let mut run = Run::new();
+            run.name = Some(format!("Run {i}"));
+            run.start_time = base + Duration::days(i);
+            run.distance_meters = 5000.0 * (i + 1) as f64;
+            run.duration_ms = 1_500_000 * (i + 1);
+            if i >= 3 {
+                run.name = Some(format!("Long run {i}"));
+                run.metadata.tags = vec!["long".to_string()];
+            }
+            db.save_run(&run).unwrap();
+        }
+
+        // Default: newest first
+        let page = db.query_runs(&RunQuery::default()).unwrap();
+        assert_eq!(page.runs.len(), 5);
+        assert_eq!(page.runs[0].name.as_deref(), Some("Long run 4"));
+        assert!(page.next_cursor.is_none());
+
+        // Distance range
+        let query = RunQuery {
+            min_distance_m: Some(10_000.0),
+            max_distance_m: Some(20_000.0),
+            ..Default::default()
+        };
+        assert_eq!(db.query_runs(&query).unwrap().runs.len(), 3);
+
+        // Tags and name search
+        let query = RunQuery {
+            tags: vec!["Long".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(db.query_runs(&query).unwrap().runs.len(), 2);
+        let query = RunQuery {
+            name_contains: Some("long".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(db.query_runs(&query).unwrap().runs.len(), 2);
+
+        // Date range
+        let query = RunQuery {
+            start_after: Some(base + Duration::days(1)),
+            start_before: Some(base + Duration::days(3)),
+            ..Default::default()
+        };
+        assert_eq!(db.query_runs(&query).unwrap().runs.len(), 2);
+
+        // Cursor pagination by distance, ascending
+        let mut query = RunQuery {
+            sort_by: RunSortField::Distance,
+            ascending: true,
+            limit: Some(2),
+            ..Default::default()
+        };
+        let mut seen = Vec::new();
+        loop {
+            let page = db.query_runs(&query).unwrap();
+            seen.extend(page.runs.iter().map(|r| r.distance_meters));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen, vec![5000.0, 10_000.0, 15_000.0, 20_000.0, 25_000.0]);
+    }
+
+    #[test]
+    fn test_foreign_keys() {
+        let db = Database::open(":memory:").unwrap();
+
+        let mut run = Run::new();
+        run.metadata.tags = vec!["club".to_string()];
+        run.add_point(GpsPoint::new(51.5074, -0.1278, Utc::now()));
+        db.save_run(&run).unwrap();
+
+        let conn = db.conn.lock().unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+
+        // Child rows can't point at a missing run
+        assert!(conn
+            .execute(
+                "INSERT INTO run_tags (run_id, tag) VALUES ('missing', 'club')",
+                [],
+            )
+            .is_err());
+
+        // Deleting a run cascades to its points and tags
+        assert_eq!(count("gps_points"), 1);
+        assert_eq!(count("run_tags"), 1);
+        conn.execute("DELETE FROM runs WHERE id = ?1", [&run.id])
+            .unwrap();
+        assert_eq!(count("gps_points"), 0);
+        assert_eq!(count("run_tags"), 0);
+    }
+
+    #[test]
+    fn test_trash() {
+        use chrono::Duration;
+
+        let db = Database::open(":memory:").unwrap();
+
+        let mut run = Run::new();
+        run.distance_meters = 5000.0;
+        run.add_point(GpsPoint::new(51.5074, -0.1278, Utc::now()));
+        db.save_run(&run).unwrap();
+
+        // Soft delete hides the run from lookups and aggregates
+        assert!(db.delete_run(&run.id).unwrap());
+        assert!(!db.delete_run(&run.id).unwrap());
+        assert!(db.get_run(&run.id).unwrap().is_none());
+        assert_eq!(db.run_count().unwrap(), 0);
+        assert_eq!(db.total_distance().unwrap(), 0.0);
+        assert!(db.query_runs(&RunQuery::default()).unwrap().runs.is_empty());
+
+        // Saving a trashed run doesn't take it out of the trash
+        db.save_run(&run).unwrap();
+        assert_eq!(db.list_trash().unwrap().len(), 1);
+
+        // Restore brings it back with its points
+        assert!(db.restore_run(&run.id).unwrap());
+        let restored = db.get_run(&run.id).unwrap().unwrap();
+        assert_eq!(restored.points.len(), 1);
+        assert_eq!(db.total_distance().unwrap(), 5000.0);
+
+        // Timed purge only removes runs deleted before the cutoff
+        db.delete_run(&run.id).unwrap();
+        let purged = db
+            .purge_deleted_before(Utc::now() - Duration::days(TRASH_RETENTION_DAYS))
+            .unwrap();
+        assert_eq!(purged, 0);
+        let purged = db
+            .purge_deleted_before(Utc::now() + Duration::seconds(1))
+            .unwrap();
+        assert_eq!(purged, 1);
+        assert!(db.list_trash().unwrap().is_empty());
+        assert!(!db.restore_run(&run.id).unwrap());
+    }
+
+    #[test]
+    fn test_merge_and_split_runs() {
+        use chrono::Duration;
+
+        let db = Database::open(":memory:").unwrap();
+        let start = Utc::now();
+
+        let mut ids = Vec::new();
+        for (offset, lat) in [(0, 51.50), (30, 51.51)] {
+            let mut run = Run::new();
+            for i in 0..=5 {
+                run.add_point(GpsPoint::new(
+                    lat + 0.001 * i as f64,
+                    -0.1,
+                    start + Duration::minutes(offset + i),
+                ));
+            }
+            run.recalculate_stats();
+            db.save_run(&run).unwrap();
+            ids.push(run.id);
+        }
+
+        let merged = db.merge_runs(&ids).unwrap();
+        assert_eq!(db.run_count().unwrap(), 1);
+        assert_eq!(db.list_trash().unwrap().len(), 2);
+
+        let loaded = db.get_run(&merged.id).unwrap().unwrap();
+        assert_eq!(loaded.points.len(), 12);
+        assert_eq!(loaded.pauses.len(), 1);
+        assert_eq!(loaded.duration_ms, 10 * 60 * 1000);
+
+        let (before, after) = db
+            .split_run(&merged.id, start + Duration::minutes(30))
+            .unwrap();
+        assert_eq!(before.points.len(), 6);
+        assert_eq!(after.points.len(), 6);
+        assert!(before.pauses.is_empty() && after.pauses.is_empty());
+        assert_eq!(db.run_count().unwrap(), 2);
+        assert!(db.get_run(&merged.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_trim_and_revert() {
+        use chrono::Duration;
+
+        let db = Database::open(":memory:").unwrap();
+        let start = Utc::now();
+
+        let mut run = Run::new();
+        for i in 0..=10 {
+            run.add_point(GpsPoint::new(
+                51.5 + 0.001 * i as f64,
+                -0.1,
+                start + Duration::minutes(i),
+            ));
+        }
+        run.recalculate_stats();
+        db.save_run(&run).unwrap();
+
+        let trimmed = db.trim_run(&run.id, 60_000, 120_000).unwrap();
+        assert_eq!(trimmed.points.len(), 8);
+        assert!(trimmed.distance_meters < run.distance_meters);
+        assert!(db.has_pending_edit(&run.id).unwrap());
+
+        // A second trim still reverts to the untouched original
+        db.trim_run_by_distance(&run.id, 200.0, 0.0).unwrap();
+        let reverted = db.revert_run_edit(&run.id).unwrap().unwrap();
+        assert_eq!(reverted.points.len(), 11);
+        assert_eq!(db.get_run(&run.id).unwrap().unwrap().points.len(), 11);
+        assert!(!db.has_pending_edit(&run.id).unwrap());
+
+        // Confirming drops the backup
+        db.trim_run(&run.id, 60_000, 0).unwrap();
+        assert!(db.confirm_run_edit(&run.id).unwrap());
+        assert!(db.revert_run_edit(&run.id).unwrap().is_none());
+        assert_eq!(db.get_run(&run.id).unwrap().unwrap().points.len(), 10);
+    }
+
+    #[test]
+    fn test_race_reports() {
+        let db = Database::open(":memory:").unwrap();
+        let run = Run::new();
+        db.save_run(&run).unwrap();
+        assert!(db.get_race_reports(&run.id).unwrap().is_empty());
+
+        let report = RaceReport {
+            banshee_name: "5:00/km".to_string(),
+            banshee_run_id: None,
+            gap_series: Vec::new(),
+            lead_changes: Vec::new(),
+            largest_lead: None,
+            largest_deficit: None,
+            split_distance_m: 1000.0,
+            splits: Vec::new(),
+            final_time_delta_ms: Some(-4000),
+            final_distance_delta_m: -15.0,
+        };
+        db.save_race_reports(&run.id, &[report.clone(), report.clone()])
+            .unwrap();
+        db.save_race_reports(&run.id, std::slice::from_ref(&report))
+            .unwrap();
+        assert_eq!(db.get_race_reports(&run.id).unwrap(), vec![report]);
+
+        db.delete_run(&run.id).unwrap();
+        db.purge_run(&run.id).unwrap();
+        assert!(db.get_race_reports(&run.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_run_laps() {
+        use chrono::Duration;
+
+        let db = Database::open(":memory:").unwrap();
+        let start = Utc::now();
+        let mut run = Run::new();
+        run.add_point(GpsPoint::new(51.5, -0.1, start));
+        run.add_point(GpsPoint::new(51.51, -0.1, start + Duration::minutes(10)));
+        run.laps = vec![
+            Lap {
+                start_time: start,
+                end_time: start + Duration::minutes(4),
+                distance_meters: 1000.0,
+                step_kind: Some(StepKind::Work),
+                target_pace_sec_per_km: Some(240.0),
+            },
+            Lap {
+                start_time: start + Duration::minutes(4),
+                end_time: start + Duration::minutes(10),
+                distance_meters: 1000.0,
+                step_kind: None,
+                target_pace_sec_per_km: None,
+            },
+        ];
+        db.save_run(&run).unwrap();
+
+        let loaded = db.get_run(&run.id).unwrap().unwrap();
+        assert_eq!(loaded.laps.len(), 2);
+        assert_eq!(loaded.laps[0].step_kind, Some(StepKind::Work));
+        assert_eq!(loaded.laps[0].duration_ms(), 240_000);
+        assert_eq!(loaded.laps[1].step_kind, None);
+
+        assert!(db.set_run_laps(&run.id, &run.laps[..1]).unwrap());
+        assert_eq!(db.get_run_laps(&run.id).unwrap().len(), 1);
+        assert!(!db.set_run_laps("missing", &run.laps).unwrap());
+
+        db.delete_run(&run.id).unwrap();
+        db.purge_run(&run.id).unwrap();
+        assert!(db.get_run_laps(&run.id).unwrap().is_empty());
+    }
 }
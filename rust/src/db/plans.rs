@@ -0,0 +1,252 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use rusqlite::{Connection, OptionalExtension};
+
+use super::Database;
+use crate::models::{PaceZones, Phase, PlanDay, PlanSessionKind, RaceTime, TrainingPlan};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+impl Database {
+    /// Save a training plan and its days, replacing any with the same ID
+    pub fn save_training_plan(&self, plan: &TrainingPlan) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO training_plans
+             (id, name, race_distance_m, race_date, fitness_distance_m, fitness_duration_ms,
+              zones_json, start_weekly_volume_m, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![
+                plan.id,
+                plan.name,
+                plan.race_distance_m,
+                plan.race_date.format(DATE_FORMAT).to_string(),
+                plan.fitness.distance_m,
+                plan.fitness.duration_ms,
+                serde_json::to_string(&plan.zones)?,
+                plan.start_weekly_volume_m,
+                plan.created_at.to_rfc3339(),
+            ],
+        )?;
+
+        tx.execute("DELETE FROM plan_days WHERE plan_id = ?1", [&plan.id])?;
+        for day in &plan.days {
+            tx.execute(
+                "INSERT INTO plan_days
+                 (plan_id, date, week, phase, kind, distance_m, target_pace_sec_per_km,
+                  workout_json, run_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                rusqlite::params![
+                    plan.id,
+                    day.date.format(DATE_FORMAT).to_string(),
+                    day.week,
+                    day.phase.as_str(),
+                    day.kind.as_str(),
+                    day.distance_m,
+                    day.target_pace_sec_per_km,
+                    day.workout
+                        .as_ref()
+                        .map(serde_json::to_string)
+                        .transpose()?,
+                    day.run_id,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a training plan with its days
+    pub fn get_training_plan(&self, id: &str) -> Result<Option<TrainingPlan>> {
+        let conn = self.conn.lock().unwrap();
+        let plan = conn
+            .query_row(
+                "SELECT id, name, race_distance_m, race_date, fitness_distance_m,
+                        fitness_duration_ms, zones_json, start_weekly_volume_m, created_at
+                 FROM training_plans WHERE id = ?1",
+                [id],
+                plan_from_row,
+            )
+            .optional()?;
+        let Some(mut plan) = plan else {
+            return Ok(None);
+        };
+
+        plan.days = load_plan_days(&conn, id)?;
+        Ok(Some(plan))
+    }
+
+    /// List training plans with their days, latest race first
+    pub fn list_training_plans(&self) -> Result<Vec<TrainingPlan>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, race_distance_m, race_date, fitness_distance_m,
+                    fitness_duration_ms, zones_json, start_weekly_volume_m, created_at
+             FROM training_plans ORDER BY race_date DESC, created_at DESC",
+        )?;
+        let plans: Vec<TrainingPlan> = stmt
+            .query_map([], plan_from_row)?
+            .filter_map(|p| p.ok())
+            .collect();
+
+        plans
+            .into_iter()
+            .map(|mut plan| {
+                plan.days = load_plan_days(&conn, &plan.id)?;
+                Ok(plan)
+            })
+            .collect()
+    }
+
+    /// Delete a training plan and its days. Returns false if it didn't exist.
+    pub fn delete_training_plan(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM plan_days WHERE plan_id = ?1", [id])?;
+        let rows = conn.execute("DELETE FROM training_plans WHERE id = ?1", [id])?;
+        Ok(rows > 0)
+    }
+
+    /// Mark a plan day done by linking the run that completed it, or undo
+    /// that with `None`. Returns false if the plan has no such day.
+    pub fn set_plan_day_run(
+        &self,
+        plan_id: &str,
+        date: NaiveDate,
+        run_id: Option<&str>,
+    ) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE plan_days SET run_id = ?3 WHERE plan_id = ?1 AND date = ?2",
+            rusqlite::params![plan_id, date.format(DATE_FORMAT).to_string(), run_id],
+        )?;
+        Ok(rows > 0)
+    }
+}
+
+/// Build a plan without its days from `id, name, race_distance_m, race_date,
+/// fitness_distance_m, fitness_duration_ms, zones_json, start_weekly_volume_m,
+/// created_at`
+fn plan_from_row(row: &rusqlite::Row) -> rusqlite::Result<TrainingPlan> {
+    let race_date: String = row.get(3)?;
+    let race_date = NaiveDate::parse_from_str(&race_date, DATE_FORMAT).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    let zones_json: String = row.get(6)?;
+    let zones: PaceZones = serde_json::from_str(&zones_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    let created_at_str: String = row.get(8)?;
+    let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+
+    Ok(TrainingPlan {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        race_distance_m: row.get(2)?,
+        race_date,
+        fitness: RaceTime {
+            distance_m: row.get(4)?,
+            duration_ms: row.get(5)?,
+        },
+        zones,
+        start_weekly_volume_m: row.get(7)?,
+        created_at,
+        days: Vec::new(),
+    })
+}
+
+/// Load a plan's days in date order, skipping any that don't parse
+fn load_plan_days(conn: &Connection, plan_id: &str) -> Result<Vec<PlanDay>> {
+    let mut stmt = conn.prepare(
+        "SELECT date, week, phase, kind, distance_m, target_pace_sec_per_km, workout_json, run_id
+         FROM plan_days WHERE plan_id = ?1 ORDER BY date",
+    )?;
+
+    let days = stmt.query_map([plan_id], |row| {
+        let date: String = row.get(0)?;
+        let phase: String = row.get(2)?;
+        let kind: String = row.get(3)?;
+        let workout_json: Option<String> = row.get(6)?;
+
+        Ok(NaiveDate::parse_from_str(&date, DATE_FORMAT)
+            .ok()
+            .zip(Phase::parse(&phase))
+            .zip(PlanSessionKind::parse(&kind))
+            .map(|((date, phase), kind)| PlanDay {
+                date,
+                week: row.get(1).unwrap_or(0),
+                phase,
+                kind,
+                distance_m: row.get(4).unwrap_or(0.0),
+                target_pace_sec_per_km: row.get(5).ok().flatten(),
+                workout: workout_json.and_then(|j| serde_json::from_str(&j).ok()),
+                run_id: row.get(7).ok().flatten(),
+            }))
+    })?;
+
+    Ok(days.filter_map(|d| d.ok().flatten()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GpsPoint, Run};
+    use crate::training::{generate_plan, PlanRequest};
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_training_plans() {
+        let db = Database::open(":memory:").unwrap();
+        let start_date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let plan = generate_plan(&PlanRequest {
+            name: "10K".to_string(),
+            race_distance_m: 10_000.0,
+            race_date: start_date + Duration::days(55),
+            start_date,
+            runs_per_week: 4,
+            weekly_volume_m: 25_000.0,
+            fitness: RaceTime {
+                distance_m: 5000.0,
+                duration_ms: 1_500_000,
+            },
+        })
+        .unwrap();
+        db.save_training_plan(&plan).unwrap();
+        assert_eq!(db.get_training_plan(&plan.id).unwrap().unwrap(), plan);
+        assert_eq!(db.list_training_plans().unwrap().len(), 1);
+
+        // Link a run to the first running day
+        let day = plan
+            .days
+            .iter()
+            .find(|d| d.kind != PlanSessionKind::Rest)
+            .unwrap()
+            .date;
+        let mut run = Run::new();
+        run.add_point(GpsPoint::new(51.5, -0.1, Utc::now()));
+        db.save_run(&run).unwrap();
+        assert!(db.set_plan_day_run(&plan.id, day, Some(&run.id)).unwrap());
+        assert!(!db
+            .set_plan_day_run(&plan.id, plan.race_date + Duration::days(1), Some(&run.id))
+            .unwrap());
+        let saved = db.get_training_plan(&plan.id).unwrap().unwrap();
+        assert_eq!(
+            saved.day(day).unwrap().run_id.as_deref(),
+            Some(run.id.as_str())
+        );
+
+        // Purging the run leaves the session undone
+        db.delete_run(&run.id).unwrap();
+        db.purge_run(&run.id).unwrap();
+        let saved = db.get_training_plan(&plan.id).unwrap().unwrap();
+        assert!(saved.day(day).unwrap().run_id.is_none());
+
+        assert!(db.delete_training_plan(&plan.id).unwrap());
+        assert!(db.get_training_plan(&plan.id).unwrap().is_none());
+        assert!(!db.delete_training_plan(&plan.id).unwrap());
+    }
+}
@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+
+use crate::models::RunSummary;
+
+/// Default page size for run history queries
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Column used to order run history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RunSortField {
+    #[default]
+    StartTime,
+    Distance,
+    Duration,
+}
+
+impl RunSortField {
+    /// Parse "start_time", "distance" or "duration"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "start_time" => Some(RunSortField::StartTime),
+            "distance" => Some(RunSortField::Distance),
+            "duration" => Some(RunSortField::Duration),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn column(&self) -> &'static str {
+        match self {
+            RunSortField::StartTime => "start_time",
+            RunSortField::Distance => "distance_meters",
+            RunSortField::Duration => "duration_ms",
+        }
+    }
+}
+
+/// Filters, ordering and pagination for run history
+#[derive(Debug, Clone)]
+pub struct RunQuery {
+    pub min_distance_m: Option<f64>,
+    pub max_distance_m: Option<f64>,
+    pub min_duration_ms: Option<i64>,
+    pub max_duration_ms: Option<i64>,
+    /// Only runs starting at or after this time
+    pub start_after: Option<DateTime<Utc>>,
+    /// Only runs starting before this time
+    pub start_before: Option<DateTime<Utc>>,
+    /// Only runs carrying every one of these tags
+    pub tags: Vec<String>,
+    /// Case-insensitive substring match on the run name
+    pub name_contains: Option<String>,
+    pub sort_by: RunSortField,
+    pub ascending: bool,
+    /// Maximum number of runs per page (None = no limit)
+    pub limit: Option<usize>,
+    /// Opaque cursor from a previous page's `next_cursor`
+    pub cursor: Option<String>,
+}
+
+impl Default for RunQuery {
+    fn default() -> Self {
+        Self {
+            min_distance_m: None,
+            max_distance_m: None,
+            min_duration_ms: None,
+            max_duration_ms: None,
+            start_after: None,
+            start_before: None,
+            tags: Vec::new(),
+            name_contains: None,
+            sort_by: RunSortField::StartTime,
+            ascending: false,
+            limit: Some(DEFAULT_PAGE_SIZE),
+            cursor: None,
+        }
+    }
+}
+
+/// One page of run history
+#[derive(Debug, Clone)]
+pub struct RunPage {
+    pub runs: Vec<RunSummary>,
+    /// Cursor for the next page, None if this is the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Build the cursor pointing just past `summary` for the given sort order.
+/// The cursor is the sort key and run ID, which keeps pages stable when
+/// several runs share the same sort value.
+pub(crate) fn encode_cursor(sort_by: RunSortField, summary: &RunSummary) -> String {
+    let key = match sort_by {
+        RunSortField::StartTime => summary.start_time.to_rfc3339(),
+        RunSortField::Distance => summary.distance_meters.to_string(),
+        RunSortField::Duration => summary.duration_ms.to_string(),
+    };
+    format!("{}|{}", key, summary.id)
+}
+
+/// Split a cursor into its sort key (as a bindable SQL value) and run ID
+pub(crate) fn decode_cursor(
+    sort_by: RunSortField,
+    cursor: &str,
+) -> Option<(rusqlite::types::Value, String)> {
+    let (key, id) = cursor.rsplit_once('|')?;
+    let key = match sort_by {
+        RunSortField::StartTime => {
+            DateTime::parse_from_rfc3339(key).ok()?;
+            rusqlite::types::Value::Text(key.to_string())
+        }
+        RunSortField::Distance => rusqlite::types::Value::Real(key.parse().ok()?),
+        RunSortField::Duration => rusqlite::types::Value::Integer(key.parse().ok()?),
+    };
+    Some((key, id.to_string()))
+}
+
+/// Escape `%`, `_` and `\` for use in a LIKE pattern with `ESCAPE '\'`
+pub(crate) fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,192 @@
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension};
+
+use super::Database;
+use crate::models::{Route, RoutePoint, RouteSource, RouteSummary};
+
+impl Database {
+    /// Save a route and its points, replacing any with the same ID
+    pub fn save_route(&self, route: &Route) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO routes
+             (id, name, source_json, created_at, distance_meters, elevation_gain_m,
+              elevation_loss_m, profile_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                route.id,
+                route.name,
+                serde_json::to_string(&route.source)?,
+                route.created_at.to_rfc3339(),
+                route.distance_meters,
+                route.elevation_gain_m,
+                route.elevation_loss_m,
+                serde_json::to_string(&route.elevation_profile)?,
+            ],
+        )?;
+
+        tx.execute("DELETE FROM route_points WHERE route_id = ?1", [&route.id])?;
+        for (idx, point) in route.points.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO route_points (route_id, point_index, lat, lon, altitude)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![route.id, idx as i64, point.lat, point.lon, point.altitude],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a route with its points and elevation profile
+    pub fn get_route(&self, id: &str) -> Result<Option<Route>> {
+        let conn = self.conn.lock().unwrap();
+
+        let row = conn
+            .query_row(
+                "SELECT id, name, source_json, created_at, distance_meters, elevation_gain_m,
+                        elevation_loss_m, profile_json
+                 FROM routes WHERE id = ?1",
+                [id],
+                |row| Ok((route_summary_from_row(row)?, row.get::<_, String>(7)?)),
+            )
+            .optional()?;
+        let Some((summary, profile_json)) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(Route {
+            points: load_route_points(&conn, id)?,
+            elevation_profile: serde_json::from_str(&profile_json).unwrap_or_default(),
+            id: summary.id,
+            name: summary.name,
+            source: summary.source,
+            created_at: summary.created_at,
+            distance_meters: summary.distance_meters,
+            elevation_gain_m: summary.elevation_gain_m,
+            elevation_loss_m: summary.elevation_loss_m,
+        }))
+    }
+
+    /// List routes without their points, newest first
+    pub fn list_routes(&self) -> Result<Vec<RouteSummary>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, source_json, created_at, distance_meters, elevation_gain_m,
+                    elevation_loss_m
+             FROM routes ORDER BY created_at DESC, id",
+        )?;
+
+        let routes = stmt.query_map([], route_summary_from_row)?;
+        Ok(routes.filter_map(|r| r.ok()).collect())
+    }
+
+    /// Rename a route. Returns false if it doesn't exist.
+    pub fn rename_route(&self, id: &str, name: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE routes SET name = ?2 WHERE id = ?1",
+            rusqlite::params![id, name],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Delete a route and its points. Returns false if it didn't exist.
+    pub fn delete_route(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM route_points WHERE route_id = ?1", [id])?;
+        let rows = conn.execute("DELETE FROM routes WHERE id = ?1", [id])?;
+        Ok(rows > 0)
+    }
+}
+
+/// Build a route summary from `id, name, source_json, created_at,
+/// distance_meters, elevation_gain_m, elevation_loss_m`
+fn route_summary_from_row(row: &rusqlite::Row) -> rusqlite::Result<RouteSummary> {
+    let source_json: String = row.get(2)?;
+    let source: RouteSource = serde_json::from_str(&source_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    let created_at_str: String = row.get(3)?;
+    let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+
+    Ok(RouteSummary {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source,
+        created_at,
+        distance_meters: row.get(4)?,
+        elevation_gain_m: row.get(5)?,
+        elevation_loss_m: row.get(6)?,
+    })
+}
+
+/// Load a route's points in order
+fn load_route_points(conn: &Connection, route_id: &str) -> Result<Vec<RoutePoint>> {
+    let mut stmt = conn.prepare(
+        "SELECT lat, lon, altitude FROM route_points WHERE route_id = ?1 ORDER BY point_index",
+    )?;
+
+    let points = stmt.query_map([route_id], |row| {
+        Ok(RoutePoint {
+            lat: row.get(0)?,
+            lon: row.get(1)?,
+            altitude: row.get(2)?,
+        })
+    })?;
+
+    Ok(points.filter_map(|p| p.ok()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes() {
+        let db = Database::open(":memory:").unwrap();
+        let route = Route::new(
+            "Park loop".to_string(),
+            RouteSource::Run {
+                run_id: "run-1".to_string(),
+            },
+            vec![
+                RoutePoint {
+                    lat: 51.5,
+                    lon: -0.1,
+                    altitude: Some(10.0),
+                },
+                RoutePoint {
+                    lat: 51.51,
+                    lon: -0.1,
+                    altitude: Some(40.0),
+                },
+            ],
+        )
+        .unwrap();
+        db.save_route(&route).unwrap();
+
+        let mut saved = db.get_route(&route.id).unwrap().unwrap();
+        assert_eq!(RouteSummary::from(&saved), RouteSummary::from(&route));
+        assert_eq!(saved.points, route.points);
+        // The profile goes through JSON, which can lose the last bit
+        assert_eq!(saved.elevation_profile.len(), route.elevation_profile.len());
+        for (a, b) in saved.elevation_profile.iter().zip(&route.elevation_profile) {
+            assert!((a.altitude_m - b.altitude_m).abs() < 1e-9);
+        }
+
+        assert!(db.rename_route(&route.id, "Big hill").unwrap());
+        saved.name = "Big hill".to_string();
+        let list = db.list_routes().unwrap();
+        assert_eq!(list, vec![RouteSummary::from(&saved)]);
+
+        assert!(db.delete_route(&route.id).unwrap());
+        assert!(db.get_route(&route.id).unwrap().is_none());
+        assert!(!db.delete_route(&route.id).unwrap());
+        assert!(!db.rename_route(&route.id, "Gone").unwrap());
+    }
+}
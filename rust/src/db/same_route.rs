@@ -0,0 +1,111 @@
+use anyhow::Result;
+
+use super::{Database, RunQuery};
+use crate::geo::similarity::{RouteShape, SAME_ROUTE_DISTANCE_RATIO};
+use crate::models::RunSummary;
+
+impl Database {
+    /// Other runs on the same route as `run_id`, fastest first. Candidates
+    /// are narrowed down by distance before their tracks are compared.
+    pub fn find_runs_on_same_route(&self, run_id: &str) -> Result<Vec<RunSummary>> {
+        let run = self
+            .get_run(run_id)?
+            .ok_or_else(|| anyhow::anyhow!("Run not found: {run_id}"))?;
+        let Some(shape) = RouteShape::new(&run.points) else {
+            return Ok(Vec::new());
+        };
+
+        // Any run within the distance ratio of this one, either way round
+        let distance = shape.distance_m();
+        let candidates = self.query_runs(&RunQuery {
+            min_distance_m: Some(distance * (1.0 - SAME_ROUTE_DISTANCE_RATIO)),
+            max_distance_m: Some(distance / (1.0 - SAME_ROUTE_DISTANCE_RATIO)),
+            limit: None,
+            ..RunQuery::default()
+        })?;
+
+        let mut matches = Vec::new();
+        for summary in candidates.runs.into_iter().filter(|s| s.id != run_id) {
+            let Some(other) = self.get_run(&summary.id)? else {
+                continue;
+            };
+            let same = RouteShape::new(&other.points).and_then(|o| shape.same_route(&o));
+            if same.is_some() {
+                matches.push(summary);
+            }
+        }
+
+        matches.sort_by(|a, b| {
+            a.duration_ms
+                .cmp(&b.duration_ms)
+                .then(a.start_time.cmp(&b.start_time))
+        });
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GpsPoint, Run};
+    use chrono::{Duration, Utc};
+
+    /// A run north along `lon`, 1.1 km over `minutes`
+    fn run_north(db: &Database, lon: f64, minutes: i64) -> Run {
+        let start = Utc::now() - Duration::days(1);
+        let mut run = Run::new();
+        for i in 0..=10 {
+            run.add_point(GpsPoint::new(
+                51.5 + i as f64 * 0.001,
+                lon,
+                start + Duration::seconds(minutes * 6 * i),
+            ));
+        }
+        run.recalculate_stats();
+        db.save_run(&run).unwrap();
+        run
+    }
+
+    #[test]
+    fn test_find_runs_on_same_route() {
+        let db = Database::open(":memory:").unwrap();
+        let run = run_north(&db, -0.1, 5);
+        let slower = run_north(&db, -0.10005, 6);
+        let faster = run_north(&db, -0.09995, 4);
+        // A parallel street 700 m away
+        run_north(&db, -0.09, 5);
+        // Same street, but half the distance
+        let mut short = Run::new();
+        short.add_point(GpsPoint::new(51.5, -0.1, Utc::now()));
+        short.add_point(GpsPoint::new(
+            51.505,
+            -0.1,
+            Utc::now() + Duration::minutes(3),
+        ));
+        db.save_run(&short).unwrap();
+
+        let found: Vec<String> = db
+            .find_runs_on_same_route(&run.id)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(found, vec![faster.id.clone(), slower.id]);
+
+        // Deleted runs drop out
+        db.delete_run(&faster.id).unwrap();
+        assert_eq!(db.find_runs_on_same_route(&run.id).unwrap().len(), 1);
+        assert!(db.find_runs_on_same_route("missing").is_err());
+    }
+
+    #[test]
+    fn test_find_runs_on_same_route_past_one_page() {
+        let db = Database::open(":memory:").unwrap();
+        let run = run_north(&db, -0.1, 5);
+        for _ in 0..60 {
+            run_north(&db, -0.1, 5);
+        }
+
+        assert_eq!(db.find_runs_on_same_route(&run.id).unwrap().len(), 60);
+    }
+}
@@ -29,9 +29,213 @@ CREATE TABLE IF NOT EXISTS gps_points (
 CREATE INDEX IF NOT EXISTS idx_gps_points_run_id ON gps_points(run_id);
 CREATE INDEX IF NOT EXISTS idx_gps_points_run_index ON gps_points(run_id, point_index);
 
+-- Indexes for run history queries
+CREATE INDEX IF NOT EXISTS idx_runs_start_time ON runs(start_time, id);
+CREATE INDEX IF NOT EXISTS idx_runs_distance ON runs(distance_meters, id);
+CREATE INDEX IF NOT EXISTS idx_runs_duration ON runs(duration_ms, id);
+
 -- Settings table
 CREATE TABLE IF NOT EXISTS settings (
     key TEXT PRIMARY KEY NOT NULL,
     value TEXT NOT NULL
 );
+
+-- Run tags table
+CREATE TABLE IF NOT EXISTS run_tags (
+    run_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (run_id, tag),
+    FOREIGN KEY (run_id) REFERENCES runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_tags_tag ON run_tags(tag, run_id);
+
+-- Run pauses table
+CREATE TABLE IF NOT EXISTS run_pauses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id TEXT NOT NULL,
+    start_time TEXT NOT NULL,
+    end_time TEXT NOT NULL,
+    FOREIGN KEY (run_id) REFERENCES runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_pauses_run_id ON run_pauses(run_id);
+
+-- Run laps table (e.g. the steps of a structured workout)
+CREATE TABLE IF NOT EXISTS run_laps (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id TEXT NOT NULL,
+    lap_index INTEGER NOT NULL,
+    start_time TEXT NOT NULL,
+    end_time TEXT NOT NULL,
+    distance_meters REAL NOT NULL DEFAULT 0,
+    step_kind TEXT,
+    target_pace_sec_per_km REAL,
+    FOREIGN KEY (run_id) REFERENCES runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_laps_run_id ON run_laps(run_id, lap_index);
+
+-- Originals of runs with an unconfirmed edit (e.g. a trim), as JSON
+CREATE TABLE IF NOT EXISTS run_backups (
+    run_id TEXT PRIMARY KEY NOT NULL,
+    run_json TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (run_id) REFERENCES runs(id) ON DELETE CASCADE
+);
+
+-- Banshee race reports stored with the runner's run, as JSON
+CREATE TABLE IF NOT EXISTS race_reports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id TEXT NOT NULL,
+    report_json TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (run_id) REFERENCES runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_race_reports_run_id ON race_reports(run_id);
+
+-- Banshee tracks that aren't the runner's own runs (composites, imports)
+CREATE TABLE IF NOT EXISTS ghost_tracks (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    source_json TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    distance_meters REAL NOT NULL DEFAULT 0,
+    duration_ms INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS ghost_points (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    ghost_id TEXT NOT NULL,
+    point_index INTEGER NOT NULL,
+    lat REAL NOT NULL,
+    lon REAL NOT NULL,
+    altitude REAL,
+    timestamp TEXT NOT NULL,
+    accuracy REAL,
+    speed REAL,
+    FOREIGN KEY (ghost_id) REFERENCES ghost_tracks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ghost_points_ghost_index ON ghost_points(ghost_id, point_index);
+
+-- Saved banshee configurations (Banshee as JSON) and the runner's record against them
+CREATE TABLE IF NOT EXISTS banshees (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    config_json TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    last_used_at TEXT,
+    wins INTEGER NOT NULL DEFAULT 0,
+    losses INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_banshees_last_used ON banshees(last_used_at);
+
+-- Structured workouts (Workout as JSON)
+CREATE TABLE IF NOT EXISTS workouts (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    workout_json TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- Training plans building up to a race, one row per plan
+CREATE TABLE IF NOT EXISTS training_plans (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    race_distance_m REAL NOT NULL,
+    race_date TEXT NOT NULL,
+    fitness_distance_m REAL NOT NULL,
+    fitness_duration_ms INTEGER NOT NULL,
+    zones_json TEXT NOT NULL,
+    start_weekly_volume_m REAL NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL
+);
+
+-- Plan days; run_id links the run that completed the session
+CREATE TABLE IF NOT EXISTS plan_days (
+    plan_id TEXT NOT NULL,
+    date TEXT NOT NULL,
+    week INTEGER NOT NULL,
+    phase TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    distance_m REAL NOT NULL DEFAULT 0,
+    target_pace_sec_per_km REAL,
+    workout_json TEXT,
+    run_id TEXT,
+    PRIMARY KEY (plan_id, date),
+    FOREIGN KEY (plan_id) REFERENCES training_plans(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_plan_days_run_id ON plan_days(run_id);
+
+-- Saved routes for pacers and route matching, with their elevation profile as JSON
+CREATE TABLE IF NOT EXISTS routes (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    source_json TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    distance_meters REAL NOT NULL DEFAULT 0,
+    elevation_gain_m REAL NOT NULL DEFAULT 0,
+    elevation_loss_m REAL NOT NULL DEFAULT 0,
+    profile_json TEXT NOT NULL DEFAULT '[]'
+);
+
+CREATE TABLE IF NOT EXISTS route_points (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    route_id TEXT NOT NULL,
+    point_index INTEGER NOT NULL,
+    lat REAL NOT NULL,
+    lon REAL NOT NULL,
+    altitude REAL,
+    FOREIGN KEY (route_id) REFERENCES routes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_route_points_route_index ON route_points(route_id, point_index);
+
+-- Segments between two gates (Gate as JSON)
+CREATE TABLE IF NOT EXISTS segments (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    start_gate_json TEXT NOT NULL,
+    end_gate_json TEXT NOT NULL,
+    distance_meters REAL NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- Leaderboard of efforts on each segment
+CREATE TABLE IF NOT EXISTS segment_efforts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    segment_id TEXT NOT NULL,
+    run_id TEXT NOT NULL,
+    start_time TEXT NOT NULL,
+    elapsed_ms INTEGER NOT NULL,
+    distance_meters REAL NOT NULL,
+    FOREIGN KEY (segment_id) REFERENCES segments(id) ON DELETE CASCADE,
+    FOREIGN KEY (run_id) REFERENCES runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_segment_efforts_segment ON segment_efforts(segment_id, elapsed_ms);
+CREATE INDEX IF NOT EXISTS idx_segment_efforts_run_id ON segment_efforts(run_id);
 "#;
+
+/// Incremental migrations applied after `CREATE_TABLES`.
+///
+/// Each entry is applied once, in order, and tracked with `PRAGMA user_version`
+/// so existing databases pick up new columns. Only ever append to this list.
+pub const MIGRATIONS: &[&str] = &[
+    // 1: Run metadata
+    r#"
+    ALTER TABLE runs ADD COLUMN notes TEXT;
+    ALTER TABLE runs ADD COLUMN run_type TEXT;
+    ALTER TABLE runs ADD COLUMN perceived_exertion INTEGER;
+    ALTER TABLE runs ADD COLUMN weather TEXT;
+    "#,
+    // 2: Soft delete
+    r#"
+    ALTER TABLE runs ADD COLUMN deleted_at TEXT;
+    CREATE INDEX IF NOT EXISTS idx_runs_deleted_at ON runs(deleted_at);
+    "#,
+];
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension};
+
+use super::{load_points, Database};
+use crate::geo::segments::find_efforts;
+use crate::models::{Gate, GpsPoint, Segment, SegmentEffort};
+
+impl Database {
+    /// Save a segment, replacing any with the same ID, and scan every
+    /// finished run for efforts on it. Returns the number of efforts found.
+    pub fn save_segment(&self, segment: &Segment) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO segments
+             (id, name, start_gate_json, end_gate_json, distance_meters, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                segment.id,
+                segment.name,
+                serde_json::to_string(&segment.start_gate)?,
+                serde_json::to_string(&segment.end_gate)?,
+                segment.distance_meters,
+                segment.created_at.to_rfc3339(),
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM segment_efforts WHERE segment_id = ?1",
+            [&segment.id],
+        )?;
+
+        let run_ids: Vec<String> = tx
+            .prepare("SELECT id FROM runs WHERE end_time IS NOT NULL")?
+            .query_map([], |row| row.get(0))?
+            .filter_map(|id| id.ok())
+            .collect();
+        let mut found = 0;
+        for run_id in run_ids {
+            let efforts = find_efforts(segment, &run_id, &load_points(&tx, &run_id)?);
+            found += efforts.len();
+            insert_efforts(&tx, &efforts)?;
+        }
+
+        tx.commit()?;
+        Ok(found)
+    }
+
+    /// Get a segment
+    pub fn get_segment(&self, id: &str) -> Result<Option<Segment>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT id, name, start_gate_json, end_gate_json, distance_meters, created_at
+                 FROM segments WHERE id = ?1",
+                [id],
+                segment_from_row,
+            )
+            .optional()?)
+    }
+
+    /// List segments by name
+    pub fn list_segments(&self) -> Result<Vec<Segment>> {
+        let conn = self.conn.lock().unwrap();
+        load_segments(&conn)
+    }
+
+    /// Rename a segment. Returns false if it doesn't exist.
+    pub fn rename_segment(&self, id: &str, name: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE segments SET name = ?2 WHERE id = ?1",
+            rusqlite::params![id, name],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Delete a segment and its efforts. Returns false if it didn't exist.
+    pub fn delete_segment(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM segment_efforts WHERE segment_id = ?1", [id])?;
+        let rows = conn.execute("DELETE FROM segments WHERE id = ?1", [id])?;
+        Ok(rows > 0)
+    }
+
+    /// A segment's efforts, fastest first, leaving out runs in the trash
+    pub fn segment_leaderboard(
+        &self,
+        segment_id: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<SegmentEffort>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT e.segment_id, e.run_id, e.start_time, e.elapsed_ms, e.distance_meters
+             FROM segment_efforts e JOIN runs r ON r.id = e.run_id
+             WHERE e.segment_id = ?1 AND r.deleted_at IS NULL
+             ORDER BY e.elapsed_ms, e.start_time
+             LIMIT ?2",
+        )?;
+        // SQLite treats a negative limit as no limit
+        let limit = limit.map_or(-1, |l| l as i64);
+
+        let efforts = stmt.query_map(rusqlite::params![segment_id, limit], effort_from_row)?;
+        Ok(efforts.filter_map(|e| e.ok()).collect())
+    }
+
+    /// Segment efforts during a run, in the order they were run
+    pub fn get_run_segment_efforts(&self, run_id: &str) -> Result<Vec<SegmentEffort>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT segment_id, run_id, start_time, elapsed_ms, distance_meters
+             FROM segment_efforts WHERE run_id = ?1 ORDER BY start_time",
+        )?;
+
+        let efforts = stmt.query_map([run_id], effort_from_row)?;
+        Ok(efforts.filter_map(|e| e.ok()).collect())
+    }
+}
+
+/// Replace a run's segment efforts with those found in `points`
+pub(super) fn match_run(conn: &Connection, run_id: &str, points: &[GpsPoint]) -> Result<()> {
+    conn.execute("DELETE FROM segment_efforts WHERE run_id = ?1", [run_id])?;
+    for segment in load_segments(conn)? {
+        insert_efforts(conn, &find_efforts(&segment, run_id, points))?;
+    }
+    Ok(())
+}
+
+fn insert_efforts(conn: &Connection, efforts: &[SegmentEffort]) -> Result<()> {
+    for effort in efforts {
+        conn.execute(
+            "INSERT INTO segment_efforts (segment_id, run_id, start_time, elapsed_ms, distance_meters)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                effort.segment_id,
+                effort.run_id,
+                effort.start_time.to_rfc3339(),
+                effort.elapsed_ms,
+                effort.distance_meters,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn load_segments(conn: &Connection) -> Result<Vec<Segment>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, start_gate_json, end_gate_json, distance_meters, created_at
+         FROM segments ORDER BY name COLLATE NOCASE, id",
+    )?;
+    let segments = stmt.query_map([], segment_from_row)?;
+    Ok(segments.filter_map(|s| s.ok()).collect())
+}
+
+/// Build a segment from `id, name, start_gate_json, end_gate_json,
+/// distance_meters, created_at`
+fn segment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Segment> {
+    let gate = |idx: usize| -> rusqlite::Result<Gate> {
+        let json: String = row.get(idx)?;
+        serde_json::from_str(&json).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+        })
+    };
+    let created_at_str: String = row.get(5)?;
+    let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+
+    Ok(Segment {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        start_gate: gate(2)?,
+        end_gate: gate(3)?,
+        distance_meters: row.get(4)?,
+        created_at,
+    })
+}
+
+/// Build an effort from `segment_id, run_id, start_time, elapsed_ms, distance_meters`
+fn effort_from_row(row: &rusqlite::Row) -> rusqlite::Result<SegmentEffort> {
+    let start_time_str: String = row.get(2)?;
+    let start_time = chrono::DateTime::parse_from_rfc3339(&start_time_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+
+    Ok(SegmentEffort {
+        segment_id: row.get(0)?,
+        run_id: row.get(1)?,
+        start_time,
+        elapsed_ms: row.get(3)?,
+        distance_meters: row.get(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::segments::segment_on_track;
+    use crate::geo::IndexedTrack;
+    use crate::models::Run;
+    use chrono::{Duration, Utc};
+
+    /// A finished run north along the same road, `seconds_per_point` per 100 m
+    fn run_north(seconds_per_point: i64) -> Run {
+        let start = Utc::now() - Duration::days(1);
+        let mut run = Run::new();
+        for i in 0..=10 {
+            run.add_point(GpsPoint::new(
+                51.5 + i as f64 * 0.0009,
+                -0.1,
+                start + Duration::seconds(i * seconds_per_point),
+            ));
+        }
+        run.recalculate_stats();
+        run.end_time = run.points.last().map(|p| p.timestamp);
+        run
+    }
+
+    #[test]
+    fn test_segment_leaderboard() {
+        let db = Database::open(":memory:").unwrap();
+        let slow = run_north(40);
+        db.save_run(&slow).unwrap();
+        // Still recording, so not matched yet
+        let mut fast = run_north(20);
+        fast.end_time = None;
+        db.save_run(&fast).unwrap();
+
+        // Existing runs are scanned when the segment is saved
+        let track = IndexedTrack::new(slow.points.clone());
+        let segment = segment_on_track("Climb".to_string(), &track, 200.0, 700.0).unwrap();
+        assert_eq!(db.save_segment(&segment).unwrap(), 1);
+        assert_eq!(db.get_segment(&segment.id).unwrap().unwrap(), segment);
+        assert_eq!(db.list_segments().unwrap().len(), 1);
+
+        // New runs are matched on save
+        fast.end_time = fast.points.last().map(|p| p.timestamp);
+        db.save_run(&fast).unwrap();
+        let board = db.segment_leaderboard(&segment.id, None).unwrap();
+        let order: Vec<&str> = board.iter().map(|e| e.run_id.as_str()).collect();
+        assert_eq!(order, vec![fast.id.as_str(), slow.id.as_str()]);
+        assert!(board[0].elapsed_ms < board[1].elapsed_ms);
+        assert_eq!(
+            db.segment_leaderboard(&segment.id, Some(1)).unwrap().len(),
+            1
+        );
+        assert_eq!(db.get_run_segment_efforts(&slow.id).unwrap().len(), 1);
+
+        // Runs in the trash leave the leaderboard, and purging removes efforts
+        db.delete_run(&fast.id).unwrap();
+        assert_eq!(db.segment_leaderboard(&segment.id, None).unwrap().len(), 1);
+        db.purge_run(&fast.id).unwrap();
+        assert!(db.get_run_segment_efforts(&fast.id).unwrap().is_empty());
+
+        assert!(db.rename_segment(&segment.id, "Big climb").unwrap());
+        assert!(db.delete_segment(&segment.id).unwrap());
+        assert!(db.get_run_segment_efforts(&slow.id).unwrap().is_empty());
+        assert!(!db.delete_segment(&segment.id).unwrap());
+    }
+}
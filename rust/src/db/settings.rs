@@ -0,0 +1,62 @@
+use anyhow::Result;
+use rusqlite::OptionalExtension;
+
+use super::Database;
+use crate::models::PrivacyZone;
+
+const PRIVACY_ZONES_KEY: &str = "privacy_zones";
+
+impl Database {
+    /// Get a setting's stored value
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    /// Store a setting, replacing any previous value
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            rusqlite::params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Privacy zones hidden from exports and shared maps
+    pub fn get_privacy_zones(&self) -> Result<Vec<PrivacyZone>> {
+        Ok(match self.get_setting(PRIVACY_ZONES_KEY)? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Vec::new(),
+        })
+    }
+
+    /// Replace the privacy zones
+    pub fn set_privacy_zones(&self, zones: &[PrivacyZone]) -> Result<()> {
+        self.set_setting(PRIVACY_ZONES_KEY, &serde_json::to_string(zones)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_privacy_zones() {
+        let db = Database::open(":memory:").unwrap();
+        assert!(db.get_privacy_zones().unwrap().is_empty());
+
+        let home = PrivacyZone::new("Home".to_string(), 51.5, -0.1, 300.0).unwrap();
+        let work = PrivacyZone::new("Work".to_string(), 51.52, -0.12, 150.0).unwrap();
+        db.set_privacy_zones(&[home.clone(), work]).unwrap();
+        assert_eq!(db.get_privacy_zones().unwrap().len(), 2);
+
+        db.set_privacy_zones(std::slice::from_ref(&home)).unwrap();
+        assert_eq!(db.get_privacy_zones().unwrap(), vec![home]);
+    }
+}
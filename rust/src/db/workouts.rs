@@ -0,0 +1,103 @@
+use anyhow::Result;
+use rusqlite::OptionalExtension;
+
+use super::Database;
+use crate::models::Workout;
+
+impl Database {
+    /// Save a workout, replacing any with the same ID
+    pub fn save_workout(&self, workout: &Workout) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO workouts (id, name, workout_json, created_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET
+                 name = excluded.name,
+                 workout_json = excluded.workout_json",
+            rusqlite::params![
+                workout.id,
+                workout.name,
+                serde_json::to_string(workout)?,
+                workout.created_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get a workout
+    pub fn get_workout(&self, id: &str) -> Result<Option<Workout>> {
+        let conn = self.conn.lock().unwrap();
+        let json: Option<String> = conn
+            .query_row(
+                "SELECT workout_json FROM workouts WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(json.map(|j| serde_json::from_str(&j)).transpose()?)
+    }
+
+    /// List workouts by name
+    pub fn list_workouts(&self) -> Result<Vec<Workout>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT workout_json FROM workouts ORDER BY name COLLATE NOCASE, id")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        Ok(rows
+            .filter_map(|json| serde_json::from_str(&json.ok()?).ok())
+            .collect())
+    }
+
+    /// Delete a workout. Returns false if it didn't exist.
+    pub fn delete_workout(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM workouts WHERE id = ?1", [id])?;
+        Ok(rows > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{StepKind, StepLength, WorkoutStep};
+
+    #[test]
+    fn test_workouts() {
+        let db = Database::open(":memory:").unwrap();
+        let work = WorkoutStep {
+            kind: StepKind::Work,
+            length: StepLength::Distance { meters: 400.0 },
+            pace_sec_per_km: Some(200.0),
+        };
+        let recovery = WorkoutStep {
+            kind: StepKind::Recovery,
+            length: StepLength::Time { ms: 60_000 },
+            pace_sec_per_km: None,
+        };
+        let mut workout = Workout::new("Track".to_string(), None, 8, work, Some(recovery), None);
+        db.save_workout(&workout).unwrap();
+        db.save_workout(&Workout::new(
+            "Hills".to_string(),
+            None,
+            6,
+            work,
+            None,
+            None,
+        ))
+        .unwrap();
+
+        assert_eq!(db.get_workout(&workout.id).unwrap().unwrap(), workout);
+
+        workout.repeats = 10;
+        db.save_workout(&workout).unwrap();
+        let list = db.list_workouts().unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].name, "Hills");
+        assert_eq!(list[1].repeats, 10);
+
+        assert!(db.delete_workout(&workout.id).unwrap());
+        assert!(db.get_workout(&workout.id).unwrap().is_none());
+        assert!(!db.delete_workout(&workout.id).unwrap());
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::Write;
+
+use super::ShareableRun;
+
+/// Escape text for an XML element or attribute
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Write a run as a GPX 1.1 track, one `trkseg` per visible section
+pub fn write(run: &ShareableRun) -> String {
+    let mut gpx = String::new();
+    let name = run.name.as_deref().unwrap_or("Run");
+
+    // Writing to a String can't fail
+    let _ = writeln!(gpx, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        gpx,
+        r#"<gpx version="1.1" creator="BansheeRun" xmlns="http://www.topografix.com/GPX/1/1">"#
+    );
+    let _ = writeln!(
+        gpx,
+        "  <metadata><name>{}</name><time>{}</time></metadata>",
+        escape(name),
+        run.start_time
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    );
+    let _ = writeln!(gpx, "  <trk>");
+    let _ = writeln!(gpx, "    <name>{}</name>", escape(name));
+    let _ = writeln!(gpx, "    <type>running</type>");
+    for section in &run.sections {
+        let _ = writeln!(gpx, "    <trkseg>");
+        for point in section {
+            let _ = write!(
+                gpx,
+                r#"      <trkpt lat="{:.7}" lon="{:.7}">"#,
+                point.lat, point.lon
+            );
+            if let Some(altitude) = point.altitude {
+                let _ = write!(gpx, "<ele>{altitude:.1}</ele>");
+            }
+            let _ = writeln!(
+                gpx,
+                "<time>{}</time></trkpt>",
+                point
+                    .timestamp
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+            );
+        }
+        let _ = writeln!(gpx, "    </trkseg>");
+    }
+    let _ = writeln!(gpx, "  </trk>");
+    let _ = writeln!(gpx, "</gpx>");
+    gpx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{GpsPoint, PrivacyZone, Run};
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_write_gpx_hides_privacy_zones() {
+        let start = Utc::now();
+        let mut run = Run::new();
+        run.name = Some("Home & back".to_string());
+        for i in 0..10 {
+            run.add_point(GpsPoint {
+                altitude: Some(20.0),
+                ..GpsPoint::new(
+                    51.5 + i as f64 * 0.001,
+                    -0.1,
+                    start + Duration::seconds(i * 30),
+                )
+            });
+        }
+        run.recalculate_stats();
+        let home = PrivacyZone::new("Home".to_string(), 51.5, -0.1, 250.0).unwrap();
+
+        let shareable = ShareableRun::new(&run, &[home]);
+        // Stats still count the hidden start
+        assert_eq!(shareable.distance_meters, run.distance_meters);
+
+        let gpx = write(&shareable);
+        assert!(gpx.contains("<name>Home &amp; back</name>"));
+        assert_eq!(gpx.matches("<trkseg>").count(), 1);
+
+        // It reads back with the start left out
+        let track = crate::import::gpx::parse(&gpx).unwrap();
+        assert_eq!(track.name.as_deref(), Some("Home & back"));
+        assert_eq!(track.points.len(), 7);
+        assert!((track.points[0].lat - 51.503).abs() < 1e-9);
+        assert_eq!(track.points[0].altitude, Some(20.0));
+        // Times are written to the millisecond
+        assert_eq!(
+            track.points[0].timestamp.timestamp_millis(),
+            run.points[3].timestamp.timestamp_millis()
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Writing runs out for other apps and people, with privacy zones applied
+
+pub mod gpx;
+
+use crate::geo::privacy::visible_sections;
+use crate::models::{GpsPoint, PrivacyZone, Run};
+
+/// A run as it may be shared: the path outside privacy zones, with stats
+/// from the full run so they still count the hidden parts
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShareableRun {
+    pub name: Option<String>,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub distance_meters: f64,
+    pub duration_ms: i64,
+    pub avg_pace_sec_per_km: Option<f64>,
+    /// Visible parts of the path, split where it enters a zone
+    pub sections: Vec<Vec<GpsPoint>>,
+}
+
+impl ShareableRun {
+    pub fn new(run: &Run, zones: &[PrivacyZone]) -> Self {
+        Self {
+            name: run.name.clone(),
+            start_time: run.start_time,
+            distance_meters: run.distance_meters,
+            duration_ms: run.duration_ms,
+            avg_pace_sec_per_km: run.avg_pace_sec_per_km,
+            sections: visible_sections(&run.points, zones),
+        }
+    }
+}
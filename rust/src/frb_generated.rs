@@ -648,49 +648,6 @@ fn wire__crate__api__stats_api__format_pace_per_mile_impl(
         },
     )
 }
-fn wire__crate__api__banshee_api__get_ai_pacer_position_impl(
-    port_: flutter_rust_bridge::for_generated::MessagePort,
-    ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
-    rust_vec_len_: i32,
-    data_len_: i32,
-) {
-    FLUTTER_RUST_BRIDGE_HANDLER.wrap_normal::<flutter_rust_bridge::for_generated::SseCodec, _, _>(
-        flutter_rust_bridge::for_generated::TaskInfo {
-            debug_name: "get_ai_pacer_position",
-            port: Some(port_),
-            mode: flutter_rust_bridge::for_generated::FfiCallMode::Normal,
-        },
-        move || {
-            let message = unsafe {
-                flutter_rust_bridge::for_generated::Dart2RustMessageSse::from_wire(
-                    ptr_,
-                    rust_vec_len_,
-                    data_len_,
-                )
-            };
-            let mut deserializer =
-                flutter_rust_bridge::for_generated::SseDeserializer::new(message);
-            let api_start_lat = <f64>::sse_decode(&mut deserializer);
-            let api_start_lon = <f64>::sse_decode(&mut deserializer);
-            let api_target_pace_sec_per_km = <f64>::sse_decode(&mut deserializer);
-            let api_elapsed_ms = <i64>::sse_decode(&mut deserializer);
-            let api_route = <Option<Vec<(f64, f64)>>>::sse_decode(&mut deserializer);
-            deserializer.end();
-            move |context| {
-                transform_result_sse::<_, String>((move || {
-                    let output_ok = crate::api::banshee_api::get_ai_pacer_position(
-                        api_start_lat,
-                        api_start_lon,
-                        api_target_pace_sec_per_km,
-                        api_elapsed_ms,
-                        api_route,
-                    )?;
-                    Ok(output_ok)
-                })())
-            }
-        },
-    )
-}
 fn wire__crate__api__run_api__get_all_runs_impl(
     port_: flutter_rust_bridge::for_generated::MessagePort,
     ptr_: flutter_rust_bridge::for_generated::PlatformGeneralizedUint8ListPtr,
@@ -1422,12 +1379,6 @@ fn pde_ffi_dispatcher_primary_impl(
         8 => wire__crate__api__run_api__create_run_impl(port, ptr, rust_vec_len, data_len),
         9 => wire__crate__api__run_api__delete_run_impl(port, ptr, rust_vec_len, data_len),
         12 => wire__crate__api__run_api__finish_run_impl(port, ptr, rust_vec_len, data_len),
-        19 => wire__crate__api__banshee_api__get_ai_pacer_position_impl(
-            port,
-            ptr,
-            rust_vec_len,
-            data_len,
-        ),
         20 => wire__crate__api__run_api__get_all_runs_impl(port, ptr, rust_vec_len, data_len),
         22 => wire__crate__api__banshee_api__get_recorded_banshee_position_impl(
             port,
@@ -1642,6 +1593,77 @@ impl SseEncode for crate::api::banshee_api::BansheeStateDto {
     }
 }
 
+impl SseEncode for crate::api::banshee_api::BansheeEventDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <crate::api::banshee_api::BansheeEventKindDto>::sse_encode(self.kind, serializer);
+        <Option<u32>>::sse_encode(self.banshee_index, serializer);
+        <Option<String>>::sse_encode(self.banshee_name, serializer);
+        <f64>::sse_encode(self.gap_meters, serializer);
+        <Option<f64>>::sse_encode(self.threshold_meters, serializer);
+        <f64>::sse_encode(self.runner_distance_meters, serializer);
+        <i64>::sse_encode(self.runner_elapsed_ms, serializer);
+    }
+}
+
+impl SseEncode for crate::api::banshee_api::BansheeEventKindDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(
+            match self {
+                crate::api::banshee_api::BansheeEventKindDto::Overtaken => 0,
+                crate::api::banshee_api::BansheeEventKindDto::Overtook => 1,
+                crate::api::banshee_api::BansheeEventKindDto::GapGrew => 2,
+                crate::api::banshee_api::BansheeEventKindDto::GapClosed => 3,
+                crate::api::banshee_api::BansheeEventKindDto::BansheeFinished => 4,
+                crate::api::banshee_api::BansheeEventKindDto::RunnerFinished => 5,
+                _ => {
+                    unimplemented!("");
+                }
+            },
+            serializer,
+        );
+    }
+}
+
+impl SseEncode for crate::api::workout_api::WorkoutEventDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <crate::api::workout_api::WorkoutEventKindDto>::sse_encode(self.kind, serializer);
+        <u32>::sse_encode(self.step_index, serializer);
+        <u32>::sse_encode(self.step_count, serializer);
+        <crate::api::workout_api::WorkoutStepDto>::sse_encode(self.step, serializer);
+        <f64>::sse_encode(self.runner_distance_meters, serializer);
+        <i64>::sse_encode(self.runner_elapsed_ms, serializer);
+    }
+}
+
+impl SseEncode for crate::api::workout_api::WorkoutEventKindDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(
+            match self {
+                crate::api::workout_api::WorkoutEventKindDto::StepStarted => 0,
+                crate::api::workout_api::WorkoutEventKindDto::WorkoutFinished => 1,
+                _ => {
+                    unimplemented!("");
+                }
+            },
+            serializer,
+        );
+    }
+}
+
+impl SseEncode for crate::api::workout_api::WorkoutStepDto {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.kind, serializer);
+        <Option<f64>>::sse_encode(self.distance_meters, serializer);
+        <Option<i64>>::sse_encode(self.duration_ms, serializer);
+        <Option<f64>>::sse_encode(self.pace_sec_per_km, serializer);
+    }
+}
+
 impl SseEncode for crate::api::run_api::GpsPointDto {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1748,6 +1770,16 @@ impl SseEncode for Option<i64> {
     }
 }
 
+impl SseEncode for Option<u32> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.is_some(), serializer);
+        if let Some(value) = self {
+            <u32>::sse_encode(value, serializer);
+        }
+    }
+}
+
 impl SseEncode for Option<crate::api::run_api::RunDetailDto> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -1824,6 +1856,13 @@ impl SseEncode for crate::api::run_api::RunSummaryDto {
     }
 }
 
+impl SseEncode for u32 {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        serializer.cursor.write_u32::<NativeEndian>(self).unwrap();
+    }
+}
+
 impl SseEncode for u8 {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -0,0 +1,138 @@
+use super::IndexedTrack;
+use crate::models::ElevationSample;
+
+/// Default spacing for grade sampling; short enough to follow real hills,
+/// long enough to smooth out GPS altitude noise
+pub const GRADE_SAMPLE_SPACING_M: f64 = 50.0;
+
+/// Steepest grade (rise over run) the cost model is evaluated at
+const MAX_GRADE: f64 = 0.45;
+
+/// A stretch of a route with a constant grade
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradeSegment {
+    /// Distance from the start where the stretch begins, in meters
+    pub start_m: f64,
+    /// Distance from the start where the stretch ends, in meters
+    pub end_m: f64,
+    /// Rise over run (0.05 = 5% uphill, negative = downhill)
+    pub grade: f64,
+}
+
+impl GradeSegment {
+    pub fn length(&self) -> f64 {
+        self.end_m - self.start_m
+    }
+}
+
+/// Energy cost of running on a grade relative to the flat (Minetti et al. 2002).
+/// A factor of 1.2 means running there at a given pace takes as much effort as
+/// running 1.2x faster on the flat, so an even-effort pace is flat pace x factor.
+pub fn grade_adjustment_factor(grade: f64) -> f64 {
+    let i = grade.clamp(-MAX_GRADE, MAX_GRADE);
+    let cost =
+        155.4 * i.powi(5) - 30.4 * i.powi(4) - 43.3 * i.powi(3) + 46.3 * i.powi(2) + 19.5 * i + 3.6;
+    cost / 3.6
+}
+
+/// Sample a track's altitude every `spacing_m` meters and return the grade of
+/// each stretch. Stretches without altitude on both ends are treated as flat.
+pub fn grade_profile(track: &IndexedTrack, spacing_m: f64) -> Vec<GradeSegment> {
+    let total = track.total_distance();
+    if total <= 0.0 || spacing_m <= 0.0 {
+        return Vec::new();
+    }
+
+    let altitude_at = |d: f64| track.position_at_distance(d).and_then(|p| p.altitude);
+
+    let mut segments = Vec::new();
+    let mut start = 0.0;
+    let mut start_alt = altitude_at(0.0);
+
+    while start < total {
+        let end = (start + spacing_m).min(total);
+        let end_alt = altitude_at(end);
+        let grade = match (start_alt, end_alt) {
+            (Some(a1), Some(a2)) if end - start > 0.001 => (a2 - a1) / (end - start),
+            _ => 0.0,
+        };
+
+        segments.push(GradeSegment {
+            start_m: start,
+            end_m: end,
+            grade,
+        });
+        start = end;
+        start_alt = end_alt;
+    }
+
+    segments
+}
+
+/// Sample a track's altitude every `spacing_m` meters (and at the end),
+/// skipping places without altitude
+pub fn elevation_profile(track: &IndexedTrack, spacing_m: f64) -> Vec<ElevationSample> {
+    let total = track.total_distance();
+    if track.is_empty() || spacing_m <= 0.0 {
+        return Vec::new();
+    }
+
+    let steps = (total / spacing_m).ceil() as usize;
+    (0..=steps)
+        .filter_map(|i| {
+            let distance_m = (i as f64 * spacing_m).min(total);
+            let altitude_m = track.position_at_distance(distance_m)?.altitude?;
+            Some(ElevationSample {
+                distance_m,
+                altitude_m,
+            })
+        })
+        .collect()
+}
+
+/// Total climb and descent over a profile, in meters
+pub fn climb_totals(profile: &[ElevationSample]) -> (f64, f64) {
+    profile
+        .windows(2)
+        .map(|w| w[1].altitude_m - w[0].altitude_m)
+        .fold((0.0, 0.0), |(gain, loss), change| {
+            if change > 0.0 {
+                (gain + change, loss)
+            } else {
+                (gain, loss - change)
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GpsPoint;
+    use chrono::Utc;
+
+    #[test]
+    fn test_grade_adjustment_factor() {
+        assert!((grade_adjustment_factor(0.0) - 1.0).abs() < 1e-9);
+        assert!(grade_adjustment_factor(0.05) > 1.2);
+        assert!(grade_adjustment_factor(-0.05) < 1.0);
+        // Very steep descents cost more again
+        assert!(grade_adjustment_factor(-0.4) > grade_adjustment_factor(-0.15));
+    }
+
+    #[test]
+    fn test_grade_profile() {
+        // ~222m north, climbing 11m over the second half
+        let now = Utc::now();
+        let track = IndexedTrack::new(vec![
+            GpsPoint::new(51.500, -0.1, now).with_altitude(10.0),
+            GpsPoint::new(51.501, -0.1, now).with_altitude(10.0),
+            GpsPoint::new(51.502, -0.1, now).with_altitude(21.0),
+        ]);
+
+        let profile = grade_profile(&track, 50.0);
+        assert_eq!(profile.len(), 5);
+        assert!(profile[0].grade.abs() < 1e-9);
+        assert!((profile[3].grade - 11.0 / track.total_distance() * 2.0).abs() < 0.01);
+        assert!((profile.last().unwrap().end_m - track.total_distance()).abs() < 1e-9);
+    }
+}
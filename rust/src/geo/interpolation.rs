@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 
 use crate::models::GpsPoint;
 
-use super::distance::cumulative_distances;
+use super::distance::{cumulative_distances, haversine_distance_points};
 
 /// Interpolate position at a specific elapsed time (in milliseconds) along a track
 pub fn interpolate_position(points: &[GpsPoint], elapsed_ms: i64) -> Option<GpsPoint> {
@@ -75,6 +75,176 @@ pub fn interpolate_position_at_distance(points: &[GpsPoint], distance_m: f64) ->
     Some(points.last().unwrap().clone())
 }
 
+/// A track with precomputed cumulative distance and elapsed time per point,
+/// so position lookups are a binary search instead of a scan of the track
+#[derive(Debug, Clone)]
+pub struct IndexedTrack {
+    points: Vec<GpsPoint>,
+    /// Cumulative distance in meters at each point
+    distances: Vec<f64>,
+    /// Elapsed milliseconds from the first point at each point
+    times_ms: Vec<i64>,
+}
+
+impl IndexedTrack {
+    pub fn new(points: Vec<GpsPoint>) -> Self {
+        let distances = cumulative_distances(&points);
+        let times_ms = match points.first() {
+            Some(first) => points
+                .iter()
+                .map(|p| (p.timestamp - first.timestamp).num_milliseconds())
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Self {
+            points,
+            distances,
+            times_ms,
+        }
+    }
+
+    /// Add a point to the end of the track, e.g. a live GPS fix
+    pub fn push(&mut self, point: GpsPoint) {
+        let (distance, time_ms) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (
+                self.total_distance() + haversine_distance_points(last, &point),
+                (point.timestamp - first.timestamp).num_milliseconds(),
+            ),
+            _ => (0.0, 0),
+        };
+        self.distances.push(distance);
+        self.times_ms.push(time_ms);
+        self.points.push(point);
+    }
+
+    pub fn points(&self) -> &[GpsPoint] {
+        &self.points
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Cumulative distance in meters at each point
+    pub fn distances(&self) -> &[f64] {
+        &self.distances
+    }
+
+    /// Total length of the track in meters
+    pub fn total_distance(&self) -> f64 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// Elapsed time from first to last point in milliseconds
+    pub fn duration_ms(&self) -> i64 {
+        self.times_ms.last().copied().unwrap_or(0)
+    }
+
+    /// Index of the segment `[i, i + 1]` containing `value` in a sorted array
+    fn segment_index(values: &[f64], value: f64) -> usize {
+        let idx = values.partition_point(|&v| v <= value);
+        idx.saturating_sub(1).min(values.len().saturating_sub(2))
+    }
+
+    /// Fraction of the way through segment `i` for `value`
+    fn fraction(lo: f64, hi: f64, value: f64) -> f64 {
+        if hi - lo > 0.001 {
+            ((value - lo) / (hi - lo)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Position at an elapsed time; clamps to the ends of the track
+    pub fn position_at_time(&self, elapsed_ms: i64) -> Option<GpsPoint> {
+        if self.points.len() < 2 || elapsed_ms <= 0 {
+            return self.points.first().cloned();
+        }
+        if elapsed_ms >= self.duration_ms() {
+            return self.points.last().cloned();
+        }
+
+        let i = self.time_segment(elapsed_ms);
+        let fraction = Self::fraction(
+            self.times_ms[i] as f64,
+            self.times_ms[i + 1] as f64,
+            elapsed_ms as f64,
+        );
+        Some(interpolate_by_fraction(
+            &self.points[i],
+            &self.points[i + 1],
+            fraction,
+        ))
+    }
+
+    /// Distance covered at an elapsed time; clamps to the ends of the track
+    pub fn distance_at_time(&self, elapsed_ms: i64) -> f64 {
+        if self.points.len() < 2 || elapsed_ms <= 0 {
+            return 0.0;
+        }
+        if elapsed_ms >= self.duration_ms() {
+            return self.total_distance();
+        }
+
+        let i = self.time_segment(elapsed_ms);
+        let fraction = Self::fraction(
+            self.times_ms[i] as f64,
+            self.times_ms[i + 1] as f64,
+            elapsed_ms as f64,
+        );
+        self.distances[i] + (self.distances[i + 1] - self.distances[i]) * fraction
+    }
+
+    /// Position at a distance along the track; clamps to the ends of the track
+    pub fn position_at_distance(&self, distance_m: f64) -> Option<GpsPoint> {
+        if self.points.len() < 2 || distance_m <= 0.0 {
+            return self.points.first().cloned();
+        }
+        if distance_m >= self.total_distance() {
+            return self.points.last().cloned();
+        }
+
+        let i = Self::segment_index(&self.distances, distance_m);
+        let fraction = Self::fraction(self.distances[i], self.distances[i + 1], distance_m);
+        Some(interpolate_by_fraction(
+            &self.points[i],
+            &self.points[i + 1],
+            fraction,
+        ))
+    }
+
+    /// Elapsed time at which the track reached a distance, None past the end
+    pub fn time_at_distance(&self, distance_m: f64) -> Option<i64> {
+        if self.points.is_empty() {
+            return None;
+        }
+        if distance_m <= 0.0 {
+            return Some(0);
+        }
+        if distance_m > self.total_distance() {
+            return None;
+        }
+        if self.points.len() < 2 {
+            return Some(0);
+        }
+
+        // The first point at or past the distance, so a stationary stretch
+        // gives the time the runner arrived rather than when they moved on
+        let j = self.distances.partition_point(|&d| d < distance_m).max(1);
+        let i = j - 1;
+        let fraction = Self::fraction(self.distances[i], self.distances[j], distance_m);
+        let segment_time = (self.times_ms[j] - self.times_ms[i]) as f64;
+        Some(self.times_ms[i] + (segment_time * fraction) as i64)
+    }
+
+    /// Index of the segment containing an elapsed time
+    fn time_segment(&self, elapsed_ms: i64) -> usize {
+        let idx = self.times_ms.partition_point(|&t| t <= elapsed_ms);
+        idx.saturating_sub(1).min(self.times_ms.len() - 2)
+    }
+}
+
 /// Interpolate between two points at a specific timestamp
 fn interpolate_between(p1: &GpsPoint, p2: &GpsPoint, target_time: DateTime<Utc>) -> GpsPoint {
     let segment_duration = (p2.timestamp - p1.timestamp).num_milliseconds() as f64;
@@ -134,7 +304,7 @@ pub fn time_at_distance(points: &[GpsPoint], distance_m: f64) -> Option<i64> {
     let cumulative = cumulative_distances(points);
     let total_distance = *cumulative.last().unwrap_or(&0.0);
 
-    if distance_m >= total_distance {
+    if distance_m > total_distance {
         let duration = points.last().unwrap().timestamp - points[0].timestamp;
         return Some(duration.num_milliseconds());
     }
@@ -227,4 +397,68 @@ mod tests {
         let result = interpolate_position_at_distance(&points, 500.0).unwrap();
         assert!(result.lat > 51.5000);
     }
+
+    #[test]
+    fn test_indexed_track_matches_linear_scan() {
+        let points = create_test_track();
+        let track = IndexedTrack::new(points.clone());
+
+        for elapsed_ms in [0, 15_000, 60_000, 95_000, 180_000, 500_000] {
+            let fast = track.position_at_time(elapsed_ms).unwrap();
+            let slow = interpolate_position(&points, elapsed_ms).unwrap();
+            assert!((fast.lat - slow.lat).abs() < 1e-9);
+            assert!(
+                (track.distance_at_time(elapsed_ms) - distance_at_time(&points, elapsed_ms)).abs()
+                    < 1e-6
+            );
+        }
+
+        for distance_m in [0.0, 50.0, 150.0, 300.0] {
+            let fast = track.position_at_distance(distance_m).unwrap();
+            let slow = interpolate_position_at_distance(&points, distance_m).unwrap();
+            assert!((fast.lat - slow.lat).abs() < 1e-9);
+            assert_eq!(
+                track.time_at_distance(distance_m),
+                time_at_distance(&points, distance_m)
+            );
+        }
+
+        assert!(track.time_at_distance(10_000.0).is_none());
+    }
+
+    #[test]
+    fn test_time_at_distance_first_arrival() {
+        let start = Utc::now();
+        let at = |secs: i64, lat: f64| GpsPoint::new(lat, -0.1, start + Duration::seconds(secs));
+        // Out, stopped at the lights for two minutes, on, then stopped at the end
+        let points = vec![
+            at(0, 51.5000),
+            at(60, 51.5010),
+            at(120, 51.5010),
+            at(180, 51.5010),
+            at(240, 51.5020),
+            at(300, 51.5020),
+        ];
+        let track = IndexedTrack::new(points.clone());
+        let lights = track.distances()[1];
+        let end = track.total_distance();
+
+        assert_eq!(track.time_at_distance(lights), Some(60_000));
+        assert_eq!(time_at_distance(&points, lights), Some(60_000));
+        assert_eq!(track.time_at_distance(end), Some(240_000));
+        assert_eq!(time_at_distance(&points, end), Some(240_000));
+    }
+
+    #[test]
+    fn test_indexed_track_push() {
+        let points = create_test_track();
+        let whole = IndexedTrack::new(points.clone());
+        let mut pushed = IndexedTrack::new(Vec::new());
+        for point in points {
+            pushed.push(point);
+        }
+
+        assert_eq!(pushed.distances(), whole.distances());
+        assert_eq!(pushed.duration_ms(), whole.duration_ms());
+    }
 }
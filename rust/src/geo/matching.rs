@@ -0,0 +1,239 @@
+use super::IndexedTrack;
+
+/// Meters per degree of latitude, for the local flat-earth projection
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Cross-track error at which the runner is flagged off course
+pub const OFF_COURSE_THRESHOLD_M: f64 = 40.0;
+
+/// Cross-track error the runner has to come back within to be on course again
+pub const BACK_ON_COURSE_THRESHOLD_M: f64 = 25.0;
+
+/// How far behind its last match the runner may be matched (GPS jitter)
+const BACKTRACK_WINDOW_M: f64 = 50.0;
+
+/// How far ahead of its last match the runner may be matched in one fix
+const FORWARD_WINDOW_M: f64 = 500.0;
+
+/// Meters of cross-track error a candidate match costs per meter it lies
+/// behind / ahead of the last match. Where a route passes the same spot
+/// twice, this picks the pass the runner is actually on.
+const BACKWARD_PENALTY: f64 = 1.0;
+const FORWARD_PENALTY: f64 = 0.1;
+
+/// The closest point on a route to a GPS fix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteProjection {
+    /// Distance from the start of the route to the matched point, in meters
+    pub distance_along_m: f64,
+    /// Distance from the fix to the matched point, in meters
+    pub cross_track_m: f64,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Project a fix onto segment `i` of a route
+fn project_onto_segment(route: &IndexedTrack, i: usize, lat: f64, lon: f64) -> RouteProjection {
+    let a = &route.points()[i];
+    let b = &route.points()[i + 1];
+    let distances = route.distances();
+
+    // Flat-earth coordinates in meters around the segment start; fine over
+    // the length of a GPS segment
+    let lon_scale = a.lat.to_radians().cos() * METERS_PER_DEGREE;
+    let to_xy = |plat: f64, plon: f64| {
+        (
+            (plon - a.lon) * lon_scale,
+            (plat - a.lat) * METERS_PER_DEGREE,
+        )
+    };
+    let (bx, by) = to_xy(b.lat, b.lon);
+    let (px, py) = to_xy(lat, lon);
+
+    let length_sq = bx * bx + by * by;
+    let t = if length_sq > 0.0 {
+        ((px * bx + py * by) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (mx, my) = (bx * t, by * t);
+
+    RouteProjection {
+        distance_along_m: distances[i] + (distances[i + 1] - distances[i]) * t,
+        cross_track_m: ((px - mx).powi(2) + (py - my).powi(2)).sqrt(),
+        lat: a.lat + (b.lat - a.lat) * t,
+        lon: a.lon + (b.lon - a.lon) * t,
+    }
+}
+
+/// Projections of a fix onto each segment overlapping `[from_m, to_m]`
+/// along the route
+fn projections(
+    route: &IndexedTrack,
+    lat: f64,
+    lon: f64,
+    from_m: f64,
+    to_m: f64,
+) -> impl Iterator<Item = RouteProjection> + '_ {
+    let distances = route.distances();
+    (0..route.points().len().saturating_sub(1))
+        .filter(move |&i| distances[i + 1] >= from_m && distances[i] <= to_m)
+        .map(move |i| project_onto_segment(route, i, lat, lon))
+}
+
+/// Closest point on the route to a fix; None for routes under two points
+pub fn project_onto_route(route: &IndexedTrack, lat: f64, lon: f64) -> Option<RouteProjection> {
+    projections(route, lat, lon, f64::MIN, f64::MAX)
+        .min_by(|a, b| a.cross_track_m.total_cmp(&b.cross_track_m))
+}
+
+/// Where the runner is on a route after a fix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteMatch {
+    /// Distance along the route, in meters
+    pub distance_along_m: f64,
+    /// Distance from the route, in meters
+    pub cross_track_m: f64,
+    /// True once the runner strays past `OFF_COURSE_THRESHOLD_M`, until they
+    /// come back within `BACK_ON_COURSE_THRESHOLD_M`
+    pub off_course: bool,
+}
+
+/// Follows a runner along a route fix by fix. Matches are searched near the
+/// previous one first, so routes that loop back on themselves (out-and-backs,
+/// laps) don't make the runner jump between legs.
+#[derive(Debug, Clone)]
+pub struct RouteMatcher {
+    route: IndexedTrack,
+    last_distance_m: f64,
+    off_course: bool,
+}
+
+impl RouteMatcher {
+    pub fn new(route: IndexedTrack) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            route.points().len() >= 2,
+            "A route needs at least two points"
+        );
+        Ok(Self {
+            route,
+            last_distance_m: 0.0,
+            off_course: false,
+        })
+    }
+
+    pub fn route(&self) -> &IndexedTrack {
+        &self.route
+    }
+
+    /// Match the runner's latest fix to the route
+    pub fn update(&mut self, lat: f64, lon: f64) -> RouteMatch {
+        let last = self.last_distance_m;
+        let cost = |p: &RouteProjection| {
+            let offset = p.distance_along_m - last;
+            let penalty = if offset < 0.0 {
+                -offset * BACKWARD_PENALTY
+            } else {
+                offset * FORWARD_PENALTY
+            };
+            p.cross_track_m + penalty
+        };
+        let nearby = projections(
+            &self.route,
+            lat,
+            lon,
+            last - BACKTRACK_WINDOW_M,
+            last + FORWARD_WINDOW_M,
+        )
+        .min_by(|a, b| cost(a).total_cmp(&cost(b)));
+
+        // Nothing close nearby: a shortcut or a restart elsewhere on the route
+        let projection = match nearby {
+            Some(p) if p.cross_track_m <= OFF_COURSE_THRESHOLD_M => p,
+            _ => project_onto_route(&self.route, lat, lon).expect("route has at least two points"),
+        };
+
+        self.off_course = if self.off_course {
+            projection.cross_track_m > BACK_ON_COURSE_THRESHOLD_M
+        } else {
+            projection.cross_track_m > OFF_COURSE_THRESHOLD_M
+        };
+        // Progress only moves on while on course
+        if !self.off_course {
+            self.last_distance_m = projection.distance_along_m;
+        }
+
+        RouteMatch {
+            distance_along_m: self.last_distance_m,
+            cross_track_m: projection.cross_track_m,
+            off_course: self.off_course,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GpsPoint;
+    use chrono::Utc;
+
+    /// 0.001 degrees of latitude is ~111m
+    fn out_and_back() -> IndexedTrack {
+        IndexedTrack::new(
+            [51.500, 51.501, 51.502, 51.501, 51.500]
+                .iter()
+                .map(|&lat| GpsPoint::new(lat, -0.1, Utc::now()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_project_onto_route() {
+        let route = IndexedTrack::new(out_and_back().points()[..3].to_vec());
+        // ~14m east of the first leg, halfway along it
+        let p = project_onto_route(&route, 51.5005, -0.0998).unwrap();
+        assert!((p.distance_along_m - 55.6).abs() < 1.0);
+        assert!((p.cross_track_m - 13.9).abs() < 1.0);
+        assert!((p.lat - 51.5005).abs() < 1e-9 && (p.lon + 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_matcher_follows_out_and_back() {
+        let mut matcher = RouteMatcher::new(out_and_back()).unwrap();
+        let total = matcher.route().total_distance();
+
+        let out = matcher.update(51.5015, -0.1);
+        assert!((out.distance_along_m - total * 0.375).abs() < 1.0);
+
+        // Near the turnaround, then back down the same road: the match stays
+        // on the return leg instead of jumping back to the outbound one
+        matcher.update(51.502, -0.1);
+        matcher.update(51.5018, -0.1);
+        let back = matcher.update(51.5015, -0.1);
+        assert!((back.distance_along_m - total * 0.625).abs() < 1.0);
+        assert!(!back.off_course);
+    }
+
+    #[test]
+    fn test_off_course_hysteresis() {
+        let mut matcher = RouteMatcher::new(out_and_back()).unwrap();
+        let east_of_route = |m: f64| -0.1 + m / (METERS_PER_DEGREE * 51.5f64.to_radians().cos());
+
+        assert!(!matcher.update(51.5005, east_of_route(30.0)).off_course);
+        let strayed = matcher.update(51.5008, east_of_route(50.0));
+        assert!(strayed.off_course);
+        // Progress holds while off course
+        assert!((strayed.distance_along_m - 55.6).abs() < 1.0);
+
+        // Between the two thresholds: still off course
+        assert!(matcher.update(51.5009, east_of_route(30.0)).off_course);
+        let back = matcher.update(51.501, east_of_route(10.0));
+        assert!(!back.off_course);
+        assert!((back.distance_along_m - 111.3).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_matcher_needs_a_route() {
+        assert!(RouteMatcher::new(IndexedTrack::new(Vec::new())).is_err());
+    }
+}
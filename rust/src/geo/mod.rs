@@ -1,7 +1,14 @@
 pub mod distance;
+pub mod elevation;
 pub mod interpolation;
+pub mod matching;
 pub mod pace;
+pub mod privacy;
+pub mod segments;
+pub mod similarity;
+pub mod simplify;
 
 pub use distance::{haversine_distance, total_distance};
-pub use interpolation::{interpolate_position, interpolate_position_at_distance};
+pub use interpolation::{interpolate_position, interpolate_position_at_distance, IndexedTrack};
+pub use matching::{RouteMatch, RouteMatcher};
 pub use pace::{calculate_pace, calculate_splits, format_pace, Split};
@@ -19,6 +19,32 @@ pub struct Split {
     pub cumulative_time_ms: i64,
 }
 
+/// Standard distances (meters, display name) tracked as best efforts
+pub const BEST_EFFORT_DISTANCES: &[(f64, &str)] = &[
+    (400.0, "400m"),
+    (1000.0, "1K"),
+    (1609.344, "1 mile"),
+    (5000.0, "5K"),
+    (10_000.0, "10K"),
+    (21_097.5, "Half marathon"),
+    (42_195.0, "Marathon"),
+];
+
+/// Fastest time over a standard distance within a single run
+#[derive(Debug, Clone)]
+pub struct BestEffort {
+    /// Distance in meters
+    pub distance_m: f64,
+    /// Display name (e.g. "5K")
+    pub name: String,
+    /// Fastest time in milliseconds
+    pub duration_ms: i64,
+    /// Elapsed time from the start of the run when the effort began
+    pub start_offset_ms: i64,
+    /// Pace in seconds per kilometer
+    pub pace_sec_per_km: f64,
+}
+
 /// Calculate pace in seconds per kilometer
 pub fn calculate_pace(distance_m: f64, duration_ms: i64) -> f64 {
     if distance_m <= 0.0 || duration_ms <= 0 {
@@ -119,6 +145,68 @@ pub fn calculate_splits(points: &[GpsPoint], split_distance_m: f64) -> Vec<Split
     splits
 }
 
+/// Find the fastest time (ms) to cover `distance_m` anywhere in the track.
+/// Returns the duration and the elapsed time at which the effort started.
+/// Efforts start on recorded points and end at an interpolated position.
+pub fn fastest_segment(points: &[GpsPoint], distance_m: f64) -> Option<(i64, i64)> {
+    if points.len() < 2 || distance_m <= 0.0 {
+        return None;
+    }
+
+    let cumulative = cumulative_distances(points);
+    let start_time = points[0].timestamp;
+    let mut best: Option<(i64, i64)> = None;
+    let mut j = 1;
+
+    for i in 0..points.len() - 1 {
+        let target = cumulative[i] + distance_m;
+        if j <= i {
+            j = i + 1;
+        }
+        while j < points.len() && cumulative[j] < target {
+            j += 1;
+        }
+        if j >= points.len() {
+            break;
+        }
+
+        let segment_distance = cumulative[j] - cumulative[j - 1];
+        let segment_time =
+            (points[j].timestamp - points[j - 1].timestamp).num_milliseconds() as f64;
+        let end_ms = if segment_distance > 0.001 {
+            let fraction = (target - cumulative[j - 1]) / segment_distance;
+            (points[j - 1].timestamp - start_time).num_milliseconds() as f64
+                + segment_time * fraction
+        } else {
+            (points[j].timestamp - start_time).num_milliseconds() as f64
+        };
+
+        let begin_ms = (points[i].timestamp - start_time).num_milliseconds();
+        let duration = end_ms as i64 - begin_ms;
+        if duration > 0 && best.is_none_or(|(d, _)| duration < d) {
+            best = Some((duration, begin_ms));
+        }
+    }
+
+    best
+}
+
+/// Calculate best efforts for each standard distance the track covers
+pub fn best_efforts(points: &[GpsPoint]) -> Vec<BestEffort> {
+    BEST_EFFORT_DISTANCES
+        .iter()
+        .filter_map(|&(distance_m, name)| {
+            fastest_segment(points, distance_m).map(|(duration_ms, start_offset_ms)| BestEffort {
+                distance_m,
+                name: name.to_string(),
+                duration_ms,
+                start_offset_ms,
+                pace_sec_per_km: calculate_pace(distance_m, duration_ms),
+            })
+        })
+        .collect()
+}
+
 /// Calculate current speed in meters per second from recent GPS points
 pub fn current_speed(points: &[GpsPoint], window_size: usize) -> f64 {
     if points.len() < 2 {
@@ -186,4 +274,29 @@ mod tests {
         let back_to_speed = pace_to_speed(pace);
         assert!((speed - back_to_speed).abs() < 0.01);
     }
+
+    #[test]
+    fn test_fastest_segment() {
+        use chrono::{Duration, Utc};
+
+        // ~111m per point: slow first half (60s/point), fast second half (30s/point)
+        let start = Utc::now();
+        let mut t = start;
+        let mut points = Vec::new();
+        for i in 0..20 {
+            points.push(GpsPoint::new(51.5 + 0.001 * i as f64, -0.1, t));
+            t += Duration::seconds(if i < 10 { 60 } else { 30 });
+        }
+
+        let (duration, start_offset) = fastest_segment(&points, 500.0).unwrap();
+        // 500m at ~30s per 111m is ~135s
+        assert!(duration > 120_000 && duration < 150_000);
+        assert!(start_offset >= 600_000);
+
+        assert!(fastest_segment(&points, 50_000.0).is_none());
+
+        let efforts = best_efforts(&points);
+        assert_eq!(efforts.len(), 3); // 400m, 1K and 1 mile
+        assert_eq!(efforts[0].name, "400m");
+    }
 }
@@ -0,0 +1,129 @@
+use super::haversine_distance;
+use crate::models::{GpsPoint, PrivacyZone};
+
+/// Whether a point falls inside any of the zones
+pub fn is_hidden(point: &GpsPoint, zones: &[PrivacyZone]) -> bool {
+    zones
+        .iter()
+        .any(|z| haversine_distance(point.lat, point.lon, z.lat, z.lon) <= z.radius_m)
+}
+
+/// Meters per degree of latitude, for the local flat-earth projection
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Whether the straight line between two points passes through any of the
+/// zones, even though both ends lie outside them
+fn chord_crosses(a: &GpsPoint, b: &GpsPoint, zones: &[PrivacyZone]) -> bool {
+    // Flat-earth coordinates in meters around `a`; fine over the length of
+    // a GPS segment
+    let lon_scale = a.lat.to_radians().cos() * METERS_PER_DEGREE;
+    let to_xy = |lat: f64, lon: f64| ((lon - a.lon) * lon_scale, (lat - a.lat) * METERS_PER_DEGREE);
+    let (bx, by) = to_xy(b.lat, b.lon);
+    let length_sq = bx * bx + by * by;
+
+    zones.iter().any(|z| {
+        let (zx, zy) = to_xy(z.lat, z.lon);
+        let t = if length_sq > 0.0 {
+            ((zx * bx + zy * by) / length_sq).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let lat = a.lat + (b.lat - a.lat) * t;
+        let lon = a.lon + (b.lon - a.lon) * t;
+        haversine_distance(lat, lon, z.lat, z.lon) <= z.radius_m
+    })
+}
+
+/// The parts of a track outside every privacy zone. The track is split
+/// wherever it enters a zone, or where the line between two points would
+/// cut across one, so nothing draws a line across the hidden part; sections
+/// left with a single point are dropped.
+pub fn visible_sections(points: &[GpsPoint], zones: &[PrivacyZone]) -> Vec<Vec<GpsPoint>> {
+    let mut sections = Vec::new();
+    let mut current: Vec<GpsPoint> = Vec::new();
+
+    for point in points {
+        if is_hidden(point, zones) {
+            if current.len() >= 2 {
+                sections.push(std::mem::take(&mut current));
+            }
+            current.clear();
+            continue;
+        }
+        if let Some(last) = current.last() {
+            if chord_crosses(last, point, zones) {
+                if current.len() >= 2 {
+                    sections.push(std::mem::take(&mut current));
+                }
+                current.clear();
+            }
+        }
+        current.push(point.clone());
+    }
+    if current.len() >= 2 {
+        sections.push(current);
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_visible_sections() {
+        let now = Utc::now();
+        // North from home, through a friend's street, and on
+        let points: Vec<GpsPoint> = (0..12)
+            .map(|i| GpsPoint::new(51.5 + i as f64 * 0.001, -0.1, now))
+            .collect();
+        let home = PrivacyZone::new("Home".to_string(), 51.5, -0.1, 250.0).unwrap();
+        let friend = PrivacyZone::new("Friend".to_string(), 51.506, -0.1, 100.0).unwrap();
+
+        let sections = visible_sections(&points, &[home.clone(), friend]);
+        let lats: Vec<Vec<f64>> = sections
+            .iter()
+            .map(|s| {
+                s.iter()
+                    .map(|p| (p.lat * 1000.0).round() / 1000.0)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            lats,
+            vec![
+                vec![51.503, 51.504, 51.505],
+                vec![51.507, 51.508, 51.509, 51.510, 51.511]
+            ]
+        );
+
+        assert_eq!(visible_sections(&points, &[]), vec![points.clone()]);
+        assert!(is_hidden(&points[0], &[home]));
+    }
+
+    #[test]
+    fn test_chord_across_zone() {
+        let now = Utc::now();
+        // Two sparse fixes either side of home; the line between them would
+        // run straight through it
+        let points = vec![
+            GpsPoint::new(51.497, -0.1, now),
+            GpsPoint::new(51.503, -0.1, now),
+        ];
+        let home = [PrivacyZone::new("Home".to_string(), 51.5, -0.1, 250.0).unwrap()];
+        assert!(!is_hidden(&points[0], &home));
+        assert!(!is_hidden(&points[1], &home));
+        assert!(visible_sections(&points, &home).is_empty());
+
+        // A zone off to the side of the line leaves it alone
+        let aside = PrivacyZone::new("Aside".to_string(), 51.5, -0.09, 250.0).unwrap();
+        assert_eq!(visible_sections(&points, &[aside]), vec![points.clone()]);
+    }
+
+    #[test]
+    fn test_invalid_zones() {
+        assert!(PrivacyZone::new("x".to_string(), 51.5, -0.1, 0.0).is_err());
+        assert!(PrivacyZone::new("x".to_string(), 91.0, -0.1, 200.0).is_err());
+    }
+}
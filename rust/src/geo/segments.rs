@@ -0,0 +1,196 @@
+use super::distance::{bearing, cumulative_distances};
+use super::IndexedTrack;
+use crate::models::{Gate, GpsPoint, Segment, SegmentEffort};
+
+/// Default gate half-width: wide enough for GPS error and the width of the
+/// road, narrow enough to miss a parallel street
+pub const GATE_HALF_WIDTH_M: f64 = 25.0;
+/// The direction of travel at a gate is taken over this far either side
+const GATE_BEARING_SPAN_M: f64 = 10.0;
+/// An effort's distance between the gates, as a fraction of the segment's,
+/// must fall in this range; anything else took a different way round
+const MIN_EFFORT_DISTANCE_RATIO: f64 = 0.8;
+const MAX_EFFORT_DISTANCE_RATIO: f64 = 1.3;
+
+/// Meters per degree of latitude
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// A gate across a track at `distance_m`, facing the direction of travel
+pub fn gate_on_track(track: &IndexedTrack, distance_m: f64) -> Option<Gate> {
+    let total = track.total_distance();
+    let centre = track.position_at_distance(distance_m)?;
+    let before = track.position_at_distance((distance_m - GATE_BEARING_SPAN_M).max(0.0))?;
+    let after = track.position_at_distance((distance_m + GATE_BEARING_SPAN_M).min(total))?;
+    if before.lat == after.lat && before.lon == after.lon {
+        return None;
+    }
+
+    Some(Gate {
+        lat: centre.lat,
+        lon: centre.lon,
+        bearing_deg: bearing(before.lat, before.lon, after.lat, after.lon),
+        half_width_m: GATE_HALF_WIDTH_M,
+    })
+}
+
+/// A segment over a stretch of a track, from `start_m` to `end_m` along it
+pub fn segment_on_track(
+    name: String,
+    track: &IndexedTrack,
+    start_m: f64,
+    end_m: f64,
+) -> anyhow::Result<Segment> {
+    anyhow::ensure!(
+        0.0 <= start_m && start_m < end_m && end_m <= track.total_distance(),
+        "The segment must lie within the track"
+    );
+    let start_gate = gate_on_track(track, start_m)
+        .ok_or_else(|| anyhow::anyhow!("No direction at the start"))?;
+    let end_gate =
+        gate_on_track(track, end_m).ok_or_else(|| anyhow::anyhow!("No direction at the end"))?;
+    Segment::new(name, start_gate, end_gate, end_m - start_m)
+}
+
+/// Where the move from `a` to `b` crosses the gate in its direction of
+/// travel, as a fraction of the way from `a` to `b`
+pub fn gate_crossing(gate: &Gate, a: &GpsPoint, b: &GpsPoint) -> Option<f64> {
+    // Flat projection around the gate: x east, y north
+    let scale_x = METERS_PER_DEGREE * gate.lat.to_radians().cos();
+    let project = |p: &GpsPoint| {
+        (
+            (p.lon - gate.lon) * scale_x,
+            (p.lat - gate.lat) * METERS_PER_DEGREE,
+        )
+    };
+    let (sin, cos) = gate.bearing_deg.to_radians().sin_cos();
+    // Distance along the direction of travel, and across it
+    let along = |(x, y): (f64, f64)| x * sin + y * cos;
+    let across = |(x, y): (f64, f64)| x * cos - y * sin;
+
+    let (pa, pb) = (project(a), project(b));
+    let (sa, sb) = (along(pa), along(pb));
+    if !(sa < 0.0 && sb >= 0.0) {
+        return None;
+    }
+
+    let fraction = -sa / (sb - sa);
+    let offset = across(pa) + fraction * (across(pb) - across(pa));
+    (offset.abs() <= gate.half_width_m).then_some(fraction)
+}
+
+/// Every traversal of `segment` in a run's points: a start gate crossing
+/// followed by an end gate crossing, over about the segment's distance.
+/// If the start is crossed again first, timing restarts from there.
+pub fn find_efforts(segment: &Segment, run_id: &str, points: &[GpsPoint]) -> Vec<SegmentEffort> {
+    let distances = cumulative_distances(points);
+    let at = |i: usize, fraction: f64| {
+        let (a, b) = (&points[i - 1], &points[i]);
+        let ms = ((b.timestamp - a.timestamp).num_milliseconds() as f64 * fraction).round();
+        let time = a.timestamp + chrono::Duration::milliseconds(ms as i64);
+        let distance = distances[i - 1] + (distances[i] - distances[i - 1]) * fraction;
+        (time, distance)
+    };
+
+    let mut efforts = Vec::new();
+    let mut started = None;
+    for i in 1..points.len() {
+        let (a, b) = (&points[i - 1], &points[i]);
+
+        if let Some(fraction) = gate_crossing(&segment.end_gate, a, b) {
+            if let Some((start_time, start_distance)) = started.take() {
+                let (end_time, end_distance) = at(i, fraction);
+                let distance_meters = end_distance - start_distance;
+                let ratio = distance_meters / segment.distance_meters;
+                if (MIN_EFFORT_DISTANCE_RATIO..=MAX_EFFORT_DISTANCE_RATIO).contains(&ratio) {
+                    efforts.push(SegmentEffort {
+                        segment_id: segment.id.clone(),
+                        run_id: run_id.to_string(),
+                        start_time,
+                        elapsed_ms: (end_time - start_time).num_milliseconds(),
+                        distance_meters,
+                    });
+                }
+            }
+        }
+
+        if let Some(fraction) = gate_crossing(&segment.start_gate, a, b) {
+            started = Some(at(i, fraction));
+        }
+    }
+    efforts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    /// Points north along `lon` from 51.500 to 51.510, one every 100 m
+    fn north(lon: f64, seconds_per_point: i64) -> Vec<GpsPoint> {
+        let start = Utc::now();
+        (0..=10)
+            .map(|i| {
+                GpsPoint::new(
+                    51.5 + i as f64 * 0.0009,
+                    lon,
+                    start + Duration::seconds(i * seconds_per_point),
+                )
+            })
+            .collect()
+    }
+
+    fn segment() -> Segment {
+        // 200 m to 700 m along the road north
+        let track = IndexedTrack::new(north(-0.1, 30));
+        segment_on_track("Hill".to_string(), &track, 200.0, 700.0).unwrap()
+    }
+
+    #[test]
+    fn test_gate_crossing() {
+        let gate = segment().start_gate;
+        assert!((gate.bearing_deg - 0.0).abs() < 0.5 || (gate.bearing_deg - 360.0).abs() < 0.5);
+
+        let now = Utc::now();
+        let south = GpsPoint::new(gate.lat - 0.0001, -0.1, now);
+        let north = GpsPoint::new(gate.lat + 0.0003, -0.1, now);
+        let fraction = gate_crossing(&gate, &south, &north).unwrap();
+        assert!((fraction - 0.25).abs() < 0.01);
+        // Wrong way, or off to the side
+        assert!(gate_crossing(&gate, &north, &south).is_none());
+        let east = |p: &GpsPoint| GpsPoint::new(p.lat, -0.099, now);
+        assert!(gate_crossing(&gate, &east(&south), &east(&north)).is_none());
+    }
+
+    #[test]
+    fn test_find_efforts() {
+        let segment = segment();
+        // Same road, 5 m over, one point every 20 s
+        let efforts = find_efforts(&segment, "run", &north(-0.10007, 20));
+        assert_eq!(efforts.len(), 1);
+        let effort = &efforts[0];
+        assert!((effort.distance_meters - 500.0).abs() < 5.0);
+        assert!(
+            (effort.elapsed_ms - 100_000).abs() < 1000,
+            "{}",
+            effort.elapsed_ms
+        );
+
+        // Running the road the other way doesn't count
+        let mut southbound = north(-0.1, 20);
+        southbound.reverse();
+        for (i, p) in southbound.iter_mut().enumerate() {
+            p.timestamp = Utc::now() + Duration::seconds(i as i64 * 20);
+        }
+        assert!(find_efforts(&segment, "run", &southbound).is_empty());
+
+        // Nor does a parallel street
+        assert!(find_efforts(&segment, "run", &north(-0.098, 20)).is_empty());
+    }
+
+    #[test]
+    fn test_segment_must_lie_on_track() {
+        let track = IndexedTrack::new(north(-0.1, 30));
+        assert!(segment_on_track("x".to_string(), &track, 500.0, 200.0).is_err());
+        assert!(segment_on_track("x".to_string(), &track, 0.0, 5000.0).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use super::distance::haversine_distance_points;
+use super::IndexedTrack;
+use crate::models::GpsPoint;
+
+/// Starts (and finishes) further apart than this are different routes
+pub const SAME_ROUTE_ENDPOINT_M: f64 = 100.0;
+/// Largest difference in length, as a fraction of the longer run
+pub const SAME_ROUTE_DISTANCE_RATIO: f64 = 0.1;
+/// Largest Fréchet distance between two runs on the same route
+pub const SAME_ROUTE_MAX_FRECHET_M: f64 = 75.0;
+
+/// Tracks are resampled at this spacing, or wider for long runs so the
+/// comparison stays fast
+const MIN_SAMPLE_SPACING_M: f64 = 25.0;
+const MAX_SAMPLES: f64 = 400.0;
+
+/// Discrete Fréchet distance between two paths in meters: how far apart
+/// two walkers have to be at worst when each moves forward along its own
+/// path. Unlike Hausdorff distance it respects direction, so a loop run
+/// the other way round is a different route.
+pub fn discrete_frechet(a: &[GpsPoint], b: &[GpsPoint]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return f64::INFINITY;
+    }
+
+    // Rolling rows of the coupling table
+    let mut previous = vec![0.0_f64; b.len()];
+    let mut current = vec![0.0; b.len()];
+    for (i, pa) in a.iter().enumerate() {
+        for (j, pb) in b.iter().enumerate() {
+            let d = haversine_distance_points(pa, pb);
+            let reach = match (i, j) {
+                (0, 0) => 0.0,
+                (0, _) => current[j - 1],
+                (_, 0) => previous[0],
+                _ => previous[j].min(previous[j - 1]).min(current[j - 1]),
+            };
+            current[j] = d.max(reach);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len() - 1]
+}
+
+/// A run's path, simplified to evenly spaced samples for comparison
+#[derive(Debug, Clone)]
+pub struct RouteShape {
+    distance_m: f64,
+    samples: Vec<GpsPoint>,
+}
+
+impl RouteShape {
+    /// None for tracks too short to compare
+    pub fn new(points: &[GpsPoint]) -> Option<Self> {
+        let track = IndexedTrack::new(points.to_vec());
+        let distance_m = track.total_distance();
+        if points.len() < 2 || distance_m <= 0.0 {
+            return None;
+        }
+
+        let spacing = (distance_m / MAX_SAMPLES).max(MIN_SAMPLE_SPACING_M);
+        let steps = (distance_m / spacing).ceil() as usize;
+        let samples = (0..=steps)
+            .filter_map(|i| track.position_at_distance((i as f64 * spacing).min(distance_m)))
+            .collect();
+
+        Some(Self {
+            distance_m,
+            samples,
+        })
+    }
+
+    pub fn distance_m(&self) -> f64 {
+        self.distance_m
+    }
+
+    /// Fréchet distance to `other` if both are on the same route: starts
+    /// and finishes close together, similar length and a close path.
+    /// The cheap checks run first so most runs are ruled out quickly.
+    pub fn same_route(&self, other: &RouteShape) -> Option<f64> {
+        let longer = self.distance_m.max(other.distance_m);
+        if (self.distance_m - other.distance_m).abs() > longer * SAME_ROUTE_DISTANCE_RATIO {
+            return None;
+        }
+
+        let ends_close = |a: Option<&GpsPoint>, b: Option<&GpsPoint>| {
+            a.zip(b)
+                .is_some_and(|(a, b)| haversine_distance_points(a, b) <= SAME_ROUTE_ENDPOINT_M)
+        };
+        if !ends_close(self.samples.first(), other.samples.first())
+            || !ends_close(self.samples.last(), other.samples.last())
+        {
+            return None;
+        }
+
+        let frechet = discrete_frechet(&self.samples, &other.samples);
+        (frechet <= SAME_ROUTE_MAX_FRECHET_M).then_some(frechet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn path(coords: &[(f64, f64)]) -> Vec<GpsPoint> {
+        let now = Utc::now();
+        coords
+            .iter()
+            .map(|&(lat, lon)| GpsPoint::new(lat, lon, now))
+            .collect()
+    }
+
+    /// A 1 km square loop, shifted east by `offset_deg`
+    fn square(offset_deg: f64) -> Vec<GpsPoint> {
+        let corners = [
+            (51.500, -0.1000),
+            (51.50225, -0.1000),
+            (51.50225, -0.0964),
+            (51.500, -0.0964),
+            (51.500, -0.1000),
+        ];
+        path(
+            &corners
+                .iter()
+                .map(|&(lat, lon)| (lat, lon + offset_deg))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_discrete_frechet() {
+        let a = path(&[(51.5, -0.1), (51.501, -0.1), (51.502, -0.1)]);
+        assert_eq!(discrete_frechet(&a, &a), 0.0);
+
+        // The same path the other way round is far apart at the ends
+        let reversed: Vec<GpsPoint> = a.iter().rev().cloned().collect();
+        assert!(discrete_frechet(&a, &reversed) > 200.0);
+        assert_eq!(discrete_frechet(&a, &[]), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_same_route() {
+        let loop_a = RouteShape::new(&square(0.0)).unwrap();
+        // About 14 m east: GPS drift on the same loop
+        let loop_b = RouteShape::new(&square(0.0002)).unwrap();
+        let frechet = loop_a.same_route(&loop_b).unwrap();
+        assert!(frechet > 5.0 && frechet < 30.0, "{frechet}");
+
+        // The same loop run anticlockwise starts and ends in the same place
+        // but isn't the same route
+        let reversed: Vec<GpsPoint> = square(0.0).into_iter().rev().collect();
+        assert!(loop_a
+            .same_route(&RouteShape::new(&reversed).unwrap())
+            .is_none());
+
+        // Same start and finish, but an out-and-back north instead
+        let out_and_back = path(&[(51.500, -0.1), (51.5045, -0.1), (51.500, -0.1)]);
+        assert!(loop_a
+            .same_route(&RouteShape::new(&out_and_back).unwrap())
+            .is_none());
+
+        assert!(RouteShape::new(&path(&[(51.5, -0.1)])).is_none());
+    }
+}
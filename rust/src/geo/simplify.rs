@@ -0,0 +1,106 @@
+use super::distance::haversine_distance_points;
+use crate::models::GpsPoint;
+
+/// Tolerance for turning a recorded run into a route: removes GPS jitter
+/// but keeps every real turn
+pub const ROUTE_TOLERANCE_M: f64 = 5.0;
+
+/// Meters per degree of latitude
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Distance from `point` to the segment `a`-`b` in meters, on a flat
+/// projection around `a` (fine over the length of one segment)
+fn distance_to_segment(point: &GpsPoint, a: &GpsPoint, b: &GpsPoint) -> f64 {
+    let scale_x = METERS_PER_DEGREE * a.lat.to_radians().cos();
+    let project = |p: &GpsPoint| {
+        (
+            (p.lon - a.lon) * scale_x,
+            (p.lat - a.lat) * METERS_PER_DEGREE,
+        )
+    };
+    let (px, py) = project(point);
+    let (bx, by) = project(b);
+
+    let length_sq = bx * bx + by * by;
+    if length_sq == 0.0 {
+        return haversine_distance_points(point, a);
+    }
+    let t = ((px * bx + py * by) / length_sq).clamp(0.0, 1.0);
+    ((px - t * bx).powi(2) + (py - t * by).powi(2)).sqrt()
+}
+
+/// Simplify a track with Douglas-Peucker: drop points that are within
+/// `tolerance_m` of the line through the points kept around them. The
+/// first and last points are always kept.
+pub fn simplify(points: &[GpsPoint], tolerance_m: f64) -> Vec<GpsPoint> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    // Ranges still to check, as (first, last) indices
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let farthest = (first + 1..last)
+            .map(|i| {
+                (
+                    i,
+                    distance_to_segment(&points[i], &points[first], &points[last]),
+                )
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance_m {
+                keep[index] = true;
+                stack.push((first, index));
+                stack.push((index, last));
+            }
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(p, _)| p.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_simplify() {
+        let now = Utc::now();
+        // Straight north with a little GPS wobble, then a right-angle turn east
+        let points: Vec<GpsPoint> = [
+            (51.500, -0.1000),
+            (51.501, -0.10001),
+            (51.502, -0.09999),
+            (51.503, -0.1000),
+            (51.503, -0.0990),
+            (51.503, -0.0980),
+        ]
+        .iter()
+        .map(|&(lat, lon)| GpsPoint::new(lat, lon, now))
+        .collect();
+
+        let simplified = simplify(&points, 5.0);
+        let coords: Vec<(f64, f64)> = simplified.iter().map(|p| (p.lat, p.lon)).collect();
+        assert_eq!(
+            coords,
+            vec![(51.500, -0.1000), (51.503, -0.1000), (51.503, -0.0980)]
+        );
+
+        // A tight tolerance keeps the wobble, but not the point exactly on
+        // the line east
+        assert_eq!(simplify(&points, 0.1).len(), 5);
+        assert_eq!(simplify(&points[..2], 5.0).len(), 2);
+    }
+}
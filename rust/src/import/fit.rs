@@ -0,0 +1,347 @@
+use chrono::{DateTime, Duration, Utc};
+
+use super::ImportedTrack;
+use crate::models::GpsPoint;
+
+/// FIT timestamps count seconds from 1989-12-31T00:00:00Z
+const FIT_EPOCH_UNIX_SECS: i64 = 631_065_600;
+
+/// Global message number of `record` messages (one per track point)
+const RECORD_MESSAGE: u16 = 20;
+
+const FIELD_LATITUDE: u8 = 0;
+const FIELD_LONGITUDE: u8 = 1;
+const FIELD_ALTITUDE: u8 = 2;
+const FIELD_SPEED: u8 = 6;
+const FIELD_ENHANCED_SPEED: u8 = 73;
+const FIELD_ENHANCED_ALTITUDE: u8 = 78;
+const FIELD_TIMESTAMP: u8 = 253;
+
+#[derive(Debug, Clone, Copy)]
+struct FieldDefinition {
+    number: u8,
+    size: usize,
+}
+
+#[derive(Debug, Clone)]
+struct MessageDefinition {
+    global_number: u16,
+    big_endian: bool,
+    fields: Vec<FieldDefinition>,
+    /// Total size of developer fields, which are skipped
+    developer_size: usize,
+}
+
+/// Values read from one record message
+#[derive(Debug, Default)]
+struct Record {
+    timestamp: Option<u32>,
+    lat: Option<i32>,
+    lon: Option<i32>,
+    altitude: Option<f64>,
+    speed: Option<f64>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self.pos + len;
+        anyhow::ensure!(end <= self.bytes.len(), "The FIT file is truncated");
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+fn read_uint(bytes: &[u8], big_endian: bool) -> u64 {
+    let fold = |acc: u64, b: &u8| (acc << 8) | *b as u64;
+    if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    }
+}
+
+/// Read the track points of a FIT activity. Only `record` messages are used;
+/// everything else is skipped using its definition.
+pub fn parse(bytes: &[u8]) -> anyhow::Result<ImportedTrack> {
+    anyhow::ensure!(
+        bytes.len() >= 12 && &bytes[8..12] == b".FIT",
+        "Not a FIT file"
+    );
+    let header_size = bytes[0] as usize;
+    anyhow::ensure!(header_size >= 12, "Invalid FIT header");
+    let data_size = read_uint(&bytes[4..8], false) as usize;
+    let end = (header_size + data_size).min(bytes.len());
+
+    let mut reader = Reader {
+        bytes: &bytes[..end],
+        pos: header_size,
+    };
+    let mut definitions: [Option<MessageDefinition>; 16] = Default::default();
+    let mut last_timestamp: Option<u32> = None;
+    let mut points = Vec::new();
+
+    while reader.pos < end {
+        let header = reader.u8()?;
+
+        if header & 0x80 != 0 {
+            // Compressed timestamp header: a data message whose time is a
+            // 5-bit offset from the last full timestamp
+            let local = ((header >> 5) & 0x03) as usize;
+            let offset = (header & 0x1F) as u32;
+            // A rollover past the end of the u32 range has no valid time;
+            // the record is dropped rather than wrapped back to 1989
+            let timestamp = last_timestamp.and_then(|last| {
+                let rolled = (last & !0x1F) + offset;
+                if offset >= last & 0x1F {
+                    Some(rolled)
+                } else {
+                    rolled.checked_add(0x20)
+                }
+            });
+            let record = read_data(&mut reader, &definitions, local)?;
+            if let Some(mut record) = record {
+                record.timestamp = record.timestamp.or(timestamp);
+                last_timestamp = record.timestamp.or(last_timestamp);
+                points.extend(to_point(&record));
+            }
+            continue;
+        }
+
+        let local = (header & 0x0F) as usize;
+        if header & 0x40 != 0 {
+            let has_developer_fields = header & 0x20 != 0;
+            reader.take(1)?; // reserved
+            let big_endian = reader.u8()? == 1;
+            let global_number = read_uint(reader.take(2)?, big_endian) as u16;
+            let field_count = reader.u8()?;
+            let fields = (0..field_count)
+                .map(|_| {
+                    let field = reader.take(3)?;
+                    Ok(FieldDefinition {
+                        number: field[0],
+                        size: field[1] as usize,
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let mut developer_size = 0;
+            if has_developer_fields {
+                let count = reader.u8()?;
+                for _ in 0..count {
+                    developer_size += reader.take(3)?[1] as usize;
+                }
+            }
+            definitions[local] = Some(MessageDefinition {
+                global_number,
+                big_endian,
+                fields,
+                developer_size,
+            });
+        } else if let Some(record) = read_data(&mut reader, &definitions, local)? {
+            last_timestamp = record.timestamp.or(last_timestamp);
+            points.extend(to_point(&record));
+        }
+    }
+
+    Ok(ImportedTrack { name: None, points })
+}
+
+/// Read a data message, returning its values if it's a `record`
+fn read_data(
+    reader: &mut Reader,
+    definitions: &[Option<MessageDefinition>; 16],
+    local: usize,
+) -> anyhow::Result<Option<Record>> {
+    let definition = definitions[local]
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("FIT data message without a definition"))?;
+
+    let mut record = Record::default();
+    for field in &definition.fields {
+        let bytes = reader.take(field.size)?;
+        if definition.global_number != RECORD_MESSAGE {
+            continue;
+        }
+        let value = read_uint(bytes, definition.big_endian);
+        // All bits set marks a missing value for unsigned fields; for sint32
+        // it's 0x7FFFFFFF
+        let valid = value != u64::MAX >> (64 - 8 * field.size.clamp(1, 8));
+        match (field.number, field.size) {
+            (FIELD_TIMESTAMP, 4) if valid => record.timestamp = Some(value as u32),
+            (FIELD_LATITUDE, 4) if value != 0x7FFF_FFFF => record.lat = Some(value as u32 as i32),
+            (FIELD_LONGITUDE, 4) if value != 0x7FFF_FFFF => record.lon = Some(value as u32 as i32),
+            // The enhanced fields win when both are present
+            (FIELD_ENHANCED_ALTITUDE, 4) if valid => {
+                record.altitude = Some(value as f64 / 5.0 - 500.0)
+            }
+            (FIELD_ALTITUDE, 2) if valid && record.altitude.is_none() => {
+                record.altitude = Some(value as f64 / 5.0 - 500.0)
+            }
+            (FIELD_ENHANCED_SPEED, 4) if valid => record.speed = Some(value as f64 / 1000.0),
+            (FIELD_SPEED, 2) if valid && record.speed.is_none() => {
+                record.speed = Some(value as f64 / 1000.0)
+            }
+            _ => {}
+        }
+    }
+    reader.take(definition.developer_size)?;
+
+    Ok((definition.global_number == RECORD_MESSAGE).then_some(record))
+}
+
+fn semicircles_to_degrees(semicircles: i32) -> f64 {
+    semicircles as f64 * (180.0 / 2f64.powi(31))
+}
+
+fn fit_time(timestamp: u32) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(FIT_EPOCH_UNIX_SECS, 0)
+        .map(|epoch| epoch + Duration::seconds(timestamp as i64))
+}
+
+fn to_point(record: &Record) -> Option<GpsPoint> {
+    let timestamp = fit_time(record.timestamp?)?;
+    Some(GpsPoint {
+        altitude: record.altitude,
+        speed: record.speed,
+        ..GpsPoint::new(
+            semicircles_to_degrees(record.lat?),
+            semicircles_to_degrees(record.lon?),
+            timestamp,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn degrees_to_semicircles(degrees: f64) -> i32 {
+        (degrees * 2f64.powi(31) / 180.0).round() as i32
+    }
+
+    /// A FIT file with a record definition (timestamp, lat, lon, altitude),
+    /// an unrelated message, and the given records
+    fn fit_file(records: &[(u32, i32, i32, u16)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        // Definition, local 0: record with timestamp, lat, lon, altitude
+        data.extend([0x40, 0, 0]);
+        data.extend(RECORD_MESSAGE.to_le_bytes());
+        data.extend([4, 253, 4, 0x86, 0, 4, 0x85, 1, 4, 0x85, 2, 2, 0x84]);
+        // Definition, local 1: file_id with a single byte field
+        data.extend([0x41, 0, 0, 0, 0, 1, 0, 1, 0]);
+        data.extend([0x01, 4]);
+
+        for &(timestamp, lat, lon, altitude) in records {
+            data.push(0x00);
+            data.extend(timestamp.to_le_bytes());
+            data.extend(lat.to_le_bytes());
+            data.extend(lon.to_le_bytes());
+            data.extend(altitude.to_le_bytes());
+        }
+
+        let mut file = vec![14, 0x20, 0, 0];
+        file.extend((data.len() as u32).to_le_bytes());
+        file.extend(b".FIT");
+        file.extend([0, 0]);
+        file.extend(data);
+        file.extend([0, 0]); // CRC, not checked
+        file
+    }
+
+    #[test]
+    fn test_parse_fit_records() {
+        let lat = degrees_to_semicircles(51.5);
+        let lon = degrees_to_semicircles(-0.1);
+        let base = 1_000_000_000;
+        let bytes = fit_file(&[
+            (base, lat, lon, (25.0 + 500.0) as u16 * 5),
+            (base + 5, lat + 10_000, lon, 0xFFFF),
+            // No fix yet: skipped
+            (base + 6, 0x7FFF_FFFF, 0x7FFF_FFFF, 0xFFFF),
+        ]);
+
+        let track = parse(&bytes).unwrap();
+        assert_eq!(track.points.len(), 2);
+        assert!((track.points[0].lat - 51.5).abs() < 1e-6);
+        assert!((track.points[0].lon + 0.1).abs() < 1e-6);
+        assert_eq!(track.points[0].altitude, Some(25.0));
+        assert_eq!(track.points[1].altitude, None);
+        assert_eq!(
+            track.points[0].timestamp.timestamp(),
+            FIT_EPOCH_UNIX_SECS + base as i64
+        );
+        assert_eq!(
+            (track.points[1].timestamp - track.points[0].timestamp).num_seconds(),
+            5
+        );
+    }
+
+    /// A FIT file with one full record at `base` followed by a record with
+    /// a compressed timestamp header carrying `offset`
+    fn compressed_fit_file(base: u32, offset: u8, lat: i32, lon: i32) -> Vec<u8> {
+        let mut bytes = fit_file(&[(base, lat, lon, 0xFFFF)]);
+        let crc = bytes.split_off(bytes.len() - 2);
+
+        // Definition, local 2: record without a timestamp field
+        let mut extra = vec![0x42, 0, 0];
+        extra.extend(RECORD_MESSAGE.to_le_bytes());
+        extra.extend([2, 0, 4, 0x85, 1, 4, 0x85]);
+        // Compressed header: local 2
+        extra.push(0x80 | (2 << 5) | offset);
+        extra.extend((lat + 10_000).to_le_bytes());
+        extra.extend(lon.to_le_bytes());
+
+        bytes.extend(&extra);
+        let data_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) + extra.len() as u32;
+        bytes[4..8].copy_from_slice(&data_size.to_le_bytes());
+        bytes.extend(crc);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_fit_compressed_timestamps() {
+        let lat = degrees_to_semicircles(51.5);
+        let lon = degrees_to_semicircles(-0.1);
+        // base ends in 0x1E, so an offset of 2 rolls over
+        let bytes = compressed_fit_file(0x3C0_001E, 2, lat, lon);
+
+        let track = parse(&bytes).unwrap();
+        assert_eq!(track.points.len(), 2);
+        assert_eq!(
+            (track.points[1].timestamp - track.points[0].timestamp).num_seconds(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_parse_fit_compressed_timestamp_past_u32_range() {
+        let lat = degrees_to_semicircles(51.5);
+        let lon = degrees_to_semicircles(-0.1);
+        // Rolling over from the top of the range has nowhere to go
+        let bytes = compressed_fit_file(u32::MAX - 1, 2, lat, lon);
+
+        let track = parse(&bytes).unwrap();
+        assert_eq!(track.points.len(), 1);
+        assert_eq!(
+            track.points[0].timestamp.timestamp(),
+            FIT_EPOCH_UNIX_SECS + (u32::MAX - 1) as i64
+        );
+    }
+
+    #[test]
+    fn test_parse_fit_rejects_bad_input() {
+        assert!(parse(b"<gpx></gpx>").is_err());
+        let mut truncated = fit_file(&[(1, 0, 0, 0)]);
+        truncated.truncate(truncated.len() - 6);
+        assert!(parse(&truncated).is_err());
+    }
+}
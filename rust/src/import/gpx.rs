@@ -0,0 +1,143 @@
+use roxmltree::{Document, Node};
+
+use super::{parse_time, ImportedRoute, ImportedTrack};
+use crate::models::{GpsPoint, RoutePoint};
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.tag_name().name() == name)
+        .and_then(|c| c.text())
+}
+
+/// Read the track points (or, failing that, route points) of a GPX file
+pub fn parse(text: &str) -> anyhow::Result<ImportedTrack> {
+    let doc = Document::parse(text)?;
+    let root = doc.root_element();
+    anyhow::ensure!(root.tag_name().name() == "gpx", "Not a GPX file");
+
+    let name = root
+        .descendants()
+        .find(|n| n.tag_name().name() == "trk")
+        .and_then(|trk| child_text(trk, "name"))
+        .or_else(|| {
+            root.children()
+                .find(|n| n.tag_name().name() == "metadata")
+                .and_then(|m| child_text(m, "name"))
+        })
+        .map(|s| s.trim().to_string());
+
+    let mut points = points_named(root, "trkpt");
+    if points.is_empty() {
+        points = points_named(root, "rtept");
+    }
+
+    Ok(ImportedTrack { name, points })
+}
+
+fn points_named(root: Node, tag: &str) -> Vec<GpsPoint> {
+    root.descendants()
+        .filter(|n| n.tag_name().name() == tag)
+        .filter_map(|n| {
+            let lat = n.attribute("lat")?.trim().parse().ok()?;
+            let lon = n.attribute("lon")?.trim().parse().ok()?;
+            let timestamp = parse_time(child_text(n, "time")?)?;
+            let altitude = child_text(n, "ele").and_then(|e| e.trim().parse().ok());
+
+            Some(GpsPoint {
+                altitude,
+                ..GpsPoint::new(lat, lon, timestamp)
+            })
+        })
+        .collect()
+}
+
+/// Read the path of a GPX route (or, failing that, track). Points don't
+/// need times, since routes are planned rather than recorded.
+pub fn parse_route(text: &str) -> anyhow::Result<ImportedRoute> {
+    let doc = Document::parse(text)?;
+    let root = doc.root_element();
+    anyhow::ensure!(root.tag_name().name() == "gpx", "Not a GPX file");
+
+    let name = ["rte", "trk", "metadata"]
+        .iter()
+        .filter_map(|tag| root.children().find(|n| n.tag_name().name() == *tag))
+        .find_map(|n| child_text(n, "name"))
+        .map(|s| s.trim().to_string());
+
+    let mut points = route_points_named(root, "rtept");
+    if points.is_empty() {
+        points = route_points_named(root, "trkpt");
+    }
+
+    Ok(ImportedRoute { name, points })
+}
+
+fn route_points_named(root: Node, tag: &str) -> Vec<RoutePoint> {
+    root.descendants()
+        .filter(|n| n.tag_name().name() == tag)
+        .filter_map(|n| {
+            Some(RoutePoint {
+                lat: n.attribute("lat")?.trim().parse().ok()?,
+                lon: n.attribute("lon")?.trim().parse().ok()?,
+                altitude: child_text(n, "ele").and_then(|e| e.trim().parse().ok()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gpx() {
+        let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <metadata><name>Saturday</name></metadata>
+  <trk>
+    <name>Parkrun</name>
+    <trkseg>
+      <trkpt lat="51.5000" lon="-0.1000"><ele>12.5</ele><time>2024-05-04T09:00:00Z</time></trkpt>
+      <trkpt lat="51.5010" lon="-0.1000"><time>2024-05-04T09:00:30Z</time></trkpt>
+      <trkpt lat="51.5020" lon="-0.1000"></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+        let track = parse(gpx).unwrap();
+        assert_eq!(track.name.as_deref(), Some("Parkrun"));
+        // The untimed point is skipped
+        assert_eq!(track.points.len(), 2);
+        assert_eq!(track.points[0].altitude, Some(12.5));
+        assert_eq!(track.points[1].altitude, None);
+        assert_eq!(
+            (track.points[1].timestamp - track.points[0].timestamp).num_seconds(),
+            30
+        );
+    }
+
+    #[test]
+    fn test_parse_gpx_rejects_other_xml() {
+        assert!(parse("<kml></kml>").is_err());
+        assert!(parse("not xml").is_err());
+    }
+
+    #[test]
+    fn test_parse_gpx_route() {
+        let gpx = r#"<gpx version="1.1" xmlns="http://www.topografix.com/GPX/1/1">
+  <rte>
+    <name>Canal loop</name>
+    <rtept lat="51.5000" lon="-0.1000"><ele>8</ele></rtept>
+    <rtept lat="51.5050" lon="-0.1020"/>
+    <rtept lat="51.5100" lon="-0.1000"><ele>14</ele></rtept>
+  </rte>
+</gpx>"#;
+
+        let route = parse_route(gpx).unwrap();
+        assert_eq!(route.name.as_deref(), Some("Canal loop"));
+        assert_eq!(route.points.len(), 3);
+        assert_eq!(route.points[0].altitude, Some(8.0));
+        assert_eq!(route.points[1].altitude, None);
+        assert_eq!(route.points[2].lat, 51.51);
+    }
+}
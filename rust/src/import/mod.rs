@@ -0,0 +1,139 @@
+//! Reading GPS tracks recorded by other apps and devices
+
+pub mod fit;
+pub mod gpx;
+pub mod tcx;
+
+use crate::models::{GpsPoint, RoutePoint};
+
+/// Track file formats that can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackFormat {
+    Gpx,
+    Tcx,
+    Fit,
+}
+
+impl TrackFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrackFormat::Gpx => "gpx",
+            TrackFormat::Tcx => "tcx",
+            TrackFormat::Fit => "fit",
+        }
+    }
+
+    /// Work out the format from the file extension, or failing that the contents
+    pub fn detect(file_name: &str, bytes: &[u8]) -> Option<Self> {
+        let extension = file_name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("gpx") => return Some(TrackFormat::Gpx),
+            Some("tcx") => return Some(TrackFormat::Tcx),
+            Some("fit") => return Some(TrackFormat::Fit),
+            _ => {}
+        }
+
+        if bytes.get(8..12) == Some(b".FIT") {
+            return Some(TrackFormat::Fit);
+        }
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+        if head.contains("<gpx") {
+            Some(TrackFormat::Gpx)
+        } else if head.contains("<TrainingCenterDatabase") {
+            Some(TrackFormat::Tcx)
+        } else {
+            None
+        }
+    }
+}
+
+/// A track read from a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedTrack {
+    /// Track or activity name from the file, if it has one
+    pub name: Option<String>,
+    /// Points in time order
+    pub points: Vec<GpsPoint>,
+}
+
+/// Parse a track file. Points without a position or a time are skipped; a
+/// track needs at least two timed points to be raced.
+pub fn parse_track(bytes: &[u8], format: TrackFormat) -> anyhow::Result<ImportedTrack> {
+    let mut track = match format {
+        TrackFormat::Gpx => gpx::parse(xml_text(bytes)?)?,
+        TrackFormat::Tcx => tcx::parse(xml_text(bytes)?)?,
+        TrackFormat::Fit => fit::parse(bytes)?,
+    };
+
+    track.points.sort_by_key(|p| p.timestamp);
+    anyhow::ensure!(
+        track.points.len() >= 2,
+        "The file has no timed track points"
+    );
+    Ok(track)
+}
+
+/// A route read from a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedRoute {
+    /// Route or track name from the file, if it has one
+    pub name: Option<String>,
+    /// Points in path order
+    pub points: Vec<RoutePoint>,
+}
+
+/// Parse a GPX route file. Untimed points are fine, but there must be at
+/// least two of them.
+pub fn parse_gpx_route(bytes: &[u8]) -> anyhow::Result<ImportedRoute> {
+    let route = gpx::parse_route(xml_text(bytes)?)?;
+    anyhow::ensure!(route.points.len() >= 2, "The file has no route points");
+    Ok(route)
+}
+
+fn xml_text(bytes: &[u8]) -> anyhow::Result<&str> {
+    let text = std::str::from_utf8(bytes)?;
+    // Some exporters write a byte order mark
+    Ok(text.trim_start_matches('\u{feff}'))
+}
+
+/// Parse an XML timestamp such as "2024-05-04T09:00:00Z"
+fn parse_time(text: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(text.trim())
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            TrackFormat::detect("parkrun.GPX", b""),
+            Some(TrackFormat::Gpx)
+        );
+        assert_eq!(TrackFormat::detect("a.tcx", b""), Some(TrackFormat::Tcx));
+        assert_eq!(
+            TrackFormat::detect("upload", b"<?xml version=\"1.0\"?><gpx version=\"1.1\">"),
+            Some(TrackFormat::Gpx)
+        );
+        assert_eq!(
+            TrackFormat::detect("upload", b"<TrainingCenterDatabase>"),
+            Some(TrackFormat::Tcx)
+        );
+        assert_eq!(
+            TrackFormat::detect("upload", b"\x0e\x10\x00\x00\x00\x00\x00\x00.FIT"),
+            Some(TrackFormat::Fit)
+        );
+        assert_eq!(TrackFormat::detect("notes.txt", b"hello"), None);
+    }
+
+    #[test]
+    fn test_parse_track_needs_points() {
+        let gpx = r#"<gpx><trk><trkseg><trkpt lat="51.5" lon="-0.1"/></trkseg></trk></gpx>"#;
+        assert!(parse_track(gpx.as_bytes(), TrackFormat::Gpx).is_err());
+    }
+}
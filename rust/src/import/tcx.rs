@@ -0,0 +1,95 @@
+use roxmltree::{Document, Node};
+
+use super::{parse_time, ImportedTrack};
+use crate::models::GpsPoint;
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|c| c.tag_name().name() == name)
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|c| c.text())
+}
+
+/// Read the trackpoints of a TCX activity or course
+pub fn parse(text: &str) -> anyhow::Result<ImportedTrack> {
+    let doc = Document::parse(text)?;
+    let root = doc.root_element();
+    anyhow::ensure!(
+        root.tag_name().name() == "TrainingCenterDatabase",
+        "Not a TCX file"
+    );
+
+    // Courses have a name; activities only have their start time as an ID
+    let name = root
+        .descendants()
+        .find(|n| n.tag_name().name() == "Course")
+        .and_then(|c| child_text(c, "Name"))
+        .map(|s| s.trim().to_string());
+
+    let points = root
+        .descendants()
+        .filter(|n| n.tag_name().name() == "Trackpoint")
+        .filter_map(|n| {
+            let position = child(n, "Position")?;
+            let lat = child_text(position, "LatitudeDegrees")?
+                .trim()
+                .parse()
+                .ok()?;
+            let lon = child_text(position, "LongitudeDegrees")?
+                .trim()
+                .parse()
+                .ok()?;
+            let timestamp = parse_time(child_text(n, "Time")?)?;
+            let altitude = child_text(n, "AltitudeMeters").and_then(|a| a.trim().parse().ok());
+
+            Some(GpsPoint {
+                altitude,
+                ..GpsPoint::new(lat, lon, timestamp)
+            })
+        })
+        .collect();
+
+    Ok(ImportedTrack { name, points })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tcx() {
+        let tcx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+  <Activities>
+    <Activity Sport="Running">
+      <Id>2024-05-04T09:00:00Z</Id>
+      <Lap StartTime="2024-05-04T09:00:00Z">
+        <Track>
+          <Trackpoint>
+            <Time>2024-05-04T09:00:00Z</Time>
+            <Position><LatitudeDegrees>51.5</LatitudeDegrees><LongitudeDegrees>-0.1</LongitudeDegrees></Position>
+            <AltitudeMeters>20.0</AltitudeMeters>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-05-04T09:00:05Z</Time>
+            <HeartRateBpm><Value>150</Value></HeartRateBpm>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-05-04T09:00:10Z</Time>
+            <Position><LatitudeDegrees>51.501</LatitudeDegrees><LongitudeDegrees>-0.1</LongitudeDegrees></Position>
+          </Trackpoint>
+        </Track>
+      </Lap>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>"#;
+
+        let track = parse(tcx).unwrap();
+        assert_eq!(track.name, None);
+        // The trackpoint without a position is skipped
+        assert_eq!(track.points.len(), 2);
+        assert_eq!(track.points[0].altitude, Some(20.0));
+        assert_eq!(track.points[1].lat, 51.501);
+    }
+}
@@ -1,6 +1,10 @@
 pub mod api;
+pub mod banshee;
 pub mod db;
+pub mod export;
 pub mod geo;
+pub mod import;
 pub mod models;
+pub mod training;
 
 mod frb_generated;
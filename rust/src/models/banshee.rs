@@ -1,14 +1,212 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// One leg of a pace plan
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PaceSegment {
+    /// Distance from the start at which this leg ends, in meters
+    pub until_distance_m: f64,
+    /// Pace for this leg in seconds per kilometer
+    pub pace_sec_per_km: f64,
+}
+
+/// How an AI pacer's pace varies over the run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PacePlan {
+    /// Constant pace per leg. Past the last leg the pacer keeps its last pace.
+    Segments(Vec<PaceSegment>),
+    /// Pace changes linearly with distance from start to finish pace
+    /// (e.g. a negative-split long run), then holds the finish pace
+    Progression {
+        start_pace_sec_per_km: f64,
+        finish_pace_sec_per_km: f64,
+        distance_m: f64,
+    },
+}
+
+impl PacePlan {
+    /// Check the plan is usable: positive paces and legs in increasing distance order
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            PacePlan::Segments(segments) => {
+                anyhow::ensure!(!segments.is_empty(), "Pace plan has no segments");
+                let mut prev = 0.0;
+                for segment in segments {
+                    anyhow::ensure!(segment.pace_sec_per_km > 0.0, "Invalid pace");
+                    anyhow::ensure!(
+                        segment.until_distance_m > prev,
+                        "Pace plan segments must be in increasing distance order"
+                    );
+                    prev = segment.until_distance_m;
+                }
+            }
+            PacePlan::Progression {
+                start_pace_sec_per_km,
+                finish_pace_sec_per_km,
+                distance_m,
+            } => {
+                anyhow::ensure!(
+                    *start_pace_sec_per_km > 0.0 && *finish_pace_sec_per_km > 0.0,
+                    "Invalid pace"
+                );
+                anyhow::ensure!(*distance_m > 0.0, "Progression distance must be positive");
+            }
+        }
+        Ok(())
+    }
+
+    /// Distance the plan covers explicitly (end of the last leg or progression)
+    pub fn planned_distance(&self) -> f64 {
+        match self {
+            PacePlan::Segments(segments) => segments.last().map_or(0.0, |s| s.until_distance_m),
+            PacePlan::Progression { distance_m, .. } => *distance_m,
+        }
+    }
+
+    /// Average pace over the planned distance in seconds per kilometer
+    pub fn average_pace(&self) -> f64 {
+        let distance = self.planned_distance();
+        if distance <= 0.0 {
+            return 0.0;
+        }
+        self.time_at_distance(distance) / distance * 1000.0
+    }
+
+    /// Seconds needed to cover `distance_m` following the plan
+    pub fn time_at_distance(&self, distance_m: f64) -> f64 {
+        let distance_m = distance_m.max(0.0);
+
+        match self {
+            PacePlan::Segments(segments) => {
+                let mut time = 0.0;
+                let mut leg_start = 0.0;
+                let mut last_pace = 0.0;
+                for segment in segments {
+                    let leg_end = segment.until_distance_m.min(distance_m);
+                    if leg_end > leg_start {
+                        time += (leg_end - leg_start) * segment.pace_sec_per_km / 1000.0;
+                    }
+                    leg_start = segment.until_distance_m;
+                    last_pace = segment.pace_sec_per_km;
+                    if distance_m <= segment.until_distance_m {
+                        return time;
+                    }
+                }
+                time + (distance_m - leg_start) * last_pace / 1000.0
+            }
+            PacePlan::Progression {
+                start_pace_sec_per_km: p0,
+                finish_pace_sec_per_km: p1,
+                distance_m: total,
+            } => {
+                // Integral of pace(x) = p0 + (p1 - p0) * x / total over [0, d]
+                let d = distance_m.min(*total);
+                let time = (p0 * d + (p1 - p0) * d * d / (2.0 * total)) / 1000.0;
+                time + (distance_m - d) * p1 / 1000.0
+            }
+        }
+    }
+
+    /// Meters covered after `elapsed_sec` following the plan
+    pub fn distance_at_time(&self, elapsed_sec: f64) -> f64 {
+        let elapsed_sec = elapsed_sec.max(0.0);
+
+        match self {
+            PacePlan::Segments(segments) => {
+                let mut time = 0.0;
+                let mut leg_start = 0.0;
+                let mut last_pace = segments.first().map_or(0.0, |s| s.pace_sec_per_km);
+                for segment in segments {
+                    let leg_time =
+                        (segment.until_distance_m - leg_start) * segment.pace_sec_per_km / 1000.0;
+                    if time + leg_time >= elapsed_sec {
+                        return leg_start + (elapsed_sec - time) * 1000.0 / segment.pace_sec_per_km;
+                    }
+                    time += leg_time;
+                    leg_start = segment.until_distance_m;
+                    last_pace = segment.pace_sec_per_km;
+                }
+                if last_pace <= 0.0 {
+                    return leg_start;
+                }
+                leg_start + (elapsed_sec - time) * 1000.0 / last_pace
+            }
+            PacePlan::Progression {
+                start_pace_sec_per_km: p0,
+                finish_pace_sec_per_km: p1,
+                distance_m: total,
+            } => {
+                let progression_time = self.time_at_distance(*total);
+                if elapsed_sec >= progression_time {
+                    return total + (elapsed_sec - progression_time) * 1000.0 / p1;
+                }
+
+                // Solve a*d^2 + p0*d - 1000*t = 0 for d
+                let a = (p1 - p0) / (2.0 * total);
+                let target = 1000.0 * elapsed_sec;
+                if a.abs() < 1e-12 {
+                    target / p0
+                } else {
+                    (-p0 + (p0 * p0 + 4.0 * a * target).sqrt()) / (2.0 * a)
+                }
+            }
+        }
+    }
+}
+
+/// Handicap applied when replaying a recorded run, e.g. "beat last week by
+/// 1%" is a speed factor of 1.01
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ReplayHandicap {
+    /// How long after the runner the banshee sets off, in milliseconds
+    /// (negative = it starts that much earlier)
+    pub start_offset_ms: i64,
+    /// Replay speed relative to the recording (1.02 = 2% faster)
+    pub speed_factor: f64,
+}
+
+impl Default for ReplayHandicap {
+    fn default() -> Self {
+        Self {
+            start_offset_ms: 0,
+            speed_factor: 1.0,
+        }
+    }
+}
+
+impl ReplayHandicap {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.speed_factor.is_finite() && self.speed_factor > 0.0,
+            "Invalid speed factor"
+        );
+        Ok(())
+    }
+
+    /// True if the replay matches the recording exactly
+    pub fn is_none(&self) -> bool {
+        *self == Self::default()
+    }
+}
 
 /// Type of banshee/pacer
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum BansheeType {
     /// Banshee based on a previous recorded run
-    RecordedRun { run_id: String },
+    RecordedRun {
+        run_id: String,
+        /// Time and speed handicap for the replay
+        #[serde(default)]
+        handicap: ReplayHandicap,
+    },
     /// AI-generated pacer with target pace
     AiPacer {
-        /// Target pace in seconds per kilometer
+        /// Target pace in seconds per kilometer (the average pace for planned pacers)
         target_pace_sec_per_km: f64,
+        /// Variable pace plan; None means a constant target pace
+        #[serde(default)]
+        pace_plan: Option<PacePlan>,
     },
 }
 
@@ -25,7 +223,18 @@ impl Banshee {
     /// Create a banshee from a recorded run
     pub fn from_run(run_id: String, name: String) -> Self {
         Self {
-            banshee_type: BansheeType::RecordedRun { run_id },
+            banshee_type: BansheeType::RecordedRun {
+                run_id,
+                handicap: ReplayHandicap::default(),
+            },
+            name,
+        }
+    }
+
+    /// Create a banshee that replays a recorded run with a handicap
+    pub fn from_run_with_handicap(run_id: String, handicap: ReplayHandicap, name: String) -> Self {
+        Self {
+            banshee_type: BansheeType::RecordedRun { run_id, handicap },
             name,
         }
     }
@@ -36,6 +245,18 @@ impl Banshee {
         Self {
             banshee_type: BansheeType::AiPacer {
                 target_pace_sec_per_km,
+                pace_plan: None,
+            },
+            name,
+        }
+    }
+
+    /// Create an AI pacer that follows a pace plan
+    pub fn ai_pacer_with_plan(pace_plan: PacePlan, name: String) -> Self {
+        Self {
+            banshee_type: BansheeType::AiPacer {
+                target_pace_sec_per_km: pace_plan.average_pace(),
+                pace_plan: Some(pace_plan),
             },
             name,
         }
@@ -51,6 +272,7 @@ impl Banshee {
         match &self.banshee_type {
             BansheeType::AiPacer {
                 target_pace_sec_per_km,
+                ..
             } => Some(*target_pace_sec_per_km),
             BansheeType::RecordedRun { .. } => None,
         }
@@ -59,7 +281,7 @@ impl Banshee {
     /// Get run ID (for recorded run banshees)
     pub fn run_id(&self) -> Option<&str> {
         match &self.banshee_type {
-            BansheeType::RecordedRun { run_id } => Some(run_id),
+            BansheeType::RecordedRun { run_id, .. } => Some(run_id),
             BansheeType::AiPacer { .. } => None,
         }
     }
@@ -70,6 +292,34 @@ impl Banshee {
     }
 }
 
+/// A banshee configuration saved to the library, with its record against
+/// the runner
+#[derive(Debug, Clone)]
+pub struct SavedBanshee {
+    pub id: String,
+    pub banshee: Banshee,
+    pub created_at: DateTime<Utc>,
+    /// When the banshee was last raced
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Races the runner won against it
+    pub wins: u32,
+    /// Races the runner lost against it
+    pub losses: u32,
+}
+
+impl SavedBanshee {
+    pub fn new(banshee: Banshee) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            banshee,
+            created_at: Utc::now(),
+            last_used_at: None,
+            wins: 0,
+            losses: 0,
+        }
+    }
+}
+
 /// Represents the banshee's current state during a run
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BansheeState {
@@ -112,3 +362,74 @@ impl BansheeState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_plan() {
+        // 5:30/km for 2km, then 5:00/km to 5km
+        let plan = PacePlan::Segments(vec![
+            PaceSegment {
+                until_distance_m: 2000.0,
+                pace_sec_per_km: 330.0,
+            },
+            PaceSegment {
+                until_distance_m: 5000.0,
+                pace_sec_per_km: 300.0,
+            },
+        ]);
+        plan.validate().unwrap();
+
+        assert!((plan.time_at_distance(2000.0) - 660.0).abs() < 1e-9);
+        assert!((plan.time_at_distance(5000.0) - 1560.0).abs() < 1e-9);
+        assert!((plan.distance_at_time(330.0) - 1000.0).abs() < 1e-9);
+        assert!((plan.distance_at_time(960.0) - 3000.0).abs() < 1e-9);
+        // Holds the last pace past the end
+        assert!((plan.distance_at_time(1860.0) - 6000.0).abs() < 1e-9);
+        assert!((plan.average_pace() - 312.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_progression_plan() {
+        // 6:00/km down to 5:00/km over 10km
+        let plan = PacePlan::Progression {
+            start_pace_sec_per_km: 360.0,
+            finish_pace_sec_per_km: 300.0,
+            distance_m: 10_000.0,
+        };
+        plan.validate().unwrap();
+
+        // Average pace over the progression is the midpoint
+        assert!((plan.time_at_distance(10_000.0) - 3300.0).abs() < 1e-6);
+        for distance in [0.0, 1234.0, 5000.0, 9999.0, 12_000.0] {
+            let time = plan.time_at_distance(distance);
+            assert!((plan.distance_at_time(time) - distance).abs() < 1e-6);
+        }
+
+        let invalid = PacePlan::Segments(vec![]);
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_recorded_banshee_without_handicap_loads() {
+        // Saved before handicaps existed
+        let json = r#"{"banshee_type":{"RecordedRun":{"run_id":"run"}},"name":"Last week"}"#;
+        let banshee: Banshee = serde_json::from_str(json).unwrap();
+        assert_eq!(banshee.run_id(), Some("run"));
+        assert_eq!(
+            banshee.banshee_type,
+            BansheeType::RecordedRun {
+                run_id: "run".to_string(),
+                handicap: ReplayHandicap::default(),
+            }
+        );
+
+        let invalid = ReplayHandicap {
+            start_offset_ms: 0,
+            speed_factor: 0.0,
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::GpsPoint;
+
+/// Where one split of a composite ghost came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompositeSplit {
+    /// Split number (1-indexed)
+    pub number: i32,
+    /// Run whose time was the fastest over this split
+    pub run_id: String,
+    pub duration_ms: i64,
+}
+
+/// How a ghost track was made
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum GhostSource {
+    /// Stitched together from the fastest split per distance over several runs
+    Composite {
+        split_distance_m: f64,
+        splits: Vec<CompositeSplit>,
+    },
+    /// Imported from a track file recorded elsewhere
+    Imported {
+        /// "gpx", "tcx" or "fit"
+        format: String,
+        file_name: Option<String>,
+    },
+}
+
+/// A banshee track that isn't one of the runner's own runs. It can be raced
+/// like a recorded run: banshee lookups fall back to ghost tracks by ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GhostTrack {
+    pub id: String,
+    pub name: String,
+    pub source: GhostSource,
+    pub created_at: DateTime<Utc>,
+    pub distance_meters: f64,
+    pub duration_ms: i64,
+    pub points: Vec<GpsPoint>,
+}
+
+impl GhostTrack {
+    pub fn new(name: String, source: GhostSource, points: Vec<GpsPoint>) -> Self {
+        let distance_meters = crate::geo::total_distance(&points);
+        let duration_ms = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => (last.timestamp - first.timestamp).num_milliseconds(),
+            _ => 0,
+        };
+
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            source,
+            created_at: Utc::now(),
+            distance_meters,
+            duration_ms,
+            points,
+        }
+    }
+}
+
+/// A ghost track without its points, for listings
+#[derive(Debug, Clone, PartialEq)]
+pub struct GhostTrackSummary {
+    pub id: String,
+    pub name: String,
+    pub source: GhostSource,
+    pub created_at: DateTime<Utc>,
+    pub distance_meters: f64,
+    pub duration_ms: i64,
+}
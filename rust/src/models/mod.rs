@@ -1,7 +1,23 @@
 pub mod banshee;
+pub mod ghost;
 pub mod gps_point;
+pub mod plan;
+pub mod privacy;
+pub mod report;
+pub mod route;
 pub mod run;
+pub mod segment;
+pub mod workout;
 
-pub use banshee::{Banshee, BansheeState, BansheeType};
+pub use banshee::{
+    Banshee, BansheeState, BansheeType, PacePlan, PaceSegment, ReplayHandicap, SavedBanshee,
+};
+pub use ghost::{CompositeSplit, GhostSource, GhostTrack, GhostTrackSummary};
 pub use gps_point::GpsPoint;
-pub use run::{Run, RunSummary};
+pub use plan::{PaceZones, Phase, PlanDay, PlanSessionKind, RaceTime, TrainingPlan};
+pub use privacy::PrivacyZone;
+pub use report::{GapSample, LeadChange, RaceReport, SplitComparison};
+pub use route::{ElevationSample, Route, RoutePoint, RouteSource, RouteSummary};
+pub use run::{Pause, Run, RunMetadata, RunMetadataPatch, RunSummary, RunType, TrashedRun};
+pub use segment::{Gate, Segment, SegmentEffort};
+pub use workout::{Lap, StepKind, StepLength, Workout, WorkoutStep};
@@ -0,0 +1,180 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Workout;
+
+/// A time over a distance, e.g. a recent race or best effort
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RaceTime {
+    pub distance_m: f64,
+    pub duration_ms: i64,
+}
+
+impl RaceTime {
+    pub fn pace_sec_per_km(&self) -> f64 {
+        crate::geo::calculate_pace(self.distance_m, self.duration_ms)
+    }
+}
+
+/// Training paces in seconds per kilometer, slowest first
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PaceZones {
+    /// Easy and long runs
+    pub easy_sec_per_km: f64,
+    /// Marathon race pace
+    pub marathon_sec_per_km: f64,
+    /// Pace that can be held for about an hour (tempo runs)
+    pub threshold_sec_per_km: f64,
+    /// Roughly 5K race pace (long intervals)
+    pub interval_sec_per_km: f64,
+    /// Roughly mile race pace (short, fast repeats)
+    pub repetition_sec_per_km: f64,
+}
+
+/// Training block a week belongs to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Phase {
+    /// Aerobic volume, easy running only
+    Base,
+    /// Volume keeps rising and quality sessions start
+    Build,
+    /// Hardest, most race-specific weeks
+    Peak,
+    /// Volume drops so the runner arrives at the race fresh
+    Taper,
+}
+
+impl Phase {
+    /// Stable string form used for storage and FFI
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Base => "base",
+            Phase::Build => "build",
+            Phase::Peak => "peak",
+            Phase::Taper => "taper",
+        }
+    }
+
+    /// Parse the string form produced by `as_str`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "base" => Some(Phase::Base),
+            "build" => Some(Phase::Build),
+            "peak" => Some(Phase::Peak),
+            "taper" => Some(Phase::Taper),
+            _ => None,
+        }
+    }
+}
+
+/// What a plan day asks for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PlanSessionKind {
+    Rest,
+    Easy,
+    Long,
+    Workout,
+    Race,
+}
+
+impl PlanSessionKind {
+    /// Stable string form used for storage and FFI
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanSessionKind::Rest => "rest",
+            PlanSessionKind::Easy => "easy",
+            PlanSessionKind::Long => "long",
+            PlanSessionKind::Workout => "workout",
+            PlanSessionKind::Race => "race",
+        }
+    }
+
+    /// Parse the string form produced by `as_str`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "rest" => Some(PlanSessionKind::Rest),
+            "easy" => Some(PlanSessionKind::Easy),
+            "long" => Some(PlanSessionKind::Long),
+            "workout" => Some(PlanSessionKind::Workout),
+            "race" => Some(PlanSessionKind::Race),
+            _ => None,
+        }
+    }
+}
+
+/// One day of a training plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanDay {
+    pub date: NaiveDate,
+    /// Plan week (1-indexed)
+    pub week: u32,
+    pub phase: Phase,
+    pub kind: PlanSessionKind,
+    /// Planned distance (0 for rest days)
+    pub distance_m: f64,
+    /// Pace for easy, long and race days
+    pub target_pace_sec_per_km: Option<f64>,
+    /// Structured session for workout days, with paces from the plan's zones
+    pub workout: Option<Workout>,
+    /// Run that completed the session
+    pub run_id: Option<String>,
+}
+
+/// A periodized plan building up to a race
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingPlan {
+    pub id: String,
+    pub name: String,
+    pub race_distance_m: f64,
+    pub race_date: NaiveDate,
+    /// Fitness the plan's paces were derived from
+    pub fitness: RaceTime,
+    pub zones: PaceZones,
+    /// Weekly volume the plan started from
+    pub start_weekly_volume_m: f64,
+    pub created_at: DateTime<Utc>,
+    /// Every day from the plan's start to race day, in date order
+    pub days: Vec<PlanDay>,
+}
+
+impl TrainingPlan {
+    pub fn new(
+        name: String,
+        race_distance_m: f64,
+        race_date: NaiveDate,
+        fitness: RaceTime,
+        zones: PaceZones,
+        start_weekly_volume_m: f64,
+        days: Vec<PlanDay>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            race_distance_m,
+            race_date,
+            fitness,
+            zones,
+            start_weekly_volume_m,
+            created_at: Utc::now(),
+            days,
+        }
+    }
+
+    pub fn day(&self, date: NaiveDate) -> Option<&PlanDay> {
+        self.days.iter().find(|d| d.date == date)
+    }
+
+    /// Share of the running sessions up to and including `today` that have a run linked
+    pub fn completion(&self, today: NaiveDate) -> f64 {
+        let due: Vec<&PlanDay> = self
+            .days
+            .iter()
+            .filter(|d| d.date <= today && d.kind != PlanSessionKind::Rest)
+            .collect();
+        if due.is_empty() {
+            return 0.0;
+        }
+        due.iter().filter(|d| d.run_id.is_some()).count() as f64 / due.len() as f64
+    }
+}
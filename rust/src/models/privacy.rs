@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Largest privacy zone radius
+const MAX_RADIUS_M: f64 = 10_000.0;
+
+/// A circle (e.g. around home) whose points are left out of exports and
+/// shared maps. Stored runs keep every point.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivacyZone {
+    pub id: String,
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub radius_m: f64,
+}
+
+impl PrivacyZone {
+    pub fn new(name: String, lat: f64, lon: f64, radius_m: f64) -> anyhow::Result<Self> {
+        let zone = Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            lat,
+            lon,
+            radius_m,
+        };
+        zone.validate()?;
+        Ok(zone)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            (-90.0..=90.0).contains(&self.lat) && (-180.0..=180.0).contains(&self.lon),
+            "Invalid privacy zone centre"
+        );
+        anyhow::ensure!(
+            self.radius_m > 0.0 && self.radius_m <= MAX_RADIUS_M,
+            "Privacy zone radius must be between 0 and {MAX_RADIUS_M} m"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// Runner and banshee compared at one moment of a finished race
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GapSample {
+    pub elapsed_ms: i64,
+    pub runner_distance_m: f64,
+    /// Distance the banshee is ahead of the runner (positive = banshee ahead)
+    pub gap_m: f64,
+}
+
+/// A moment the lead changed hands
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LeadChange {
+    pub elapsed_ms: i64,
+    pub runner_distance_m: f64,
+    /// True if the runner took the lead, false if the banshee did
+    pub runner_took_lead: bool,
+}
+
+/// Runner and banshee times over one split
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SplitComparison {
+    /// Split number (1-indexed)
+    pub number: i32,
+    pub runner_ms: i64,
+    pub banshee_ms: i64,
+}
+
+impl SplitComparison {
+    /// True if the runner covered the split faster (ties go to the runner)
+    pub fn runner_won(&self) -> bool {
+        self.runner_ms <= self.banshee_ms
+    }
+}
+
+/// How a race against one banshee went, stored with the runner's run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RaceReport {
+    pub banshee_name: String,
+    /// Run the banshee replayed (None for AI pacers)
+    pub banshee_run_id: Option<String>,
+    pub gap_series: Vec<GapSample>,
+    pub lead_changes: Vec<LeadChange>,
+    /// Sample where the runner was furthest ahead, if they ever led
+    pub largest_lead: Option<GapSample>,
+    /// Sample where the runner was furthest behind, if they ever trailed
+    pub largest_deficit: Option<GapSample>,
+    pub split_distance_m: f64,
+    pub splits: Vec<SplitComparison>,
+    /// Runner's finish time minus the banshee's time over the same distance
+    /// (positive = banshee ahead). None if the banshee's run was shorter.
+    pub final_time_delta_ms: Option<i64>,
+    /// Distance the banshee was ahead when the runner finished
+    pub final_distance_delta_m: f64,
+}
+
+impl RaceReport {
+    /// True if the runner beat the banshee
+    pub fn runner_won(&self) -> bool {
+        match self.final_time_delta_ms {
+            Some(delta) => delta <= 0,
+            None => self.final_distance_delta_m <= 0.0,
+        }
+    }
+
+    pub fn splits_won(&self) -> usize {
+        self.splits.iter().filter(|s| s.runner_won()).count()
+    }
+}
@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::GpsPoint;
+use crate::geo::elevation::{climb_totals, elevation_profile, GRADE_SAMPLE_SPACING_M};
+use crate::geo::IndexedTrack;
+
+/// A point on a planned route. Routes have no timing, just a path.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RoutePoint {
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude: Option<f64>,
+}
+
+impl From<&GpsPoint> for RoutePoint {
+    fn from(point: &GpsPoint) -> Self {
+        Self {
+            lat: point.lat,
+            lon: point.lon,
+            altitude: point.altitude,
+        }
+    }
+}
+
+/// Altitude at a distance along a route
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ElevationSample {
+    pub distance_m: f64,
+    pub altitude_m: f64,
+}
+
+/// How a route was made
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RouteSource {
+    /// Drawn from waypoints in the route planner
+    Waypoints,
+    /// Simplified from one of the runner's runs
+    Run { run_id: String },
+    /// Imported from a GPX route or track file
+    Gpx { file_name: Option<String> },
+}
+
+/// A saved route that pacers can follow and runs can be matched onto
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub id: String,
+    pub name: String,
+    pub source: RouteSource,
+    pub created_at: DateTime<Utc>,
+    pub distance_meters: f64,
+    /// Total climb and descent, from the elevation profile
+    pub elevation_gain_m: f64,
+    pub elevation_loss_m: f64,
+    /// Altitude sampled along the route; empty if the points have none
+    pub elevation_profile: Vec<ElevationSample>,
+    pub points: Vec<RoutePoint>,
+}
+
+impl Route {
+    /// Build a route, working out its distance and elevation profile
+    pub fn new(name: String, source: RouteSource, points: Vec<RoutePoint>) -> anyhow::Result<Self> {
+        anyhow::ensure!(points.len() >= 2, "A route needs at least two points");
+        anyhow::ensure!(
+            points.iter().all(|p| p.lat.is_finite()
+                && p.lon.is_finite()
+                && (-90.0..=90.0).contains(&p.lat)
+                && (-180.0..=180.0).contains(&p.lon)),
+            "Invalid route point"
+        );
+
+        let track = track_from_points(&points);
+        let elevation_profile = elevation_profile(&track, GRADE_SAMPLE_SPACING_M);
+        let (elevation_gain_m, elevation_loss_m) = climb_totals(&elevation_profile);
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            source,
+            created_at: Utc::now(),
+            distance_meters: track.total_distance(),
+            elevation_gain_m,
+            elevation_loss_m,
+            elevation_profile,
+            points,
+        })
+    }
+
+    /// The route as a track pacers and route matching can follow
+    pub fn track(&self) -> IndexedTrack {
+        track_from_points(&self.points)
+    }
+}
+
+fn track_from_points(points: &[RoutePoint]) -> IndexedTrack {
+    let now = Utc::now();
+    IndexedTrack::new(
+        points
+            .iter()
+            .map(|p| GpsPoint {
+                altitude: p.altitude,
+                ..GpsPoint::new(p.lat, p.lon, now)
+            })
+            .collect(),
+    )
+}
+
+/// A route without its points and profile, for listings
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteSummary {
+    pub id: String,
+    pub name: String,
+    pub source: RouteSource,
+    pub created_at: DateTime<Utc>,
+    pub distance_meters: f64,
+    pub elevation_gain_m: f64,
+    pub elevation_loss_m: f64,
+}
+
+impl From<&Route> for RouteSummary {
+    fn from(route: &Route) -> Self {
+        Self {
+            id: route.id.clone(),
+            name: route.name.clone(),
+            source: route.source.clone(),
+            created_at: route.created_at,
+            distance_meters: route.distance_meters,
+            elevation_gain_m: route.elevation_gain_m,
+            elevation_loss_m: route.elevation_loss_m,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(lat: f64, altitude: Option<f64>) -> RoutePoint {
+        RoutePoint {
+            lat,
+            lon: -0.1,
+            altitude,
+        }
+    }
+
+    #[test]
+    fn test_route_distance_and_climb() {
+        // About 1.1 km north, climbing 20 m then dropping 5 m
+        let route = Route::new(
+            "Hill".to_string(),
+            RouteSource::Waypoints,
+            vec![
+                point(51.500, Some(10.0)),
+                point(51.505, Some(30.0)),
+                point(51.510, Some(25.0)),
+            ],
+        )
+        .unwrap();
+
+        assert!((route.distance_meters - 1112.0).abs() < 5.0);
+        assert!((route.elevation_gain_m - 20.0).abs() < 0.5);
+        assert!((route.elevation_loss_m - 5.0).abs() < 0.5);
+        assert_eq!(route.elevation_profile[0].distance_m, 0.0);
+        assert_eq!(route.elevation_profile.last().unwrap().altitude_m, 25.0);
+        assert_eq!(route.track().points().len(), 3);
+    }
+
+    #[test]
+    fn test_route_without_altitude() {
+        let route = Route::new(
+            "Flat".to_string(),
+            RouteSource::Waypoints,
+            vec![point(51.5, None), point(51.51, None)],
+        )
+        .unwrap();
+        assert!(route.elevation_profile.is_empty());
+        assert_eq!(route.elevation_gain_m, 0.0);
+    }
+
+    #[test]
+    fn test_invalid_routes() {
+        assert!(Route::new(
+            "x".to_string(),
+            RouteSource::Waypoints,
+            vec![point(51.5, None)]
+        )
+        .is_err());
+        assert!(Route::new(
+            "x".to_string(),
+            RouteSource::Waypoints,
+            vec![point(51.5, None), point(95.0, None)]
+        )
+        .is_err());
+    }
+}
@@ -2,7 +2,130 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::GpsPoint;
+use super::{GpsPoint, Lap};
+use crate::geo;
+
+/// Kind of run, as chosen by the user
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RunType {
+    Easy,
+    Long,
+    Tempo,
+    Race,
+    Workout,
+}
+
+impl RunType {
+    /// Stable string form used for storage and FFI
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunType::Easy => "easy",
+            RunType::Long => "long",
+            RunType::Tempo => "tempo",
+            RunType::Race => "race",
+            RunType::Workout => "workout",
+        }
+    }
+
+    /// Parse the string form produced by `as_str`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "easy" => Some(RunType::Easy),
+            "long" => Some(RunType::Long),
+            "tempo" => Some(RunType::Tempo),
+            "race" => Some(RunType::Race),
+            "workout" => Some(RunType::Workout),
+            _ => None,
+        }
+    }
+}
+
+/// User-editable information about a run that isn't derived from the GPS track
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RunMetadata {
+    /// Free-text notes
+    pub notes: Option<String>,
+    /// Kind of run (easy, long, tempo, ...)
+    pub run_type: Option<RunType>,
+    /// Tags, sorted and de-duplicated
+    pub tags: Vec<String>,
+    /// Rate of perceived exertion (1-10)
+    pub perceived_exertion: Option<i32>,
+    /// Short weather note (e.g. "Windy, 12°C")
+    pub weather: Option<String>,
+}
+
+/// Partial update for a run's name and metadata.
+/// Fields left as `None` are unchanged; empty strings clear text fields,
+/// and `Some(None)` clears the run type or perceived exertion.
+#[derive(Debug, Clone, Default)]
+pub struct RunMetadataPatch {
+    pub name: Option<String>,
+    pub notes: Option<String>,
+    pub run_type: Option<Option<RunType>>,
+    pub tags: Option<Vec<String>>,
+    pub perceived_exertion: Option<Option<i32>>,
+    pub weather: Option<String>,
+}
+
+impl RunMetadataPatch {
+    /// Apply this patch to a run's name and metadata
+    pub fn apply(&self, name: &mut Option<String>, metadata: &mut RunMetadata) {
+        if let Some(value) = &self.name {
+            *name = non_empty(value);
+        }
+        if let Some(value) = &self.notes {
+            metadata.notes = non_empty(value);
+        }
+        if let Some(run_type) = self.run_type {
+            metadata.run_type = run_type;
+        }
+        if let Some(tags) = &self.tags {
+            metadata.tags = normalize_tags(tags);
+        }
+        if let Some(rpe) = self.perceived_exertion {
+            metadata.perceived_exertion = rpe;
+        }
+        if let Some(value) = &self.weather {
+            metadata.weather = non_empty(value);
+        }
+    }
+}
+
+/// Trim, lowercase, de-duplicate and sort tags
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+/// A period during a run when the runner wasn't moving (e.g. a café stop)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Pause {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+impl Pause {
+    /// Length of the pause in milliseconds
+    pub fn duration_ms(&self) -> i64 {
+        (self.end_time - self.start_time).num_milliseconds().max(0)
+    }
+}
 
 /// A recorded run with GPS track and statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +146,15 @@ pub struct Run {
     pub duration_ms: i64,
     /// Average pace in seconds per kilometer
     pub avg_pace_sec_per_km: Option<f64>,
+    /// Notes, run type, tags and other user-editable fields
+    #[serde(default)]
+    pub metadata: RunMetadata,
+    /// Pauses excluded from distance and duration
+    #[serde(default)]
+    pub pauses: Vec<Pause>,
+    /// Laps in time order, e.g. the steps of a structured workout
+    #[serde(default)]
+    pub laps: Vec<Lap>,
 }
 
 impl Run {
@@ -37,6 +169,9 @@ impl Run {
             distance_meters: 0.0,
             duration_ms: 0,
             avg_pace_sec_per_km: None,
+            metadata: RunMetadata::default(),
+            pauses: Vec::new(),
+            laps: Vec::new(),
         }
     }
 
@@ -51,6 +186,9 @@ impl Run {
             distance_meters: 0.0,
             duration_ms: 0,
             avg_pace_sec_per_km: None,
+            metadata: RunMetadata::default(),
+            pauses: Vec::new(),
+            laps: Vec::new(),
         }
     }
 
@@ -67,6 +205,209 @@ impl Run {
         }
     }
 
+    /// Recompute distance, duration and pace from the GPS points,
+    /// leaving out the time and ground covered during pauses
+    pub fn recalculate_stats(&mut self) {
+        self.distance_meters = self
+            .moving_segments()
+            .iter()
+            .map(|segment| geo::total_distance(segment))
+            .sum();
+
+        self.duration_ms = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => {
+                let elapsed = (last.timestamp - first.timestamp).num_milliseconds();
+                let paused: i64 = self.pauses.iter().map(|p| p.duration_ms()).sum();
+                (elapsed - paused).max(0)
+            }
+            _ => 0,
+        };
+
+        self.avg_pace_sec_per_km = if self.distance_meters > 0.0 && self.duration_ms > 0 {
+            Some(geo::calculate_pace(self.distance_meters, self.duration_ms))
+        } else {
+            None
+        };
+    }
+
+    /// Split the points into the stretches between pauses
+    fn moving_segments(&self) -> Vec<&[GpsPoint]> {
+        let mut segments = Vec::new();
+        let mut start = 0;
+
+        for i in 1..self.points.len() {
+            let prev = self.points[i - 1].timestamp;
+            let next = self.points[i].timestamp;
+            let spans_pause = self
+                .pauses
+                .iter()
+                .any(|p| prev <= p.start_time && next >= p.end_time);
+
+            if spans_pause {
+                segments.push(&self.points[start..i]);
+                start = i;
+            }
+        }
+        segments.push(&self.points[start..]);
+
+        segments
+    }
+
+    /// Merge several runs into one, concatenating their tracks in time order.
+    /// The gap between consecutive runs is recorded as a pause. The merged run
+    /// takes its name and metadata from the earliest run, with tags combined.
+    pub fn merge(mut runs: Vec<Run>) -> anyhow::Result<Run> {
+        anyhow::ensure!(runs.len() >= 2, "Need at least two runs to merge");
+        anyhow::ensure!(
+            runs.iter().all(|r| !r.points.is_empty()),
+            "Cannot merge a run with no GPS points"
+        );
+
+        runs.sort_by_key(|r| r.points[0].timestamp);
+
+        for pair in runs.windows(2) {
+            let prev_end = pair[0].points.last().unwrap().timestamp;
+            let next_start = pair[1].points[0].timestamp;
+            anyhow::ensure!(next_start >= prev_end, "Runs overlap in time");
+        }
+
+        let first = &runs[0];
+        let mut merged = Run::new();
+        merged.name = first.name.clone();
+        merged.metadata = first.metadata.clone();
+        merged.start_time = first.start_time.min(first.points[0].timestamp);
+        merged.end_time = runs
+            .last()
+            .and_then(|r| r.end_time.or(r.points.last().map(|p| p.timestamp)));
+
+        let all_tags: Vec<String> = runs
+            .iter()
+            .flat_map(|r| r.metadata.tags.iter().cloned())
+            .collect();
+        merged.metadata.tags = normalize_tags(&all_tags);
+
+        for run in runs {
+            if let (Some(prev), Some(next)) = (merged.points.last(), run.points.first()) {
+                if next.timestamp > prev.timestamp {
+                    merged.pauses.push(Pause {
+                        start_time: prev.timestamp,
+                        end_time: next.timestamp,
+                    });
+                }
+            }
+            merged.pauses.extend(run.pauses);
+            merged.laps.extend(run.laps);
+            merged.points.extend(run.points);
+        }
+
+        merged.recalculate_stats();
+        Ok(merged)
+    }
+
+    /// Split a run in two at `at`. Points before `at` go to the first run and
+    /// the rest to the second; both get new IDs and copies of the metadata.
+    pub fn split_at(&self, at: DateTime<Utc>) -> anyhow::Result<(Run, Run)> {
+        let idx = self.points.partition_point(|p| p.timestamp < at);
+        anyhow::ensure!(
+            idx > 0 && idx < self.points.len(),
+            "Split time must fall inside the run"
+        );
+
+        let make_part = |points: &[GpsPoint]| {
+            let first = points[0].timestamp;
+            let last = points[points.len() - 1].timestamp;
+
+            let mut part = Run::new();
+            part.name = self.name.clone();
+            part.metadata = self.metadata.clone();
+            part.start_time = first;
+            part.end_time = Some(last);
+            part.points = points.to_vec();
+            part.pauses = self
+                .pauses
+                .iter()
+                .filter(|p| p.start_time >= first && p.end_time <= last)
+                .copied()
+                .collect();
+            part.laps = self
+                .laps
+                .iter()
+                .filter(|l| l.start_time >= first && l.end_time <= last)
+                .copied()
+                .collect();
+            part.recalculate_stats();
+            part
+        };
+
+        let mut before = make_part(&self.points[..idx]);
+        before.start_time = self.start_time.min(before.start_time);
+        let mut after = make_part(&self.points[idx..]);
+        after.end_time = self.end_time.or(after.end_time);
+
+        Ok((before, after))
+    }
+
+    /// Remove points recorded in the first `start_offset_ms` and the last
+    /// `end_offset_ms` of the run, then recompute the stats
+    pub fn trim(&mut self, start_offset_ms: i64, end_offset_ms: i64) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            start_offset_ms >= 0 && end_offset_ms >= 0,
+            "Trim offsets must not be negative"
+        );
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+            _ => anyhow::bail!("Run has no GPS points"),
+        };
+
+        let keep_from = first + chrono::Duration::milliseconds(start_offset_ms);
+        let keep_to = last - chrono::Duration::milliseconds(end_offset_ms);
+        let lo = self.points.partition_point(|p| p.timestamp < keep_from);
+        let hi = self.points.partition_point(|p| p.timestamp <= keep_to);
+
+        self.retain_points(lo, hi)
+    }
+
+    /// Remove the first `start_m` and the last `end_m` meters of the run,
+    /// then recompute the stats
+    pub fn trim_by_distance(&mut self, start_m: f64, end_m: f64) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            start_m >= 0.0 && end_m >= 0.0,
+            "Trim distances must not be negative"
+        );
+
+        let cumulative = geo::distance::cumulative_distances(&self.points);
+        let total = cumulative.last().copied().unwrap_or(0.0);
+        let lo = cumulative.partition_point(|&d| d < start_m);
+        let hi = cumulative.partition_point(|&d| d <= total - end_m);
+
+        self.retain_points(lo, hi)
+    }
+
+    /// Keep only `points[lo..hi]` and the pauses and laps inside that stretch
+    fn retain_points(&mut self, lo: usize, hi: usize) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            hi > lo && hi - lo >= 2,
+            "Trim would leave fewer than two GPS points"
+        );
+
+        self.points.truncate(hi);
+        self.points.drain(..lo);
+
+        let first = self.points[0].timestamp;
+        let last = self.points[self.points.len() - 1].timestamp;
+        self.pauses
+            .retain(|p| p.start_time >= first && p.end_time <= last);
+        self.laps
+            .retain(|l| l.start_time >= first && l.end_time <= last);
+        self.start_time = first;
+        if self.end_time.is_some() {
+            self.end_time = Some(last);
+        }
+
+        self.recalculate_stats();
+        Ok(())
+    }
+
     /// Check if run is in progress
     pub fn is_active(&self) -> bool {
         self.end_time.is_none()
@@ -126,3 +467,95 @@ impl From<&Run> for RunSummary {
         }
     }
 }
+
+/// A soft-deleted run waiting in the trash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedRun {
+    pub summary: RunSummary,
+    /// When the run was moved to the trash
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn run_at(start: DateTime<Utc>, lat: f64, minutes: i64) -> Run {
+        let mut run = Run::new();
+        run.start_time = start;
+        for i in 0..=minutes {
+            // ~111m per minute heading north
+            run.add_point(GpsPoint::new(
+                lat + 0.001 * i as f64,
+                -0.1,
+                start + Duration::minutes(i),
+            ));
+        }
+        run.recalculate_stats();
+        run
+    }
+
+    #[test]
+    fn test_merge_records_gap_as_pause() {
+        let start = Utc::now();
+        let first = run_at(start, 51.5, 10);
+        let second = run_at(start + Duration::minutes(40), 51.51, 10);
+        let expected_distance = first.distance_meters + second.distance_meters;
+
+        // Order of the input doesn't matter
+        let merged = Run::merge(vec![second, first]).unwrap();
+
+        assert_eq!(merged.points.len(), 22);
+        assert_eq!(merged.pauses.len(), 1);
+        assert_eq!(merged.pauses[0].duration_ms(), 30 * 60 * 1000);
+        assert_eq!(merged.duration_ms, 20 * 60 * 1000);
+        assert!((merged.distance_meters - expected_distance).abs() < 0.01);
+        assert!(merged.avg_pace_sec_per_km.is_some());
+    }
+
+    #[test]
+    fn test_merge_rejects_overlap() {
+        let start = Utc::now();
+        let first = run_at(start, 51.5, 10);
+        let second = run_at(start + Duration::minutes(5), 51.51, 10);
+        assert!(Run::merge(vec![first, second]).is_err());
+    }
+
+    #[test]
+    fn test_trim() {
+        let start = Utc::now();
+        let mut run = run_at(start, 51.5, 10);
+        run.end_time = Some(start + Duration::minutes(30));
+
+        run.trim(2 * 60 * 1000, 3 * 60 * 1000).unwrap();
+        assert_eq!(run.points.len(), 6);
+        assert_eq!(run.start_time, start + Duration::minutes(2));
+        assert_eq!(run.end_time, Some(start + Duration::minutes(7)));
+        assert_eq!(run.duration_ms, 5 * 60 * 1000);
+
+        // ~111m between points, so 150m drops two from the start
+        run.trim_by_distance(150.0, 0.0).unwrap();
+        assert_eq!(run.points.len(), 4);
+
+        assert!(run.trim(0, 10 * 60 * 1000).is_err());
+        assert_eq!(run.points.len(), 4);
+    }
+
+    #[test]
+    fn test_split_at() {
+        let start = Utc::now();
+        let run = run_at(start, 51.5, 10);
+
+        let (before, after) = run.split_at(start + Duration::minutes(4)).unwrap();
+        assert_eq!(before.points.len(), 4);
+        assert_eq!(after.points.len(), 7);
+        assert_eq!(before.duration_ms, 3 * 60 * 1000);
+        assert_eq!(after.duration_ms, 6 * 60 * 1000);
+        assert_ne!(before.id, run.id);
+        assert!(before.distance_meters + after.distance_meters < run.distance_meters);
+
+        assert!(run.split_at(start).is_err());
+        assert!(run.split_at(start + Duration::hours(1)).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A line across the path that efforts cross, centred on a point and at
+/// right angles to the direction of travel
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Gate {
+    pub lat: f64,
+    pub lon: f64,
+    /// Direction of travel through the gate, degrees clockwise from north
+    pub bearing_deg: f64,
+    /// How far either side of the centre a crossing still counts
+    pub half_width_m: f64,
+}
+
+/// A stretch between two gates (e.g. a hill climb) with a leaderboard of
+/// the runner's efforts on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub id: String,
+    pub name: String,
+    pub start_gate: Gate,
+    pub end_gate: Gate,
+    /// Length along the path the segment was drawn on
+    pub distance_meters: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Segment {
+    pub fn new(
+        name: String,
+        start_gate: Gate,
+        end_gate: Gate,
+        distance_meters: f64,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(distance_meters > 0.0, "A segment needs a length");
+        anyhow::ensure!(
+            start_gate.half_width_m > 0.0 && end_gate.half_width_m > 0.0,
+            "Gates need a width"
+        );
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            start_gate,
+            end_gate,
+            distance_meters,
+            created_at: Utc::now(),
+        })
+    }
+}
+
+/// One traversal of a segment during a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentEffort {
+    pub segment_id: String,
+    pub run_id: String,
+    /// When the start gate was crossed
+    pub start_time: DateTime<Utc>,
+    pub elapsed_ms: i64,
+    /// Distance covered between the gates
+    pub distance_meters: f64,
+}
@@ -0,0 +1,228 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Part a workout step plays
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StepKind {
+    Warmup,
+    Work,
+    Recovery,
+    Cooldown,
+}
+
+impl StepKind {
+    /// Stable string form used for storage and FFI
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepKind::Warmup => "warmup",
+            StepKind::Work => "work",
+            StepKind::Recovery => "recovery",
+            StepKind::Cooldown => "cooldown",
+        }
+    }
+
+    /// Parse the string form produced by `as_str`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "warmup" => Some(StepKind::Warmup),
+            "work" => Some(StepKind::Work),
+            "recovery" => Some(StepKind::Recovery),
+            "cooldown" => Some(StepKind::Cooldown),
+            _ => None,
+        }
+    }
+}
+
+/// How long a step lasts
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum StepLength {
+    Distance { meters: f64 },
+    Time { ms: i64 },
+}
+
+/// One step of a workout
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct WorkoutStep {
+    pub kind: StepKind,
+    pub length: StepLength,
+    /// Pace for the pacer to run the step at; None for no pacer (e.g. a
+    /// standing recovery or an easy warmup at feel)
+    pub pace_sec_per_km: Option<f64>,
+}
+
+impl WorkoutStep {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self.length {
+            StepLength::Distance { meters } => {
+                anyhow::ensure!(meters > 0.0, "Step distance must be positive")
+            }
+            StepLength::Time { ms } => anyhow::ensure!(ms > 0, "Step time must be positive"),
+        }
+        if let Some(pace) = self.pace_sec_per_km {
+            anyhow::ensure!(pace > 0.0, "Invalid pace");
+        }
+        Ok(())
+    }
+}
+
+/// A structured workout: warmup, `repeats` × (work, recovery), cooldown
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Workout {
+    pub id: String,
+    pub name: String,
+    pub warmup: Option<WorkoutStep>,
+    pub repeats: u32,
+    pub work: WorkoutStep,
+    /// Recovery after each work step
+    pub recovery: Option<WorkoutStep>,
+    pub cooldown: Option<WorkoutStep>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Workout {
+    pub fn new(
+        name: String,
+        warmup: Option<WorkoutStep>,
+        repeats: u32,
+        work: WorkoutStep,
+        recovery: Option<WorkoutStep>,
+        cooldown: Option<WorkoutStep>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            warmup,
+            repeats,
+            work,
+            recovery,
+            cooldown,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Check the steps are usable and each one is in its right place
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(self.repeats > 0, "A workout needs at least one repeat");
+        let expected = [
+            (self.warmup.as_ref(), StepKind::Warmup),
+            (Some(&self.work), StepKind::Work),
+            (self.recovery.as_ref(), StepKind::Recovery),
+            (self.cooldown.as_ref(), StepKind::Cooldown),
+        ];
+        for (step, kind) in expected {
+            if let Some(step) = step {
+                anyhow::ensure!(
+                    step.kind == kind,
+                    "Expected a {} step, got {}",
+                    kind.as_str(),
+                    step.kind.as_str()
+                );
+                step.validate()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Every step in the order it's run, with the repeats expanded. There is
+    /// no recovery after the last work step.
+    pub fn steps(&self) -> Vec<WorkoutStep> {
+        let mut steps: Vec<WorkoutStep> = self.warmup.into_iter().collect();
+        for rep in 0..self.repeats {
+            steps.push(self.work);
+            if rep + 1 < self.repeats {
+                steps.extend(self.recovery);
+            }
+        }
+        steps.extend(self.cooldown);
+        steps
+    }
+}
+
+/// A lap of a run, e.g. one step of a workout
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Lap {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub distance_meters: f64,
+    /// Workout step the lap was, if it came from a workout
+    pub step_kind: Option<StepKind>,
+    /// Pace the step asked for
+    pub target_pace_sec_per_km: Option<f64>,
+}
+
+impl Lap {
+    pub fn duration_ms(&self) -> i64 {
+        (self.end_time - self.start_time).num_milliseconds().max(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(kind: StepKind, length: StepLength, pace: Option<f64>) -> WorkoutStep {
+        WorkoutStep {
+            kind,
+            length,
+            pace_sec_per_km: pace,
+        }
+    }
+
+    #[test]
+    fn test_steps_expand_repeats() {
+        let workout = Workout::new(
+            "6 x 800m".to_string(),
+            Some(step(
+                StepKind::Warmup,
+                StepLength::Time { ms: 600_000 },
+                None,
+            )),
+            6,
+            step(
+                StepKind::Work,
+                StepLength::Distance { meters: 800.0 },
+                Some(225.0),
+            ),
+            Some(step(
+                StepKind::Recovery,
+                StepLength::Time { ms: 90_000 },
+                None,
+            )),
+            Some(step(
+                StepKind::Cooldown,
+                StepLength::Time { ms: 600_000 },
+                None,
+            )),
+        );
+        workout.validate().unwrap();
+
+        let kinds: Vec<StepKind> = workout.steps().iter().map(|s| s.kind).collect();
+        assert_eq!(kinds.len(), 1 + 6 + 5 + 1);
+        assert_eq!(kinds[0], StepKind::Warmup);
+        assert_eq!(kinds[1], StepKind::Work);
+        assert_eq!(kinds[2], StepKind::Recovery);
+        assert_eq!(kinds[11], StepKind::Work);
+        assert_eq!(kinds[12], StepKind::Cooldown);
+    }
+
+    #[test]
+    fn test_validate() {
+        let work = step(
+            StepKind::Work,
+            StepLength::Distance { meters: 400.0 },
+            Some(80.0),
+        );
+        let mut workout = Workout::new("Laps".to_string(), None, 0, work, None, None);
+        assert!(workout.validate().is_err());
+
+        workout.repeats = 4;
+        workout.validate().unwrap();
+
+        workout.recovery = Some(step(StepKind::Work, StepLength::Time { ms: 60_000 }, None));
+        assert!(workout.validate().is_err());
+
+        workout.recovery = Some(step(StepKind::Recovery, StepLength::Time { ms: 0 }, None));
+        assert!(workout.validate().is_err());
+    }
+}
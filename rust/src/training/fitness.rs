@@ -0,0 +1,157 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::geo::pace;
+use crate::models::{PaceZones, RaceTime, Run, RunSummary};
+
+/// Fatigue exponent in Riegel's formula, T2 = T1 × (D2 / D1)^1.06
+pub const RIEGEL_EXPONENT: f64 = 1.06;
+
+/// How far back recent best efforts count towards predicted fitness
+pub const FITNESS_LOOKBACK_WEEKS: i64 = 8;
+
+/// How far back runs count towards current weekly volume
+pub const VOLUME_LOOKBACK_WEEKS: i64 = 4;
+
+/// Shortest best effort used to predict fitness; shorter ones overrate
+/// endurance
+const MIN_FITNESS_EFFORT_M: f64 = 1609.344;
+
+const MILE_M: f64 = 1609.344;
+const MARATHON_M: f64 = 42_195.0;
+
+/// Predicted time over `distance_m` from a known performance
+pub fn predict_time(known: RaceTime, distance_m: f64) -> i64 {
+    (known.duration_ms as f64 * (distance_m / known.distance_m).powf(RIEGEL_EXPONENT)).round()
+        as i64
+}
+
+/// Predicted pace over `distance_m` from a known performance
+pub fn predict_pace(known: RaceTime, distance_m: f64) -> f64 {
+    pace::calculate_pace(distance_m, predict_time(known, distance_m))
+}
+
+/// Distance that could be covered flat out in `duration_ms`
+pub fn distance_for_time(known: RaceTime, duration_ms: i64) -> f64 {
+    known.distance_m * (duration_ms as f64 / known.duration_ms as f64).powf(1.0 / RIEGEL_EXPONENT)
+}
+
+/// Training paces for a runner capable of `fitness`
+pub fn pace_zones(fitness: RaceTime) -> PaceZones {
+    let hour_distance = distance_for_time(fitness, 3_600_000);
+    let marathon = predict_pace(fitness, MARATHON_M);
+
+    PaceZones {
+        easy_sec_per_km: marathon * 1.2,
+        marathon_sec_per_km: marathon,
+        threshold_sec_per_km: predict_pace(fitness, hour_distance),
+        interval_sec_per_km: predict_pace(fitness, 5000.0),
+        repetition_sec_per_km: predict_pace(fitness, MILE_M),
+    }
+}
+
+/// Predict fitness from the best efforts in `runs`: the effort (a mile or
+/// longer) that predicts the fastest 10K
+pub fn estimate_fitness(runs: &[Run]) -> Option<RaceTime> {
+    runs.iter()
+        .flat_map(|run| pace::best_efforts(&run.points))
+        .filter(|effort| effort.distance_m >= MIN_FITNESS_EFFORT_M && effort.duration_ms > 0)
+        .map(|effort| RaceTime {
+            distance_m: effort.distance_m,
+            duration_ms: effort.duration_ms,
+        })
+        .min_by_key(|effort| predict_time(*effort, 10_000.0))
+}
+
+/// Average distance per week over the `weeks` weeks before `now`
+pub fn weekly_volume(runs: &[RunSummary], now: DateTime<Utc>, weeks: i64) -> f64 {
+    if weeks <= 0 {
+        return 0.0;
+    }
+    let since = now - Duration::weeks(weeks);
+    let total: f64 = runs
+        .iter()
+        .filter(|r| r.start_time >= since && r.start_time <= now)
+        .map(|r| r.distance_meters)
+        .sum();
+    total / weeks as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GpsPoint;
+
+    /// 20:00 5K
+    const FIVE_K: RaceTime = RaceTime {
+        distance_m: 5000.0,
+        duration_ms: 1_200_000,
+    };
+
+    #[test]
+    fn test_predict_time() {
+        assert_eq!(predict_time(FIVE_K, 5000.0), 1_200_000);
+        // Riegel puts a 20:00 5K at about 41:41 for 10K
+        let ten_k = predict_time(FIVE_K, 10_000.0);
+        assert!((ten_k - 2_501_000).abs() < 1000, "{ten_k}");
+        let back = distance_for_time(FIVE_K, ten_k);
+        assert!((back - 10_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_pace_zones_are_ordered() {
+        let zones = pace_zones(FIVE_K);
+        assert!((zones.interval_sec_per_km - 240.0).abs() < 1e-6);
+        assert!(zones.repetition_sec_per_km < zones.interval_sec_per_km);
+        assert!(zones.interval_sec_per_km < zones.threshold_sec_per_km);
+        assert!(zones.threshold_sec_per_km < zones.marathon_sec_per_km);
+        assert!(zones.marathon_sec_per_km < zones.easy_sec_per_km);
+    }
+
+    /// Run heading north at a steady pace, one point per ~111m
+    fn run(legs: usize, secs_per_leg: i64) -> Run {
+        let start = Utc::now();
+        let mut run = Run::new();
+        for i in 0..=legs {
+            run.add_point(GpsPoint::new(
+                51.5 + 0.001 * i as f64,
+                -0.1,
+                start + Duration::seconds(secs_per_leg * i as i64),
+            ));
+        }
+        run.recalculate_stats();
+        run
+    }
+
+    #[test]
+    fn test_estimate_fitness() {
+        // ~5.5km at 5:00/km, and ~2.2km at 4:00/km
+        let steady = run(50, 33);
+        let fast = run(20, 27);
+
+        let fitness = estimate_fitness(&[steady, fast]).unwrap();
+        assert!((fitness.pace_sec_per_km() - 243.0).abs() < 5.0);
+        assert!(fitness.distance_m >= MIN_FITNESS_EFFORT_M);
+
+        // Too short to predict from
+        assert!(estimate_fitness(&[run(5, 27)]).is_none());
+    }
+
+    #[test]
+    fn test_weekly_volume() {
+        let now = Utc::now();
+        let summary = |days_ago: i64, distance_meters: f64| RunSummary {
+            id: String::new(),
+            name: None,
+            start_time: now - Duration::days(days_ago),
+            distance_meters,
+            duration_ms: 0,
+            avg_pace_sec_per_km: None,
+        };
+        let runs = [
+            summary(1, 10_000.0),
+            summary(10, 20_000.0),
+            summary(40, 50_000.0),
+        ];
+        assert!((weekly_volume(&runs, now, 4) - 7500.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,10 @@
+//! Fitness prediction, pace zones and training plans
+
+pub mod fitness;
+pub mod plan;
+
+pub use fitness::{
+    estimate_fitness, pace_zones, predict_time, weekly_volume, FITNESS_LOOKBACK_WEEKS,
+    VOLUME_LOOKBACK_WEEKS,
+};
+pub use plan::{generate_plan, PlanRequest};
@@ -0,0 +1,510 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use super::fitness::{pace_zones, predict_pace};
+use crate::models::{
+    PaceZones, Phase, PlanDay, PlanSessionKind, RaceTime, StepKind, StepLength, TrainingPlan,
+    Workout, WorkoutStep,
+};
+
+/// Week-on-week volume growth outside step-back weeks
+const WEEKLY_GROWTH: f64 = 1.08;
+/// Every this many weeks is a lighter step-back week
+const STEP_BACK_EVERY: u32 = 4;
+const STEP_BACK_FACTOR: f64 = 0.8;
+/// Shortest easy run worth scheduling
+const MIN_EASY_RUN_M: f64 = 3000.0;
+const MAX_LONG_RUN_M: f64 = 32_000.0;
+
+/// What to build a plan for
+#[derive(Debug, Clone)]
+pub struct PlanRequest {
+    pub name: String,
+    pub race_distance_m: f64,
+    pub race_date: NaiveDate,
+    /// First day of the plan (usually today)
+    pub start_date: NaiveDate,
+    /// Running days per week, 3 to 7
+    pub runs_per_week: u32,
+    /// Current weekly volume
+    pub weekly_volume_m: f64,
+    /// Current fitness, e.g. a recent race or best effort
+    pub fitness: RaceTime,
+}
+
+impl PlanRequest {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(self.race_distance_m > 0.0, "Invalid race distance");
+        anyhow::ensure!(
+            (3..=7).contains(&self.runs_per_week),
+            "Plans need 3 to 7 runs a week"
+        );
+        anyhow::ensure!(self.weekly_volume_m >= 0.0, "Invalid weekly volume");
+        anyhow::ensure!(
+            self.fitness.distance_m > 0.0 && self.fitness.duration_ms > 0,
+            "Invalid fitness"
+        );
+        let weeks = plan_weeks(self.start_date, self.race_date);
+        anyhow::ensure!(weeks >= 3, "The race must be at least three weeks away");
+        anyhow::ensure!(weeks <= 52, "The race must be within a year");
+        Ok(())
+    }
+}
+
+/// Number of plan weeks; the last one ends on race day
+fn plan_weeks(start_date: NaiveDate, race_date: NaiveDate) -> u32 {
+    let days = (race_date - start_date).num_days() + 1;
+    (days.max(0) as u32).div_ceil(7)
+}
+
+/// Phase of each week: base, build, peak then taper
+fn phases(weeks: u32, race_distance_m: f64) -> Vec<Phase> {
+    let taper = if race_distance_m >= 30_000.0 && weeks >= 12 {
+        3
+    } else if race_distance_m >= 15_000.0 && weeks >= 6 {
+        2
+    } else {
+        1
+    };
+    let remaining = weeks - taper;
+    let peak = ((remaining as f64 * 0.2).round() as u32).max(1);
+    let build = ((remaining as f64 * 0.35).round() as u32).min(remaining - peak);
+    let base = remaining - peak - build;
+
+    [
+        (Phase::Base, base),
+        (Phase::Build, build),
+        (Phase::Peak, peak),
+        (Phase::Taper, taper),
+    ]
+    .iter()
+    .flat_map(|&(phase, count)| std::iter::repeat_n(phase, count as usize))
+    .collect()
+}
+
+/// Target volume for each week
+fn weekly_volumes(request: &PlanRequest, phases: &[Phase]) -> Vec<f64> {
+    let start = request
+        .weekly_volume_m
+        .max(request.runs_per_week as f64 * MIN_EASY_RUN_M);
+    let race_peak = 30_000.0 + 1.4 * request.race_distance_m;
+    let peak = start.max(race_peak.min(start * 1.5));
+    let taper_weeks = phases.iter().filter(|&&p| p == Phase::Taper).count();
+
+    let mut volumes = Vec::with_capacity(phases.len());
+    let mut current = start;
+    let mut taper_week = 0;
+    for (idx, &phase) in phases.iter().enumerate() {
+        let week = idx as u32 + 1;
+        let volume = match phase {
+            Phase::Taper => {
+                taper_week += 1;
+                peak * (1.0 - 0.55 * taper_week as f64 / taper_weeks as f64)
+            }
+            _ if week.is_multiple_of(STEP_BACK_EVERY) && phase != Phase::Peak => {
+                current * STEP_BACK_FACTOR
+            }
+            _ => {
+                if idx > 0 {
+                    current = (current * WEEKLY_GROWTH).min(peak);
+                }
+                current
+            }
+        };
+        volumes.push(volume);
+    }
+    volumes
+}
+
+/// Weekdays (0 = Monday) to run on, by runs per week. The long run is on
+/// Sunday and quality sessions go on the first run days after Monday.
+fn run_days(runs_per_week: u32) -> &'static [u32] {
+    match runs_per_week {
+        3 => &[1, 3, 6],
+        4 => &[1, 2, 4, 6],
+        5 => &[0, 1, 3, 4, 6],
+        6 => &[0, 1, 2, 3, 4, 6],
+        _ => &[0, 1, 2, 3, 4, 5, 6],
+    }
+}
+
+const LONG_RUN_DAY: u32 = 6;
+const FIRST_QUALITY_DAY: u32 = 1;
+
+/// Day of the second quality session: the next run day from Thursday on
+fn second_quality_day(runs_per_week: u32) -> Option<u32> {
+    if runs_per_week < 5 {
+        return None;
+    }
+    run_days(runs_per_week)
+        .iter()
+        .copied()
+        .find(|&d| d >= 3 && d != LONG_RUN_DAY)
+}
+
+fn step(kind: StepKind, length: StepLength, pace: Option<f64>) -> WorkoutStep {
+    WorkoutStep {
+        kind,
+        length,
+        pace_sec_per_km: pace,
+    }
+}
+
+fn minutes(m: i64) -> StepLength {
+    StepLength::Time { ms: m * 60_000 }
+}
+
+/// Warmup, `repeats` × (work, jog recovery), cooldown, paced from the zones
+fn structured(
+    name: String,
+    zones: &PaceZones,
+    warmup_min: i64,
+    repeats: u32,
+    work: StepLength,
+    work_pace: f64,
+    recovery_min: i64,
+) -> Workout {
+    let easy = Some(zones.easy_sec_per_km);
+    Workout::new(
+        name,
+        Some(step(StepKind::Warmup, minutes(warmup_min), easy)),
+        repeats,
+        step(StepKind::Work, work, Some(work_pace)),
+        Some(step(StepKind::Recovery, minutes(recovery_min), None)),
+        Some(step(StepKind::Cooldown, minutes(10), easy)),
+    )
+}
+
+fn threshold_workout(zones: &PaceZones, repeats: u32) -> Workout {
+    structured(
+        format!("Threshold {repeats} × 8 min"),
+        zones,
+        10,
+        repeats,
+        minutes(8),
+        zones.threshold_sec_per_km,
+        2,
+    )
+}
+
+fn interval_workout(zones: &PaceZones, repeats: u32) -> Workout {
+    structured(
+        format!("Intervals {repeats} × 1000m"),
+        zones,
+        15,
+        repeats,
+        StepLength::Distance { meters: 1000.0 },
+        zones.interval_sec_per_km,
+        3,
+    )
+}
+
+fn race_pace_workout(
+    zones: &PaceZones,
+    race_pace: f64,
+    race_distance_m: f64,
+    repeats: u32,
+) -> Workout {
+    let rep_m = ((race_distance_m / 5.0 / 100.0).round() * 100.0).clamp(1000.0, 3000.0);
+    structured(
+        format!("Race pace {repeats} × {rep_m:.0}m"),
+        zones,
+        15,
+        repeats,
+        StepLength::Distance { meters: rep_m },
+        race_pace,
+        2,
+    )
+}
+
+/// Expected distance of a workout, with unpaced steps at easy pace
+fn workout_distance(workout: &Workout, easy_pace: f64) -> f64 {
+    workout
+        .steps()
+        .iter()
+        .map(|s| match s.length {
+            StepLength::Distance { meters } => meters,
+            StepLength::Time { ms } => ms as f64 / s.pace_sec_per_km.unwrap_or(easy_pace),
+        })
+        .sum()
+}
+
+/// Generate a periodized plan from `request.start_date` to race day.
+///
+/// Weeks run base (easy volume), build (threshold and interval sessions),
+/// peak (intervals and race-pace work) and taper (volume cut, sharpening).
+/// Volume grows from the current weekly volume with a step-back week every
+/// fourth week, and every pace comes from zones derived from the fitness.
+pub fn generate_plan(request: &PlanRequest) -> anyhow::Result<TrainingPlan> {
+    request.validate()?;
+
+    let weeks = plan_weeks(request.start_date, request.race_date);
+    let phases = phases(weeks, request.race_distance_m);
+    let volumes = weekly_volumes(request, &phases);
+    let zones = pace_zones(request.fitness);
+    let race_pace = predict_pace(request.fitness, request.race_distance_m);
+    let easy = zones.easy_sec_per_km;
+    let long_cap = (request.race_distance_m * 0.75).clamp(16_000.0, MAX_LONG_RUN_M);
+    let second_quality = second_quality_day(request.runs_per_week);
+
+    let first_day = request.race_date - Duration::days(weeks as i64 * 7 - 1);
+    let mut days = Vec::new();
+    let mut phase_week = 0;
+
+    for (idx, (&phase, &volume)) in phases.iter().zip(&volumes).enumerate() {
+        let week = idx as u32 + 1;
+        phase_week = if idx > 0 && phases[idx - 1] == phase {
+            phase_week + 1
+        } else {
+            0
+        };
+        let race_week = week == weeks;
+        let week_start = first_day + Duration::days(idx as i64 * 7);
+
+        // Quality sessions by weekday
+        let quality = |weekday: u32, date: NaiveDate| -> Option<Workout> {
+            let first = weekday == FIRST_QUALITY_DAY;
+            let second = Some(weekday) == second_quality;
+            if race_week {
+                let days_out = (request.race_date - date).num_days();
+                return (first && days_out >= 3).then(|| interval_workout(&zones, 3));
+            }
+            match phase {
+                Phase::Base => None,
+                Phase::Build if first => {
+                    Some(threshold_workout(&zones, (3 + phase_week / 2).min(5)))
+                }
+                Phase::Build if second => {
+                    Some(interval_workout(&zones, (4 + phase_week / 2).min(6)))
+                }
+                Phase::Peak
+                    if first && (second_quality.is_some() || phase_week.is_multiple_of(2)) =>
+                {
+                    Some(interval_workout(&zones, (5 + phase_week / 2).min(6)))
+                }
+                Phase::Peak if first || second => Some(race_pace_workout(
+                    &zones,
+                    race_pace,
+                    request.race_distance_m,
+                    (3 + phase_week / 2).min(4),
+                )),
+                Phase::Taper if first => Some(interval_workout(&zones, 4)),
+                _ => None,
+            }
+        };
+
+        // Lay out the week's sessions, then share out the easy volume
+        let mut week_days: Vec<PlanDay> = (0..7)
+            .map(|offset| {
+                let date = week_start + Duration::days(offset);
+                let weekday = date.weekday().num_days_from_monday();
+                let runs = run_days(request.runs_per_week).contains(&weekday);
+
+                let (kind, distance_m, pace, workout) = if date == request.race_date {
+                    (
+                        PlanSessionKind::Race,
+                        request.race_distance_m,
+                        Some(race_pace),
+                        None,
+                    )
+                } else if !runs || (race_week && request.race_date - date == Duration::days(1)) {
+                    (PlanSessionKind::Rest, 0.0, None, None)
+                } else if let Some(workout) = quality(weekday, date) {
+                    let distance = workout_distance(&workout, easy);
+                    (PlanSessionKind::Workout, distance, None, Some(workout))
+                } else if weekday == LONG_RUN_DAY && !race_week {
+                    (
+                        PlanSessionKind::Long,
+                        (volume * 0.3).min(long_cap),
+                        Some(easy),
+                        None,
+                    )
+                } else {
+                    (PlanSessionKind::Easy, 0.0, Some(easy), None)
+                };
+
+                PlanDay {
+                    date,
+                    week,
+                    phase,
+                    kind,
+                    distance_m,
+                    target_pace_sec_per_km: pace,
+                    workout,
+                    run_id: None,
+                }
+            })
+            .collect();
+
+        let scheduled: f64 = week_days
+            .iter()
+            .filter(|d| d.kind != PlanSessionKind::Race)
+            .map(|d| d.distance_m)
+            .sum();
+        let easy_count = week_days
+            .iter()
+            .filter(|d| d.kind == PlanSessionKind::Easy)
+            .count();
+        if easy_count > 0 {
+            let each = ((volume - scheduled) / easy_count as f64).max(MIN_EASY_RUN_M);
+            // Round to the nearest 500m
+            let each = (each / 500.0).round() * 500.0;
+            for day in week_days
+                .iter_mut()
+                .filter(|d| d.kind == PlanSessionKind::Easy)
+            {
+                day.distance_m = each;
+            }
+        }
+        for day in week_days
+            .iter_mut()
+            .filter(|d| d.kind == PlanSessionKind::Long)
+        {
+            day.distance_m = (day.distance_m / 500.0).round() * 500.0;
+        }
+
+        days.extend(
+            week_days
+                .into_iter()
+                .filter(|d| d.date >= request.start_date),
+        );
+    }
+
+    Ok(TrainingPlan::new(
+        request.name.clone(),
+        request.race_distance_m,
+        request.race_date,
+        request.fitness,
+        zones,
+        request.weekly_volume_m,
+        days,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(weeks: i64, race_distance_m: f64, runs_per_week: u32) -> PlanRequest {
+        let start_date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        PlanRequest {
+            name: "Spring race".to_string(),
+            race_distance_m,
+            race_date: start_date + Duration::days(weeks * 7 - 1),
+            start_date,
+            runs_per_week,
+            weekly_volume_m: 30_000.0,
+            fitness: RaceTime {
+                distance_m: 5000.0,
+                duration_ms: 1_350_000,
+            },
+        }
+    }
+
+    fn week_distance(plan: &TrainingPlan, week: u32) -> f64 {
+        plan.days
+            .iter()
+            .filter(|d| d.week == week && d.kind != PlanSessionKind::Race)
+            .map(|d| d.distance_m)
+            .sum()
+    }
+
+    #[test]
+    fn test_phases() {
+        let phases = phases(16, 42_195.0);
+        assert_eq!(phases.len(), 16);
+        assert_eq!(phases[0], Phase::Base);
+        assert_eq!(&phases[13..], &[Phase::Taper; 3]);
+        // In order, with each block present
+        let mut seen = phases.clone();
+        seen.dedup();
+        assert_eq!(
+            seen,
+            vec![Phase::Base, Phase::Build, Phase::Peak, Phase::Taper]
+        );
+
+        let short = super::phases(3, 5000.0);
+        assert_eq!(short.len(), 3);
+        assert_eq!(short[2], Phase::Taper);
+        assert!(short.contains(&Phase::Peak));
+    }
+
+    #[test]
+    fn test_generate_plan() {
+        let request = request(12, 21_097.5, 5);
+        let plan = generate_plan(&request).unwrap();
+
+        assert_eq!(plan.days.len(), 12 * 7);
+        assert_eq!(plan.days[0].date, request.start_date);
+        let race = plan.days.last().unwrap();
+        assert_eq!(race.date, request.race_date);
+        assert_eq!(race.kind, PlanSessionKind::Race);
+        assert_eq!(race.distance_m, 21_097.5);
+        // Five runs a week plus race day
+        for week in 1..12 {
+            let runs = plan
+                .days
+                .iter()
+                .filter(|d| d.week == week && d.kind != PlanSessionKind::Rest)
+                .count();
+            assert_eq!(runs, 5, "week {week}");
+        }
+
+        // Base is easy running; build and peak have paced workouts
+        let workouts = |phase| {
+            plan.days
+                .iter()
+                .filter(|d| d.phase == phase && d.kind == PlanSessionKind::Workout)
+                .collect::<Vec<_>>()
+        };
+        assert!(workouts(Phase::Base).is_empty());
+        assert!(!workouts(Phase::Build).is_empty());
+        for day in workouts(Phase::Peak) {
+            let workout = day.workout.as_ref().unwrap();
+            workout.validate().unwrap();
+            assert!(workout.work.pace_sec_per_km.unwrap() < plan.zones.marathon_sec_per_km);
+            assert!(day.distance_m > 5000.0);
+        }
+    }
+
+    #[test]
+    fn test_volume_progression() {
+        let plan = generate_plan(&request(16, 42_195.0, 5)).unwrap();
+        let volumes: Vec<f64> = (1..=16).map(|w| week_distance(&plan, w)).collect();
+
+        // Easy runs are rounded, so allow a little over 10% growth on the
+        // biggest week so far
+        for week in 1..volumes.len() {
+            let before = volumes[..week].iter().cloned().fold(0.0, f64::max);
+            assert!(volumes[week] <= before * 1.15, "{volumes:?}");
+        }
+        assert!(volumes[3] < volumes[2], "week 4 steps back: {volumes:?}");
+        let peak = volumes.iter().cloned().fold(0.0, f64::max);
+        assert!(volumes[15] < peak * 0.6);
+        // Long runs stay capped
+        assert!(plan
+            .days
+            .iter()
+            .filter(|d| d.kind == PlanSessionKind::Long)
+            .all(|d| d.distance_m <= MAX_LONG_RUN_M));
+    }
+
+    #[test]
+    fn test_plan_starting_mid_week() {
+        let mut request = request(6, 10_000.0, 3);
+        request.start_date += Duration::days(3);
+        let plan = generate_plan(&request).unwrap();
+
+        assert_eq!(plan.days[0].date, request.start_date);
+        assert_eq!(plan.days.last().unwrap().date, request.race_date);
+        assert!(plan.days.iter().all(|d| d.week >= 1));
+        // The day before the race is a rest day
+        assert_eq!(plan.days[plan.days.len() - 2].kind, PlanSessionKind::Rest);
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        assert!(generate_plan(&request(2, 5000.0, 4)).is_err());
+        assert!(generate_plan(&request(8, 5000.0, 2)).is_err());
+        assert!(generate_plan(&request(8, 0.0, 4)).is_err());
+    }
+}